@@ -122,10 +122,10 @@ impl App<PongTextures> for PongGame {
             .set_texture(PongTextures::Paddle)
             .apply(player_gt)
             .set_z(0.);
-        let player_shape_outline = Shape::from_square_centered()
-            .set_color(Color::BLUE)
-            .apply(player_gt.inflate_fixed(0.02))
-            .set_z(0.3);
+        let player_shape_outline = player_shape
+            .stroke(0.01, StrokeAlignment::Outside, LineJoin::Miter)
+            .reset_texture()
+            .set_color(Color::BLUE);
 
         let enemy_gt = gt
             .translate(vec2(X_OFFSET, self.enemy_pos))
@@ -135,10 +135,10 @@ impl App<PongTextures> for PongGame {
             .set_texture(PongTextures::Paddle)
             .apply(enemy_gt)
             .set_z(0.);
-        let enemy_shape_outline = Shape::from_square_centered()
-            .set_color(Color::RED)
-            .apply(enemy_gt.inflate_fixed(0.02))
-            .set_z(0.3);
+        let enemy_shape_outline = enemy_shape
+            .stroke(0.01, StrokeAlignment::Outside, LineJoin::Miter)
+            .reset_texture()
+            .set_color(Color::RED);
 
         let ball_shape = Shape::from_circle(30)
             .apply(gt.translate(self.ball.pos).inflate(BALL_RADIUS))
@@ -0,0 +1,744 @@
+use glam::{vec2, Vec2};
+
+use crate::{Aabb, GTransform, Shape, Textures};
+
+mod broadphase;
+pub use broadphase::SpatialHash;
+
+/// Collision geometry, either a circle or a set of convex polygons
+#[derive(Clone, Debug, PartialEq)]
+pub enum Collider {
+    Circle {
+        center: Vec2,
+        radius: f32,
+    },
+    /// Convex pieces with counter-clockwise winding
+    Polygons(Vec<Vec<Vec2>>),
+}
+
+/// Overlap of two colliders
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contact {
+    /// Unit direction from the first collider towards the second, moving the second one by
+    /// `normal * depth` separates them
+    pub normal: Vec2,
+    pub depth: f32,
+    /// Up to two points where the colliders touch, each with its own penetration depth
+    pub points: Vec<(Vec2, f32)>,
+}
+
+/// First touch of a moving collider
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Impact {
+    /// Fraction of the movement in `[0, 1]` after which the colliders touch
+    pub time: f32,
+    /// Unit direction from the moving collider towards the other one
+    pub normal: Vec2,
+}
+
+/// Outward normal of the edge from `a` to `b` of a counter-clockwise polygon
+fn edge_normal(a: Vec2, b: Vec2) -> Vec2 {
+    vec2(b.y - a.y, a.x - b.x).normalize_or_zero()
+}
+
+fn edges(polygon: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    let n = polygon.len();
+    (0..n).map(move |i| (polygon[i], polygon[(i + 1) % n]))
+}
+
+fn is_convex(polygon: &[Vec2]) -> bool {
+    let n = polygon.len();
+    (0..n).all(|i| {
+        let (a, b, c) = (polygon[i], polygon[(i + 1) % n], polygon[(i + 2) % n]);
+        (b - a).perp_dot(c - b) >= -1e-6 * (b - a).length() * (c - b).length()
+    })
+}
+
+/// Joins the polygons over the edge they share if the result stays convex
+fn merge(points: &[Vec2], a: &[u32], b: &[u32]) -> Option<Vec<u32>> {
+    for i in 0..a.len() {
+        let (p, q) = (a[i], a[(i + 1) % a.len()]);
+        let Some(j) = (0..b.len()).find(|j| b[*j] == q && b[(j + 1) % b.len()] == p) else {
+            continue;
+        };
+
+        // From `q` around `a` to `p`, then around `b` back to `q`
+        let merged = (1..=a.len())
+            .map(|k| a[(i + k) % a.len()])
+            .chain((2..b.len()).map(|k| b[(j + k) % b.len()]))
+            .collect::<Vec<_>>();
+        let polygon = merged
+            .iter()
+            .map(|i| points[*i as usize])
+            .collect::<Vec<_>>();
+        let repeats = (1..merged.len()).any(|k| merged[k..].contains(&merged[k - 1]));
+        return (!repeats && is_convex(&polygon)).then_some(merged);
+    }
+    None
+}
+
+/// Greedily merges counter-clockwise triangles into convex polygons (Hertel-Mehlhorn)
+fn convex_decomposition(points: &[Vec2], indices: &[u32]) -> Vec<Vec<Vec2>> {
+    let mut polygons = indices
+        .chunks_exact(3)
+        .map(|triangle| triangle.to_vec())
+        .collect::<Vec<_>>();
+
+    let mut i = 0;
+    while i < polygons.len() {
+        let merged = (i + 1..polygons.len())
+            .find_map(|j| merge(points, &polygons[i], &polygons[j]).map(|polygon| (j, polygon)));
+        match merged {
+            Some((j, polygon)) => {
+                polygons[i] = polygon;
+                polygons.swap_remove(j);
+            }
+            None => i += 1,
+        }
+    }
+
+    polygons
+        .into_iter()
+        .map(|polygon| polygon.iter().map(|i| points[*i as usize]).collect())
+        .collect()
+}
+
+/// Largest distance of `b` in front of an edge of `a`, together with that edge
+fn max_separation(a: &[Vec2], b: &[Vec2]) -> (f32, usize) {
+    edges(a)
+        .enumerate()
+        .map(|(i, (p, q))| {
+            let normal = edge_normal(p, q);
+            let separation = b
+                .iter()
+                .map(|v| normal.dot(*v - p))
+                .fold(f32::INFINITY, f32::min);
+            (separation, i)
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .unwrap_or((f32::INFINITY, 0))
+}
+
+/// Part of the segment in front of the line `direction · p = offset`
+fn clip(segment: [Vec2; 2], direction: Vec2, offset: f32) -> Option<[Vec2; 2]> {
+    let [d0, d1] = segment.map(|p| direction.dot(p) - offset);
+    match (d0 >= 0., d1 >= 0.) {
+        (true, true) => Some(segment),
+        (false, false) => None,
+        _ => {
+            let crossing = segment[0] + (segment[1] - segment[0]) * (d0 / (d0 - d1));
+            Some(match d0 >= 0. {
+                true => [segment[0], crossing],
+                false => [crossing, segment[1]],
+            })
+        }
+    }
+}
+
+/// Separating axis test, contact points are found by clipping the most anti-parallel edge of
+/// one polygon against the edge of the other one closest to it
+fn polygon_polygon(a: &[Vec2], b: &[Vec2]) -> Option<Contact> {
+    let (separation_a, edge_a) = max_separation(a, b);
+    if separation_a > 0. {
+        return None;
+    }
+    let (separation_b, edge_b) = max_separation(b, a);
+    if separation_b > 0. {
+        return None;
+    }
+
+    // Prefer the first polygon as reference, so that manifolds don't flip between frames
+    let (reference, incident, edge, flip) = match separation_b > 0.98 * separation_a {
+        true => (b, a, edge_b, true),
+        false => (a, b, edge_a, false),
+    };
+    let (v1, v2) = (reference[edge], reference[(edge + 1) % reference.len()]);
+    let normal = edge_normal(v1, v2);
+    let tangent = (v2 - v1).normalize_or_zero();
+
+    let i = edges(incident)
+        .map(|(p, q)| edge_normal(p, q).dot(normal))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))?
+        .0;
+    let segment = [incident[i], incident[(i + 1) % incident.len()]];
+    let segment = clip(segment, tangent, tangent.dot(v1))?;
+    let segment = clip(segment, -tangent, -tangent.dot(v2))?;
+
+    let (mut depth, mut points) = (0f32, vec![]);
+    for p in segment {
+        let separation = normal.dot(p - v1);
+        if separation <= 0. {
+            depth = depth.max(-separation);
+            points.push((p, -separation));
+        }
+    }
+    points.dedup_by(|a, b| a.0 == b.0);
+    (!points.is_empty()).then(|| Contact {
+        normal: match flip {
+            true => -normal,
+            false => normal,
+        },
+        depth,
+        points,
+    })
+}
+
+/// Normal from the circle towards the polygon
+fn circle_polygon(center: Vec2, radius: f32, polygon: &[Vec2]) -> Option<Contact> {
+    let (separation, edge) = edges(polygon)
+        .map(|(p, q)| edge_normal(p, q).dot(center - p))
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, separation)| (separation, i))?;
+    if separation > radius {
+        return None;
+    }
+
+    let (a, b) = (polygon[edge], polygon[(edge + 1) % polygon.len()]);
+    let normal = edge_normal(a, b);
+    if separation <= 0. {
+        return Some(Contact {
+            normal: -normal,
+            depth: radius - separation,
+            points: vec![(center - normal * separation, radius - separation)],
+        });
+    }
+
+    // Outside of the polygon, the closest point may also be a corner of the edge
+    let edge = b - a;
+    let closest = a + edge * ((center - a).dot(edge) / edge.length_squared()).clamp(0., 1.);
+    let offset = center - closest;
+    let distance = offset.length();
+    (distance <= radius).then(|| Contact {
+        normal: match distance > 0. {
+            true => -offset / distance,
+            false => -normal,
+        },
+        depth: radius - distance,
+        points: vec![(closest, radius - distance)],
+    })
+}
+
+fn circle_circle(a: Vec2, radius_a: f32, b: Vec2, radius_b: f32) -> Option<Contact> {
+    let offset = b - a;
+    let distance = offset.length();
+    if distance > radius_a + radius_b {
+        return None;
+    }
+    let normal = match distance > 0. {
+        true => offset / distance,
+        false => Vec2::Y,
+    };
+    let depth = radius_a + radius_b - distance;
+    Some(Contact {
+        normal,
+        depth,
+        points: vec![(a + normal * (radius_a - depth / 2.), depth)],
+    })
+}
+
+fn flipped(contact: Contact) -> Contact {
+    Contact {
+        normal: -contact.normal,
+        ..contact
+    }
+}
+
+/// Earliest time in `[0, 1]` at which the point moving along `velocity` from `start` is
+/// `radius` away from `point`
+fn sweep_point_circle(start: Vec2, velocity: Vec2, point: Vec2, radius: f32) -> Option<f32> {
+    let offset = start - point;
+    let (a, b, c) = (
+        velocity.length_squared(),
+        2. * offset.dot(velocity),
+        offset.length_squared() - radius * radius,
+    );
+    let discriminant = b * b - 4. * a * c;
+    if a == 0. || discriminant < 0. {
+        return None;
+    }
+    let time = (-b - discriminant.sqrt()) / (2. * a);
+    (0. ..=1.).contains(&time).then_some(time)
+}
+
+/// Moving circle against a static polygon, normal from the circle towards the polygon
+fn sweep_circle_polygon(
+    center: Vec2,
+    radius: f32,
+    velocity: Vec2,
+    polygon: &[Vec2],
+) -> Option<Impact> {
+    if let Some(contact) = circle_polygon(center, radius, polygon) {
+        return Some(Impact {
+            time: 0.,
+            normal: contact.normal,
+        });
+    }
+
+    // Against the polygon grown by the radius: its edges moved outwards and circles at the
+    // corners
+    let faces = edges(polygon).filter_map(|(a, b)| {
+        let normal = edge_normal(a, b);
+        let speed = normal.dot(velocity);
+        if speed >= 0. {
+            return None;
+        }
+        let time = (radius - normal.dot(center - a)) / speed;
+        let along = (center + velocity * time - a).dot(b - a) / (b - a).length_squared();
+        ((0. ..=1.).contains(&time) && (0. ..=1.).contains(&along)).then_some(Impact {
+            time,
+            normal: -normal,
+        })
+    });
+    let corners = polygon.iter().filter_map(|corner| {
+        let time = sweep_point_circle(center, velocity, *corner, radius)?;
+        Some(Impact {
+            time,
+            normal: (*corner - (center + velocity * time)).normalize_or_zero(),
+        })
+    });
+    faces
+        .chain(corners)
+        .min_by(|a, b| a.time.total_cmp(&b.time))
+}
+
+/// Separating axis test over the movement, exact for convex polygons
+fn sweep_polygon_polygon(a: &[Vec2], velocity: Vec2, b: &[Vec2]) -> Option<Impact> {
+    let project = |polygon: &[Vec2], axis: Vec2| {
+        polygon
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), p| {
+                (min.min(axis.dot(*p)), max.max(axis.dot(*p)))
+            })
+    };
+
+    let (mut enter, mut exit) = (f32::NEG_INFINITY, f32::INFINITY);
+    let mut normal = Vec2::ZERO;
+    for axis in edges(a).chain(edges(b)).map(|(p, q)| edge_normal(p, q)) {
+        let ((min_a, max_a), (min_b, max_b)) = (project(a, axis), project(b, axis));
+        let speed = axis.dot(velocity);
+
+        let (axis_enter, axis_exit, axis_normal) = if max_a < min_b {
+            if speed <= 0. {
+                return None;
+            }
+            ((min_b - max_a) / speed, (max_b - min_a) / speed, axis)
+        } else if max_b < min_a {
+            if speed >= 0. {
+                return None;
+            }
+            ((max_b - min_a) / speed, (min_b - max_a) / speed, -axis)
+        } else {
+            let axis_exit = match speed {
+                speed if speed > 0. => (max_b - min_a) / speed,
+                speed if speed < 0. => (min_b - max_a) / speed,
+                _ => f32::INFINITY,
+            };
+            (f32::NEG_INFINITY, axis_exit, axis)
+        };
+
+        if axis_enter > enter {
+            enter = axis_enter;
+            normal = axis_normal;
+        }
+        exit = exit.min(axis_exit);
+        if enter > exit || enter > 1. {
+            return None;
+        }
+    }
+
+    match enter >= 0. {
+        true => Some(Impact {
+            time: enter,
+            normal,
+        }),
+        false => polygon_polygon(a, b).map(|contact| Impact {
+            time: 0.,
+            normal: contact.normal,
+        }),
+    }
+}
+
+impl Collider {
+    pub fn circle(center: Vec2, radius: f32) -> Self {
+        Self::Circle { center, radius }
+    }
+
+    /// Convex polygon with the given corners in either winding order
+    pub fn convex(mut points: Vec<Vec2>) -> Self {
+        points.dedup();
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if edges(&points).map(|(a, b)| a.perp_dot(b)).sum::<f32>() < 0. {
+            points.reverse();
+        }
+        Self::Polygons(vec![points])
+    }
+
+    /// Filled area of the shape, holes included. Concave shapes are decomposed into convex
+    /// pieces, shapes which can't be triangulated have no pieces.
+    pub fn from_shape<T: Textures>(shape: &Shape<T>) -> Self {
+        let points = shape
+            .points
+            .iter()
+            .chain(shape.holes.iter().flatten())
+            .map(|(p, _)| *p)
+            .collect::<Vec<_>>();
+
+        if shape.indices().is_none() && shape.holes.is_empty() {
+            let convex = Self::convex(points.clone());
+            if matches!(&convex, Self::Polygons(polygons) if is_convex(&polygons[0])) {
+                return convex;
+            }
+        }
+
+        let mut indices = shape.triangulate().unwrap_or_default();
+        // Triangles of meshes may wind either way
+        for triangle in indices.chunks_exact_mut(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| points[triangle[i] as usize]);
+            if (b - a).perp_dot(c - a) < 0. {
+                triangle.swap(1, 2);
+            }
+        }
+        Self::Polygons(convex_decomposition(&points, &indices))
+    }
+
+    /// Circles are scaled by the larger scale factor and stay round
+    pub fn apply(self, gtransform: GTransform) -> Self {
+        match self {
+            Self::Circle { center, radius } => Self::Circle {
+                center: gtransform.transform(center),
+                radius: radius * gtransform.scale.abs().max_element(),
+            },
+            Self::Polygons(polygons) => Self::Polygons(
+                polygons
+                    .into_iter()
+                    .map(|polygon| {
+                        let mut polygon = polygon
+                            .into_iter()
+                            .map(|p| gtransform.transform(p))
+                            .collect::<Vec<_>>();
+                        // Mirroring flips the winding
+                        if gtransform.scale.x * gtransform.scale.y < 0. {
+                            polygon.reverse();
+                        }
+                        polygon
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// `None` for colliders without pieces
+    pub fn aabb(&self) -> Option<Aabb> {
+        match self {
+            Self::Circle { center, radius } => Some(Aabb::new(
+                *center - Vec2::splat(*radius),
+                *center + Vec2::splat(*radius),
+            )),
+            Self::Polygons(polygons) => Aabb::from_points(polygons.iter().flatten().copied()),
+        }
+    }
+
+    /// Circle and convex pieces this collider consists of
+    fn pieces(&self) -> Vec<Piece<'_>> {
+        match self {
+            Self::Circle { center, radius } => vec![Piece::Circle(*center, *radius)],
+            Self::Polygons(polygons) => polygons
+                .iter()
+                .filter(|polygon| polygon.len() >= 3)
+                .map(|polygon| Piece::Polygon(polygon))
+                .collect(),
+        }
+    }
+
+    /// Contacts between every pair of overlapping pieces
+    pub fn contacts(&self, other: &Collider) -> Vec<Contact> {
+        let pieces = other.pieces();
+        self.pieces()
+            .iter()
+            .flat_map(|a| pieces.iter().filter_map(|b| a.collide(b)))
+            .collect()
+    }
+
+    /// Deepest contact of [`Collider::contacts`]
+    pub fn collide(&self, other: &Collider) -> Option<Contact> {
+        self.contacts(other)
+            .into_iter()
+            .max_by(|a, b| a.depth.total_cmp(&b.depth))
+    }
+
+    /// Continuous test for this collider moving by `velocity` while `other` stands still, so
+    /// fast objects don't pass through thin ones. Pass the relative velocity if both move.
+    pub fn time_of_impact(&self, velocity: Vec2, other: &Collider) -> Option<Impact> {
+        let pieces = other.pieces();
+        self.pieces()
+            .iter()
+            .flat_map(|a| pieces.iter().filter_map(|b| a.sweep(velocity, b)))
+            .min_by(|a, b| a.time.total_cmp(&b.time))
+    }
+}
+
+enum Piece<'a> {
+    Circle(Vec2, f32),
+    Polygon(&'a [Vec2]),
+}
+
+impl Piece<'_> {
+    fn collide(&self, other: &Piece) -> Option<Contact> {
+        match (self, other) {
+            (Piece::Circle(a, radius_a), Piece::Circle(b, radius_b)) => {
+                circle_circle(*a, *radius_a, *b, *radius_b)
+            }
+            (Piece::Circle(center, radius), Piece::Polygon(polygon)) => {
+                circle_polygon(*center, *radius, polygon)
+            }
+            (Piece::Polygon(polygon), Piece::Circle(center, radius)) => {
+                circle_polygon(*center, *radius, polygon).map(flipped)
+            }
+            (Piece::Polygon(a), Piece::Polygon(b)) => polygon_polygon(a, b),
+        }
+    }
+
+    fn sweep(&self, velocity: Vec2, other: &Piece) -> Option<Impact> {
+        match (self, other) {
+            (Piece::Circle(a, radius_a), Piece::Circle(b, radius_b)) => {
+                if let Some(contact) = circle_circle(*a, *radius_a, *b, *radius_b) {
+                    return Some(Impact {
+                        time: 0.,
+                        normal: contact.normal,
+                    });
+                }
+                let time = sweep_point_circle(*a, velocity, *b, radius_a + radius_b)?;
+                Some(Impact {
+                    time,
+                    normal: (*b - (*a + velocity * time)).normalize_or_zero(),
+                })
+            }
+            (Piece::Circle(center, radius), Piece::Polygon(polygon)) => {
+                sweep_circle_polygon(*center, *radius, velocity, polygon)
+            }
+            (Piece::Polygon(polygon), Piece::Circle(center, radius)) => {
+                sweep_circle_polygon(*center, *radius, -velocity, polygon).map(|impact| Impact {
+                    normal: -impact.normal,
+                    ..impact
+                })
+            }
+            (Piece::Polygon(a), Piece::Polygon(b)) => sweep_polygon_polygon(a, velocity, b),
+        }
+    }
+}
+
+impl<T: Textures> Shape<T> {
+    /// Deepest overlap with `other`, see [`Collider::from_shape`] for repeated tests
+    pub fn collide(&self, other: &Shape<T>) -> Option<Contact> {
+        Collider::from_shape(self).collide(&Collider::from_shape(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::TestTextures;
+
+    fn rect(min: Vec2, max: Vec2) -> Collider {
+        Collider::convex(vec![min, vec2(max.x, min.y), max, vec2(min.x, max.y)])
+    }
+
+    fn assert_contact(contact: Option<Contact>, normal: Vec2, depth: f32) -> Contact {
+        let contact = contact.expect("expected a contact");
+        assert!(contact.normal.abs_diff_eq(normal, 1e-4), "{contact:?}");
+        assert!((contact.depth - depth).abs() < 1e-4, "{contact:?}");
+        contact
+    }
+
+    fn assert_impact(impact: Option<Impact>, time: f32, normal: Vec2) {
+        let impact = impact.expect("expected an impact");
+        assert!((impact.time - time).abs() < 1e-4, "{impact:?}");
+        assert!(impact.normal.abs_diff_eq(normal, 1e-3), "{impact:?}");
+    }
+
+    #[test]
+    fn circle_circle_contacts() {
+        let a = Collider::circle(Vec2::ZERO, 1.);
+        let contact = assert_contact(
+            a.collide(&Collider::circle(vec2(1.5, 0.), 1.)),
+            Vec2::X,
+            0.5,
+        );
+        assert_eq!(contact.points, vec![(vec2(0.75, 0.), 0.5)]);
+        assert_contact(
+            Collider::circle(vec2(1.5, 0.), 1.).collide(&a),
+            -Vec2::X,
+            0.5,
+        );
+        // Concentric circles are pushed apart along y
+        assert_contact(a.collide(&Collider::circle(Vec2::ZERO, 0.5)), Vec2::Y, 1.5);
+        assert!(a.collide(&Collider::circle(vec2(3., 0.), 1.)).is_none());
+    }
+
+    #[test]
+    fn circle_polygon_contacts() {
+        let wall = rect(vec2(0.5, -1.), vec2(2.5, 1.));
+        let circle = Collider::circle(Vec2::ZERO, 1.);
+        let contact = assert_contact(circle.collide(&wall), Vec2::X, 0.5);
+        assert_eq!(contact.points, vec![(vec2(0.5, 0.), 0.5)]);
+        assert_contact(wall.collide(&circle), -Vec2::X, 0.5);
+
+        // Centers inside the polygon are pushed out through the closest edge
+        let inside = Collider::circle(vec2(1., 0.), 0.5);
+        assert_contact(
+            inside.collide(&rect(vec2(0., -2.), vec2(4., 2.))),
+            Vec2::X,
+            1.5,
+        );
+
+        // Near a corner the normal points at it
+        let corner = Collider::circle(vec2(3., 1.5), 1.);
+        let normal = vec2(-1., -1.).normalize();
+        let depth = 1. - 0.5f32.hypot(0.5);
+        assert_contact(corner.collide(&wall), normal, depth);
+        assert!(Collider::circle(vec2(3.5, 2.), 1.).collide(&wall).is_none());
+    }
+
+    #[test]
+    fn polygon_polygon_contacts() {
+        let a = rect(Vec2::ZERO, Vec2::splat(2.));
+        let b = rect(vec2(1.5, 0.5), vec2(3.5, 1.5));
+        let contact = assert_contact(a.collide(&b), Vec2::X, 0.5);
+        let mut points = contact.points.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+        points.sort_by(|a, b| a.y.total_cmp(&b.y));
+        assert_eq!(points, vec![vec2(1.5, 0.5), vec2(1.5, 1.5)]);
+        assert!(contact
+            .points
+            .iter()
+            .all(|(_, depth)| (depth - 0.5).abs() < 1e-5));
+
+        assert_contact(b.collide(&a), -Vec2::X, 0.5);
+        assert!(a.collide(&rect(vec2(2.5, 0.), vec2(3., 1.))).is_none());
+        // Diagonal neighbours overlap in their bounds but not along the edge normals
+        let diamond = Collider::convex(vec![
+            vec2(3., 2.5),
+            vec2(3.5, 3.),
+            vec2(3., 3.5),
+            vec2(2.5, 3.),
+        ]);
+        assert!(rect(Vec2::ZERO, Vec2::splat(2.6))
+            .collide(&diamond)
+            .is_none());
+    }
+
+    #[test]
+    fn concave_shapes() {
+        let l = Shape::<TestTextures>::new(vec![
+            vec2(0., 0.),
+            vec2(2., 0.),
+            vec2(2., 1.),
+            vec2(1., 1.),
+            vec2(1., 2.),
+            vec2(0., 2.),
+        ]);
+        let Collider::Polygons(pieces) = Collider::from_shape(&l) else {
+            panic!("expected polygons");
+        };
+        assert_eq!(pieces.len(), 2);
+        assert!(pieces.iter().all(|piece| is_convex(piece)));
+
+        let collider = Collider::Polygons(pieces);
+        assert!(collider
+            .collide(&rect(vec2(1.2, 1.2), vec2(1.8, 1.8)))
+            .is_none());
+        assert_contact(
+            collider.collide(&rect(vec2(1.8, 0.2), vec2(3., 0.8))),
+            Vec2::X,
+            0.2,
+        );
+
+        // Mirroring keeps the pieces counter-clockwise
+        let mirrored = collider.apply(GTransform::default().set_scale(vec2(-1., 1.)));
+        let Collider::Polygons(pieces) = &mirrored else {
+            panic!("expected polygons");
+        };
+        assert!(pieces.iter().all(|piece| is_convex(piece)));
+        assert_contact(
+            mirrored.collide(&rect(vec2(-3., 0.2), vec2(-1.8, 0.8))),
+            -Vec2::X,
+            0.2,
+        );
+
+        // Clockwise mesh triangles are turned around
+        let mesh = Shape::<TestTextures>::from_mesh(
+            vec![vec2(0., 0.), vec2(2., 0.), vec2(2., 1.), vec2(0., 1.)],
+            vec![0, 2, 1, 0, 3, 2],
+        );
+        let collider = Collider::from_shape(&mesh);
+        let Collider::Polygons(pieces) = &collider else {
+            panic!("expected polygons");
+        };
+        assert_eq!(pieces.len(), 1);
+        assert!(is_convex(&pieces[0]));
+        assert_contact(
+            collider.collide(&rect(vec2(1.8, 0.2), vec2(3., 0.8))),
+            Vec2::X,
+            0.2,
+        );
+    }
+
+    #[test]
+    fn circle_circle_sweeps() {
+        let a = Collider::circle(Vec2::ZERO, 1.);
+        let b = Collider::circle(vec2(5., 0.), 1.);
+        assert_impact(a.time_of_impact(vec2(10., 0.), &b), 0.3, Vec2::X);
+        assert!(a.time_of_impact(vec2(0., 10.), &b).is_none());
+        assert!(a.time_of_impact(vec2(2., 0.), &b).is_none());
+        // Overlapping colliders touch right away
+        assert_impact(
+            a.time_of_impact(Vec2::X, &Collider::circle(Vec2::X, 1.)),
+            0.,
+            Vec2::X,
+        );
+    }
+
+    #[test]
+    fn circle_polygon_sweeps() {
+        let wall = rect(vec2(4., -1.), vec2(6., 1.));
+        let circle = Collider::circle(Vec2::ZERO, 1.);
+        assert_impact(circle.time_of_impact(vec2(10., 0.), &wall), 0.3, Vec2::X);
+
+        // Passing by the corner
+        let high = Collider::circle(vec2(0., 1.5), 1.);
+        let time = (4. - 0.75f32.sqrt()) / 10.;
+        assert_impact(
+            high.time_of_impact(vec2(10., 0.), &wall),
+            time,
+            vec2(0.75f32.sqrt(), -0.5),
+        );
+        assert!(Collider::circle(vec2(0., 2.5), 1.)
+            .time_of_impact(vec2(10., 0.), &wall)
+            .is_none());
+
+        // The polygon moving towards the circle
+        let ball = Collider::circle(vec2(8., 0.), 1.);
+        assert_impact(wall.time_of_impact(vec2(10., 0.), &ball), 0.1, Vec2::X);
+        assert!(wall.time_of_impact(vec2(-10., 0.), &ball).is_none());
+    }
+
+    #[test]
+    fn polygon_polygon_sweeps() {
+        let a = rect(Vec2::ZERO, Vec2::splat(2.));
+        let b = rect(vec2(5., 0.), vec2(7., 2.));
+        assert_impact(a.time_of_impact(vec2(10., 0.), &b), 0.3, Vec2::X);
+        assert!(a.time_of_impact(vec2(0., 10.), &b).is_none());
+        assert!(a.time_of_impact(vec2(-10., 0.), &b).is_none());
+
+        // Fast objects don't tunnel through thin walls
+        let bullet = rect(Vec2::ZERO, Vec2::splat(0.1));
+        let wall = rect(vec2(50., -5.), vec2(50.1, 5.));
+        assert_impact(bullet.time_of_impact(vec2(100., 0.), &wall), 0.499, Vec2::X);
+
+        let overlapping = rect(vec2(1.5, 0.5), vec2(3.5, 1.5));
+        assert_impact(a.time_of_impact(Vec2::Y, &overlapping), 0., Vec2::X);
+    }
+}
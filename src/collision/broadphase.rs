@@ -0,0 +1,393 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use glam::{IVec2, Vec2};
+
+use crate::Aabb;
+
+/// Uniform grid of buckets holding the keys of the boxes overlapping each cell. Works best
+/// with a cell size around the size of a typical object.
+#[derive(Clone, Debug)]
+pub struct SpatialHash<K> {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<K>>,
+    entries: HashMap<K, Aabb>,
+}
+
+/// Distance from `point` to the box, zero inside of it
+fn distance_to(aabb: Aabb, point: Vec2) -> f32 {
+    (aabb.min - point)
+        .max(point - aabb.max)
+        .max(Vec2::ZERO)
+        .length()
+}
+
+/// Distances along the ray at which it enters and leaves the box, entering at zero if it
+/// starts inside
+fn ray_interval(aabb: Aabb, origin: Vec2, direction: Vec2) -> Option<(f32, f32)> {
+    let inverse = direction.recip();
+    let (t0, t1) = ((aabb.min - origin) * inverse, (aabb.max - origin) * inverse);
+    // Axes the ray runs parallel to don't limit the interval, they divide by zero
+    let parallel = direction.cmpeq(Vec2::ZERO);
+    let enter = Vec2::select(parallel, Vec2::splat(f32::NEG_INFINITY), t0.min(t1));
+    let exit = Vec2::select(parallel, Vec2::splat(f32::INFINITY), t0.max(t1));
+    let (enter, exit) = (enter.max_element().max(0.), exit.min_element());
+    let inside = |axis: usize| origin[axis] >= aabb.min[axis] && origin[axis] <= aabb.max[axis];
+    let parallel_miss = (0..2).any(|axis| direction[axis] == 0. && !inside(axis));
+    (!parallel_miss && enter <= exit).then_some((enter, exit))
+}
+
+impl<K: Copy + Eq + Hash> SpatialHash<K> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, point: Vec2) -> IVec2 {
+        (point / self.cell_size).floor().as_ivec2()
+    }
+
+    fn cells(&self, aabb: Aabb) -> impl Iterator<Item = IVec2> {
+        let (min, max) = (self.cell(aabb.min), self.cell(aabb.max));
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+    }
+
+    pub fn get(&self, key: K) -> Option<Aabb> {
+        self.entries.get(&key).copied()
+    }
+
+    /// Adds the box or moves it if the key is already present
+    pub fn insert(&mut self, key: K, aabb: Aabb) {
+        if let Some(old) = self.entries.get(&key).copied() {
+            if self.cell(old.min) == self.cell(aabb.min)
+                && self.cell(old.max) == self.cell(aabb.max)
+            {
+                self.entries.insert(key, aabb);
+                return;
+            }
+            self.remove(key);
+        }
+
+        for cell in self.cells(aabb).collect::<Vec<_>>() {
+            self.cells.entry(cell).or_default().push(key);
+        }
+        self.entries.insert(key, aabb);
+    }
+
+    /// Same as [`SpatialHash::insert`]
+    pub fn update(&mut self, key: K, aabb: Aabb) {
+        self.insert(key, aabb);
+    }
+
+    pub fn remove(&mut self, key: K) -> Option<Aabb> {
+        let aabb = self.entries.remove(&key)?;
+        for cell in self.cells(aabb).collect::<Vec<_>>() {
+            if let Some(keys) = self.cells.get_mut(&cell) {
+                keys.retain(|k| *k != key);
+                if keys.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        Some(aabb)
+    }
+
+    /// Keys whose boxes overlap `aabb`
+    pub fn query_aabb(&self, aabb: Aabb) -> Vec<K> {
+        let mut found = HashSet::new();
+        for cell in self.cells(aabb) {
+            for key in self.cells.get(&cell).into_iter().flatten() {
+                if self.entries[key].intersects(aabb) {
+                    found.insert(*key);
+                }
+            }
+        }
+        found.into_iter().collect()
+    }
+
+    /// Keys whose boxes contain `point`, like everything under the cursor
+    pub fn query_point(&self, point: Vec2) -> Vec<K> {
+        self.cells
+            .get(&self.cell(point))
+            .into_iter()
+            .flatten()
+            .filter(|key| self.entries[*key].contains_point(point))
+            .copied()
+            .collect()
+    }
+
+    /// Keys whose boxes the ray from `origin` along `direction` enters within `max_distance`,
+    /// sorted by that distance in multiples of `direction`
+    pub fn query_ray(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Vec<(K, f32)> {
+        let bounds = self.entries.values().copied().reduce(Aabb::union);
+        let Some((enter, exit)) = bounds.and_then(|bounds| ray_interval(bounds, origin, direction))
+        else {
+            return vec![];
+        };
+        if enter > max_distance {
+            return vec![];
+        }
+        let max_distance = max_distance.min(exit);
+        let mut hits = HashMap::new();
+
+        // Walk the cells along the ray from where it enters the boxes, one cell border at a
+        // time. Axes the ray runs parallel to are never stepped along, instead of dividing by
+        // zero.
+        let mut cell = self.cell(origin + direction * enter);
+        let end = self.cell(origin + direction * max_distance);
+        let parallel = direction.cmpeq(Vec2::ZERO);
+        let step = Vec2::select(parallel, Vec2::ZERO, direction.signum()).as_ivec2();
+        let border = (cell + step.max(IVec2::ZERO)).as_vec2() * self.cell_size;
+        let mut t = Vec2::select(
+            parallel,
+            Vec2::splat(f32::INFINITY),
+            (border - origin) / direction,
+        );
+        let delta = Vec2::select(
+            parallel,
+            Vec2::splat(f32::INFINITY),
+            (self.cell_size / direction).abs(),
+        );
+        let cells_crossed = (end - cell).abs();
+
+        for _ in 0..=cells_crossed.x + cells_crossed.y {
+            for key in self.cells.get(&cell).into_iter().flatten() {
+                if let Some((distance, _)) = ray_interval(self.entries[key], origin, direction) {
+                    if distance <= max_distance {
+                        hits.insert(*key, distance);
+                    }
+                }
+            }
+            if t.x < t.y {
+                cell.x += step.x;
+                t.x += delta.x;
+            } else {
+                cell.y += step.y;
+                t.y += delta.y;
+            }
+        }
+
+        let mut hits = hits.into_iter().collect::<Vec<_>>();
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1));
+        hits
+    }
+
+    /// Key of the box closest to `point` together with the distance to it, searching rings of
+    /// cells around the point until no closer box can be found
+    pub fn nearest(&self, point: Vec2) -> Option<(K, f32)> {
+        let center = self.cell(point);
+        let mut best: Option<(K, f32)> = None;
+        let mut searched = 0;
+
+        for ring in 0i32.. {
+            // Boxes in further rings are at least this far away
+            let ring_distance = (ring - 1).max(0) as f32 * self.cell_size;
+            if best.is_some_and(|(_, distance)| distance <= ring_distance) {
+                break;
+            }
+            // Far away from all boxes checking each of them is cheaper than searching more
+            // empty cells
+            if searched > self.cells.len() {
+                return self
+                    .entries
+                    .iter()
+                    .map(|(key, aabb)| (*key, distance_to(*aabb, point)))
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+            }
+
+            let ring_cells = (-ring..=ring).flat_map(|y| {
+                (-ring..=ring)
+                    .filter(move |x| x.abs() == ring || y.abs() == ring)
+                    .map(move |x| center + IVec2::new(x, y))
+            });
+            for cell in ring_cells {
+                searched += 1;
+                for key in self.cells.get(&cell).into_iter().flatten() {
+                    let distance = distance_to(self.entries[key], point);
+                    if !best.is_some_and(|(_, best)| best <= distance) {
+                        best = Some((*key, distance));
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    /// Every pair of keys whose boxes overlap, each reported once
+    pub fn pairs(&self) -> Vec<(K, K)> {
+        let mut pairs = HashSet::new();
+        for keys in self.cells.values() {
+            for (i, a) in keys.iter().enumerate() {
+                for b in &keys[i + 1..] {
+                    if !pairs.contains(&(*b, *a)) && self.entries[a].intersects(self.entries[b]) {
+                        pairs.insert((*a, *b));
+                    }
+                }
+            }
+        }
+        pairs.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use super::*;
+
+    fn square(min: Vec2, size: f32) -> Aabb {
+        Aabb::new(min, min + Vec2::splat(size))
+    }
+
+    fn sorted(mut keys: Vec<u32>) -> Vec<u32> {
+        keys.sort_unstable();
+        keys
+    }
+
+    fn grid() -> SpatialHash<u32> {
+        let mut hash = SpatialHash::new(1.);
+        hash.insert(0, square(Vec2::ZERO, 0.5));
+        hash.insert(1, square(vec2(2., 0.), 0.5));
+        hash.insert(2, square(vec2(0., 2.), 2.5));
+        hash.insert(3, square(vec2(-3.5, -0.25), 0.5));
+        hash
+    }
+
+    #[test]
+    fn insert_update_remove() {
+        let mut hash = grid();
+        assert_eq!(hash.len(), 4);
+        assert_eq!(hash.get(1), Some(square(vec2(2., 0.), 0.5)));
+
+        // Within the same cells and into other ones
+        hash.update(1, square(vec2(2.25, 0.25), 0.5));
+        assert_eq!(hash.query_point(vec2(2.6, 0.6)), vec![1]);
+        hash.update(1, square(vec2(10., 10.), 0.5));
+        assert!(hash.query_point(vec2(2.6, 0.6)).is_empty());
+        assert_eq!(hash.query_point(vec2(10.25, 10.25)), vec![1]);
+
+        assert_eq!(hash.remove(1), Some(square(vec2(10., 10.), 0.5)));
+        assert_eq!(hash.remove(1), None);
+        assert!(hash.query_point(vec2(10.25, 10.25)).is_empty());
+        assert_eq!(hash.len(), 3);
+        // Removing clears the cells the box covered
+        assert!(!hash.cells.values().flatten().any(|key| *key == 1));
+
+        hash.clear();
+        assert!(hash.is_empty() && hash.cells.is_empty());
+    }
+
+    #[test]
+    fn regions() {
+        let hash = grid();
+        let region = Aabb::new(vec2(-0.5, -0.5), vec2(2.1, 2.1));
+        assert_eq!(sorted(hash.query_aabb(region)), vec![0, 1, 2]);
+        assert!(hash.query_aabb(square(vec2(5., 5.), 1.)).is_empty());
+        assert_eq!(hash.query_point(vec2(1., 3.)), vec![2]);
+        // Only the box itself, not everything sharing its cell
+        assert!(hash.query_point(vec2(0.75, 0.75)).is_empty());
+        assert_eq!(hash.pairs(), vec![]);
+
+        let mut hash = hash;
+        hash.insert(4, square(vec2(0.25, 0.25), 2.));
+        let pairs = hash.pairs().into_iter().map(|(a, b)| (a.min(b), a.max(b)));
+        let mut pairs = pairs.collect::<Vec<_>>();
+        pairs.sort_unstable();
+        assert_eq!(pairs, vec![(0, 4), (1, 4), (2, 4)]);
+    }
+
+    #[test]
+    fn rays() {
+        let hash = grid();
+        let hits = hash.query_ray(vec2(-5., 0.25), Vec2::X, 100.);
+        assert_eq!(hits, vec![(3, 1.5), (0, 5.), (1, 7.)]);
+        assert_eq!(
+            hash.query_ray(vec2(-5., 0.25), Vec2::X, 6.),
+            vec![(3, 1.5), (0, 5.)]
+        );
+        assert_eq!(hash.query_ray(vec2(5., 0.25), -Vec2::X, 100.)[0], (1, 2.5));
+
+        // Vertical rays, also along a cell border and with a negative zero component
+        assert_eq!(
+            hash.query_ray(vec2(0.25, -5.), Vec2::Y, 100.),
+            vec![(0, 5.), (2, 7.)]
+        );
+        assert_eq!(
+            hash.query_ray(vec2(0., -5.), Vec2::Y, 100.),
+            vec![(0, 5.), (2, 7.)]
+        );
+        assert_eq!(
+            hash.query_ray(vec2(0., 10.), vec2(-0., -2.), 100.),
+            vec![(2, 2.75), (0, 4.75)]
+        );
+
+        let diagonal = hash.query_ray(vec2(-1., -1.), Vec2::ONE, 100.);
+        assert_eq!(
+            diagonal.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+        assert!(hash.query_ray(vec2(-5., 5.), Vec2::X, 2.).is_empty());
+        assert!(hash.query_ray(vec2(-5., 10.), Vec2::X, 100.).is_empty());
+
+        // Far away rays start walking where they reach the boxes
+        assert_eq!(
+            hash.query_ray(vec2(-1e6, 0.25), Vec2::X, f32::INFINITY),
+            vec![(3, 1e6 - 3.5), (0, 1e6), (1, 1e6 + 2.)]
+        );
+        assert!(hash.query_ray(vec2(-1e6, 0.25), Vec2::X, 1e5).is_empty());
+        assert_eq!(
+            hash.query_ray(vec2(2.25, 0.25), -Vec2::X, 100.),
+            vec![(1, 0.), (0, 1.75), (3, 5.25)]
+        );
+    }
+
+    #[test]
+    fn nearest() {
+        let mut hash = grid();
+        assert_eq!(hash.nearest(vec2(0.25, 0.25)), Some((0, 0.)));
+        assert_eq!(hash.nearest(vec2(1.5, 0.25)), Some((1, 0.5)));
+        assert_eq!(hash.nearest(vec2(-2., 0.)), Some((3, 1.)));
+        // Far away points don't walk all rings in between
+        assert_eq!(hash.nearest(vec2(-1e6, 0.)), Some((3, 1e6 - 3.5)));
+
+        // Same as checking every box, near and far from the occupied cells
+        for i in 0..20 {
+            hash.insert(
+                10 + i,
+                square(vec2((i * 7 % 13) as f32, (i * 5 % 11) as f32), 0.3),
+            );
+        }
+        for i in 0..100 {
+            let point = vec2(
+                (i * 37 % 41) as f32 / 2. - 5.,
+                (i * 23 % 29) as f32 / 2. - 5.,
+            );
+            let (_, distance) = hash.nearest(point).unwrap();
+            let closest = hash
+                .entries
+                .values()
+                .map(|aabb| distance_to(*aabb, point))
+                .fold(f32::INFINITY, f32::min);
+            assert_eq!(distance, closest, "{point}");
+        }
+
+        hash.clear();
+        assert_eq!(hash.nearest(Vec2::ZERO), None);
+    }
+}
@@ -1,6 +1,6 @@
-use std::{fmt::Display, marker::PhantomData, num::NonZeroU32, path::{Path, PathBuf}};
+use std::{fmt::Display, marker::PhantomData, num::NonZeroU32, path::PathBuf};
 
-use glam::{Vec2, Vec3};
+use glam::{UVec2, Vec2, Vec3};
 
 use strum::{EnumIter, IntoEnumIterator};
 use winit::window::Window;
@@ -9,7 +9,10 @@ mod gtransform;
 mod shape;
 
 pub use gtransform::GTransform;
-pub use shape::Shape;
+pub use shape::{
+    Aabb, FillRule, Gradient, LineCap, LineJoin, Path, Polyline, RayHit, Segments, Shape,
+    StrokeAlignment, TriangulationError,
+};
 
 mod color;
 pub use color::Color;
@@ -17,12 +20,24 @@ pub use color::Color;
 mod texture;
 pub use texture::*;
 
+mod lerp;
+pub use lerp::{Lerp, Ramp};
+
+mod tween;
+pub use tween::{Ease, Repeat, Tween};
+
+mod timeline;
+pub use timeline::{Interpolation, Key, Pose, Timeline, Track};
+
+pub mod particles;
+pub mod scene;
+pub mod tilemap;
+
 const VERTEX_BUFFER_INIT_SIZE: wgpu::BufferAddress =
     1000 * std::mem::size_of::<VertexRaw>() as wgpu::BufferAddress;
 const INDEX_BUFFER_INIT_SIZE: wgpu::BufferAddress =
     300 * std::mem::size_of::<u32>() as wgpu::BufferAddress;
 
-
 pub type Geometry<T> = (Vec<Vertex<T>>, Vec<u32>);
 
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
@@ -30,6 +45,8 @@ pub struct Vertex<T: Textures> {
     position: Vec3,
     texture: T,
     texture_coords: Vec2,
+    /// Region of the texture `texture_coords` from 0 to 1 map to, outside of it they repeat
+    texture_rect: (Vec2, Vec2),
     color: Color,
 }
 
@@ -39,6 +56,7 @@ impl<T: Textures> Into<Vertex<T>> for (Vec3, Vec2) {
             position: self.0,
             texture: T::default(),
             texture_coords: self.1,
+            texture_rect: (Vec2::ZERO, Vec2::ONE),
             color: Color::WHITE,
         }
     }
@@ -51,11 +69,13 @@ pub struct VertexRaw {
     texture_index: u32,
     texture_coords: [f32; 2],
     color: [f32; 4],
+    texture_rect: [f32; 4],
 }
 
 impl VertexRaw {
-    const ATTRIBS: [wgpu::VertexAttribute; 4] =
-        wgpu::vertex_attr_array![0 => Float32x3, 1 => Uint32, 2 => Float32x2, 3 => Float32x4];
+    const ATTRIBS: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        0 => Float32x3, 1 => Uint32, 2 => Float32x2, 3 => Float32x4, 4 => Float32x4
+    ];
 
     fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         use std::mem;
@@ -74,7 +94,13 @@ impl<T: Textures> Into<VertexRaw> for Vertex<T> {
             position: [self.position.x, self.position.y, self.position.z],
             texture_index: self.texture.into(),
             texture_coords: [self.texture_coords.x, self.texture_coords.y],
-            color: self.color.into(),
+            color: self.color.to_linear(),
+            texture_rect: [
+                self.texture_rect.0.x,
+                self.texture_rect.0.y,
+                self.texture_rect.1.x,
+                self.texture_rect.1.y,
+            ],
         }
     }
 }
@@ -87,6 +113,33 @@ fn align<T: Default + Clone>(v: &mut Vec<T>) {
     }
 }
 
+/// Turns clockwise triangles counter-clockwise so that back face culling keeps them, like
+/// those of shapes defined clockwise or mirrored by a negative scale
+fn counter_clockwise<T: Textures>(vertices: &[Vertex<T>], indices: &mut [u32]) {
+    for triangle in indices.chunks_exact_mut(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].position.truncate());
+        if (b - a).perp_dot(c - a) < 0. {
+            triangle.swap(1, 2);
+        }
+    }
+}
+
+/// Appends the geometry with its triangles turned counter-clockwise and its indices moved
+/// past the vertices already present
+fn append_geometry<T: Textures>(
+    vertices: &mut Vec<Vertex<T>>,
+    indices: &mut Vec<u32>,
+    geometry: Geometry<T>,
+) {
+    let index_offset = vertices.len() as u32;
+
+    let (new_vertices, mut new_indices) = geometry;
+    counter_clockwise(&new_vertices, &mut new_indices);
+
+    vertices.extend(new_vertices);
+    indices.extend(new_indices.into_iter().map(|i| i + index_offset));
+}
+
 pub struct Graphics<T: Textures> {
     pub size: winit::dpi::PhysicalSize<u32>,
     pub egui_platform: egui_winit_platform::Platform,
@@ -104,6 +157,7 @@ pub struct Graphics<T: Textures> {
     vertices: Vec<Vertex<T>>,
     indices: Vec<u32>,
     texture_views: Vec<wgpu::TextureView>,
+    texture_infos: Vec<TextureInfo>,
     textures_bind_group: wgpu::BindGroup,
     depth_texture: wgpu::Texture,
     depth_texture_view: wgpu::TextureView,
@@ -153,9 +207,8 @@ impl<T: Textures> Graphics<T> {
             .unwrap();
 
         let surface_caps = surface.get_capabilities(&adapter);
-        // Shader code in this tutorial assumes an Srgb surface texture. Using a different
-        // one will result all the colors comming out darker. If you want to support non
-        // Srgb surfaces, you'll need to account for that when drawing to the frame.
+        // The shader works in linear light, which sRGB surfaces encode on write. Other
+        // surfaces get a fragment entry point that encodes the colours itself.
         let surface_format = surface_caps
             .formats
             .iter()
@@ -174,12 +227,13 @@ impl<T: Textures> Graphics<T> {
         };
         surface.configure(&device, &config);
 
+        let mut texture_infos = vec![];
         let texture_views = T::iter()
             .enumerate()
             .map(|(i, texture)| {
                 let image_bytes = texture.bytes();
-                let diffuse_image =
-                    image::load_from_memory(&image_bytes).unwrap();
+                let diffuse_image = image::load_from_memory(&image_bytes).unwrap();
+                texture_infos.push(TextureInfo::new(&image_bytes, &diffuse_image));
 
                 use image::GenericImageView;
                 let dimensions = diffuse_image.dimensions();
@@ -281,7 +335,6 @@ impl<T: Textures> Graphics<T> {
             source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
         });
 
-
         let depth_texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("depth_texture"),
             size: wgpu::Extent3d {
@@ -305,7 +358,7 @@ impl<T: Textures> Graphics<T> {
             depth_compare: wgpu::CompareFunction::Less,
             stencil: wgpu::StencilState::default(),
             bias: wgpu::DepthBiasState::default(),
-        };        
+        };
 
         let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
@@ -317,7 +370,10 @@ impl<T: Textures> Graphics<T> {
             },
             fragment: Some(wgpu::FragmentState {
                 module: &shader,
-                entry_point: "fs_main",
+                entry_point: match config.format.is_srgb() {
+                    true => "fs_main",
+                    false => "fs_main_srgb",
+                },
                 targets: &[Some(wgpu::ColorTargetState {
                     format: config.format,
                     blend: Some(wgpu::BlendState {
@@ -408,19 +464,24 @@ impl<T: Textures> Graphics<T> {
             indices: vec![],
             textures_bind_group,
             texture_views,
+            texture_infos,
             depth_texture,
-            depth_texture_view
+            depth_texture_view,
         }
     }
 
     pub fn add_geometry(&mut self, geometry: Geometry<T>) {
-        let index_offset = self.vertices.len() as u32;
+        append_geometry(&mut self.vertices, &mut self.indices, geometry);
+    }
 
-        let (vertices, indices) = geometry;
+    /// Size, format and colour type of the image `texture` was loaded from
+    pub fn texture_info(&self, texture: impl Into<T>) -> TextureInfo {
+        self.texture_infos[Into::<u32>::into(texture.into()) as usize]
+    }
 
-        self.vertices.extend(vertices);
-        self.indices
-            .extend(indices.into_iter().map(|i| i + index_offset));
+    /// Width and height of `texture` in pixels
+    pub fn texture_size(&self, texture: impl Into<T>) -> UVec2 {
+        self.texture_info(texture).size
     }
 
     pub fn window(&self) -> &Window {
@@ -451,7 +512,9 @@ impl<T: Textures> Graphics<T> {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
         });
 
-        self.depth_texture_view = self.depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.depth_texture_view = self
+            .depth_texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
     }
 
     pub fn handle_raw_event(&mut self, event: &winit::event::Event<()>) {
@@ -526,7 +589,6 @@ impl<T: Textures> Graphics<T> {
             });
 
         {
-
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -588,3 +650,101 @@ impl<T: Textures> Graphics<T> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec2, vec3};
+
+    use super::*;
+
+    fn vertices(points: &[Vec2]) -> Vec<Vertex<TestTextures>> {
+        points
+            .iter()
+            .map(|p| (p.extend(0.), Vec2::ZERO).into())
+            .collect()
+    }
+
+    fn signed_areas(vertices: &[Vertex<TestTextures>], indices: &[u32]) -> Vec<f32> {
+        indices
+            .chunks_exact(3)
+            .map(|t| {
+                let [a, b, c] = [0, 1, 2].map(|i| vertices[t[i] as usize].position.truncate());
+                (b - a).perp_dot(c - a) / 2.
+            })
+            .collect()
+    }
+
+    #[test]
+    fn triangles_turn_counter_clockwise() {
+        let square = vertices(&[vec2(0., 0.), vec2(1., 0.), vec2(1., 1.), vec2(0., 1.)]);
+        // Counter-clockwise, clockwise and mixed triangles
+        for (mut indices, expected) in [
+            (vec![0, 1, 2, 0, 2, 3], vec![0, 1, 2, 0, 2, 3]),
+            (vec![0, 2, 1, 0, 3, 2], vec![0, 1, 2, 0, 2, 3]),
+            (vec![0, 1, 2, 0, 3, 2], vec![0, 1, 2, 0, 2, 3]),
+        ] {
+            counter_clockwise(&square, &mut indices);
+            assert_eq!(indices, expected);
+        }
+
+        // Triangles without area are left alone
+        let line = vertices(&[vec2(0., 0.), vec2(1., 1.), vec2(2., 2.)]);
+        let mut indices = vec![0, 2, 1, 1, 1, 2];
+        counter_clockwise(&line, &mut indices);
+        assert_eq!(indices, vec![0, 2, 1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn z_is_ignored() {
+        let mut triangle = vertices(&[vec2(0., 0.), vec2(0., 1.), vec2(1., 0.)]);
+        triangle[1].position = vec3(0., 1., 5.);
+        let mut indices = vec![0, 1, 2];
+        counter_clockwise(&triangle, &mut indices);
+        assert_eq!(indices, vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn mirrored_shapes_are_appended_counter_clockwise() {
+        let mesh = Shape::<TestTextures>::from_mesh(
+            vec![vec2(0., 0.), vec2(1., 0.), vec2(1., 1.), vec2(0., 1.)],
+            vec![0, 1, 2, 0, 2, 3],
+        );
+        let (mut all_vertices, mut all_indices) = (vec![], vec![]);
+        for scale in [vec2(1., 1.), vec2(-1., 1.), vec2(1., -2.), vec2(-1., -1.)] {
+            let geometry: Geometry<TestTextures> =
+                mesh.clone().apply(GTransform::from_scale(scale)).into();
+            let mirrored = scale.x * scale.y < 0.;
+            assert_eq!(
+                signed_areas(&geometry.0, &geometry.1)
+                    .iter()
+                    .all(|area| *area < 0.),
+                mirrored
+            );
+            append_geometry(&mut all_vertices, &mut all_indices, geometry);
+        }
+
+        assert_eq!(all_vertices.len(), 16);
+        // The mirrored square is flipped and moved past the vertices of the first one
+        assert_eq!(&all_indices[6..12], &[4, 6, 5, 4, 7, 6]);
+        let areas = signed_areas(&all_vertices, &all_indices);
+        assert_eq!(areas.len(), 8);
+        assert!(areas.iter().all(|area| *area > 0.), "{areas:?}");
+        assert!((areas.iter().sum::<f32>() - 5.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn clockwise_outlines_fill() {
+        let clockwise = Shape::<TestTextures>::new(vec![
+            vec2(0., 0.),
+            vec2(0., 2.),
+            vec2(1., 1.),
+            vec2(2., 2.),
+            vec2(2., 0.),
+        ]);
+        let (mut vertices, mut indices) = (vec![], vec![]);
+        append_geometry(&mut vertices, &mut indices, clockwise.into());
+        let areas = signed_areas(&vertices, &indices);
+        assert!(areas.iter().all(|area| *area > 0.), "{areas:?}");
+        assert!((areas.iter().sum::<f32>() - 3.).abs() < 1e-5);
+    }
+}
@@ -1,4 +1,13 @@
-#[derive(Copy, Clone, Debug, Default, PartialEq)]
+use std::{ops, str::FromStr};
+
+use anyhow::{bail, Context};
+use glam::{vec3, Mat3, Vec3};
+
+/// Channels are sRGB encoded like colours picked in image editors and CSS, and converted to
+/// linear light for rendering, so `Color::from_hex(0x808080)` shows up as #808080 on screen.
+/// Operations on the channels, like [`Lerp`](super::Lerp) and the arithmetic operators, work on
+/// the encoded values.
+#[derive(Copy, Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Color {
     pub r: f32,
     pub g: f32,
@@ -61,23 +70,335 @@ impl Color {
         b: 0.,
         a: 0.,
     };
+    /// sRGB encoded channels
     pub const fn from_rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
         Self { r, g, b, a }
     }
 
+    /// sRGB encoded channels
     pub const fn from_rgb(r: f32, g: f32, b: f32) -> Self {
         Self { r, g, b, a: 1.0 }
     }
+    /// sRGB encoded channels, same as [`Color::from_rgb`]
+    pub const fn srgb(r: f32, g: f32, b: f32) -> Self {
+        Self::from_rgb(r, g, b)
+    }
+    /// Linear light channels, like results of lighting calculations
+    pub fn linear(r: f32, g: f32, b: f32) -> Self {
+        Self::from_rgb(linear_to_srgb(r), linear_to_srgb(g), linear_to_srgb(b))
+    }
+    /// Linear light channels as the shader expects them, alpha is kept as is
+    pub fn to_linear(self) -> [f32; 4] {
+        [
+            srgb_to_linear(self.r),
+            srgb_to_linear(self.g),
+            srgb_to_linear(self.b),
+            self.a,
+        ]
+    }
+    /// `0xRRGGBB`, opaque
     pub fn from_hex(hex: u32) -> Self {
-        let r = ((hex >> 16) & 0xFF) as f32 / 255.0;
-        let g = ((hex >> 8) & 0xFF) as f32 / 255.0;
-        let b = (hex & 0xFF) as f32 / 255.0;
-        Self { r, g, b, a: 1. }
+        Self::from_hex_rgba(hex << 8 | 0xFF)
+    }
+    /// `0xRRGGBBAA`
+    pub fn from_hex_rgba(hex: u32) -> Self {
+        let channel = |shift: u32| ((hex >> shift) & 0xFF) as f32 / 255.0;
+        Self::from_rgba(channel(24), channel(16), channel(8), channel(0))
+    }
+    /// `0xRRGGBB`, channels are clamped to 0 to 1
+    pub fn to_hex(self) -> u32 {
+        self.to_hex_rgba() >> 8
+    }
+    /// `0xRRGGBBAA`, channels are clamped to 0 to 1
+    pub fn to_hex_rgba(self) -> u32 {
+        let channel = |c: f32| (c.clamp(0., 1.) * 255.).round() as u32;
+        channel(self.r) << 24 | channel(self.g) << 16 | channel(self.b) << 8 | channel(self.a)
     }
     pub const fn set_alpha(mut self, a: f32) -> Self {
         self.a = a;
         self
     }
+
+    /// Hue in degrees, saturation and value from 0 to 1
+    pub fn from_hsv(h: f32, s: f32, v: f32) -> Self {
+        let channel = |n: f32| {
+            let k = (n + h / 60.).rem_euclid(6.);
+            v - v * s * k.min(4. - k).clamp(0., 1.)
+        };
+        Self::from_rgb(channel(5.), channel(3.), channel(1.))
+    }
+    /// Hue in degrees, saturation and lightness from 0 to 1
+    pub fn from_hsl(h: f32, s: f32, l: f32) -> Self {
+        let a = s * l.min(1. - l);
+        let channel = |n: f32| {
+            let k = (n + h / 30.).rem_euclid(12.);
+            l - a * (k - 3.).min(9. - k).clamp(-1., 1.)
+        };
+        Self::from_rgb(channel(0.), channel(8.), channel(4.))
+    }
+    /// Perceptual lightness, green-red and blue-yellow axes of the OKLab colour space
+    pub fn from_oklab(l: f32, a: f32, b: f32) -> Self {
+        let lms = OKLAB_TO_LMS * vec3(l, a, b);
+        let rgb = LMS_TO_LINEAR * lms.powf(3.);
+        Self::linear(rgb.x, rgb.y, rgb.z)
+    }
+
+    /// Hue is 0 for greys
+    fn hue(self, max: f32, delta: f32) -> f32 {
+        let hue = if delta == 0. {
+            0.
+        } else if max == self.r {
+            (self.g - self.b) / delta
+        } else if max == self.g {
+            (self.b - self.r) / delta + 2.
+        } else {
+            (self.r - self.g) / delta + 4.
+        };
+        (hue * 60.).rem_euclid(360.)
+    }
+    /// `(hue in degrees, saturation, value)`
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let delta = max - self.r.min(self.g).min(self.b);
+        let s = if max > 0. { delta / max } else { 0. };
+        (self.hue(max, delta), s, max)
+    }
+    /// `(hue in degrees, saturation, lightness)`
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let l = (max + min) / 2.;
+        let s = match l.min(1. - l) {
+            d if d > 0. => (max - l) / d,
+            _ => 0.,
+        };
+        (self.hue(max, max - min), s, l)
+    }
+    /// `(lightness, green-red, blue-yellow)`
+    pub fn to_oklab(self) -> (f32, f32, f32) {
+        let [r, g, b, _] = self.to_linear();
+        let lms = LINEAR_TO_LMS * vec3(r, g, b);
+        let lms = vec3(lms.x.cbrt(), lms.y.cbrt(), lms.z.cbrt());
+        (LMS_TO_OKLAB * lms).into()
+    }
+
+    /// Interpolates in OKLab, which keeps the lightness even where [`Lerp`](super::Lerp)
+    /// on the channels passes through dark or grey colours
+    pub fn mix(self, other: Color, t: f32) -> Self {
+        let a = Vec3::from(self.to_oklab());
+        let b = Vec3::from(other.to_oklab());
+        let lab = a.lerp(b, t);
+        Self::from_oklab(lab.x, lab.y, lab.z).set_alpha(self.a + (other.a - self.a) * t)
+    }
+
+    fn map_hsl(self, f: impl FnOnce(f32, f32) -> (f32, f32)) -> Self {
+        let (h, s, l) = self.to_hsl();
+        let (s, l) = f(s, l);
+        Self::from_hsl(h, s.clamp(0., 1.), l.clamp(0., 1.)).set_alpha(self.a)
+    }
+    /// Adds `amount` to the HSL lightness
+    pub fn lighten(self, amount: f32) -> Self {
+        self.map_hsl(|s, l| (s, l + amount))
+    }
+    /// Subtracts `amount` from the HSL lightness
+    pub fn darken(self, amount: f32) -> Self {
+        self.lighten(-amount)
+    }
+    /// Adds `amount` to the HSL saturation
+    pub fn saturate(self, amount: f32) -> Self {
+        self.map_hsl(|s, l| (s + amount, l))
+    }
+    /// Subtracts `amount` from the HSL saturation
+    pub fn desaturate(self, amount: f32) -> Self {
+        self.saturate(-amount)
+    }
+}
+
+/// Matrices from <https://bottosson.github.io/posts/oklab>, one column per input channel
+const LINEAR_TO_LMS: Mat3 = Mat3::from_cols(
+    Vec3::new(0.41222147, 0.2119035, 0.08830246),
+    Vec3::new(0.53633254, 0.6806995, 0.28171884),
+    Vec3::new(0.051445993, 0.10739696, 0.6299787),
+);
+const LMS_TO_OKLAB: Mat3 = Mat3::from_cols(
+    Vec3::new(0.21045426, 1.9779985, 0.025904037),
+    Vec3::new(0.7936178, -2.4285922, 0.78277177),
+    Vec3::new(-0.004072047, 0.4505937, -0.80867577),
+);
+const OKLAB_TO_LMS: Mat3 = Mat3::from_cols(
+    Vec3::new(1., 1., 1.),
+    Vec3::new(0.39633778, -0.105561346, -0.08948418),
+    Vec3::new(0.21580376, -0.06385417, -1.2914855),
+);
+const LMS_TO_LINEAR: Mat3 = Mat3::from_cols(
+    Vec3::new(4.0767417, -1.268438, -0.0041960863),
+    Vec3::new(-3.3077116, 2.6097574, -0.7034186),
+    Vec3::new(0.23096993, -0.3413194, 1.7076147),
+);
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
+
+/// Parses CSS colours: `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`, `hsl()`,
+/// `hsla()` and basic named colours
+impl FromStr for Color {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        let value = value.trim();
+        if let Some(hex) = value.strip_prefix('#') {
+            let mut hex = match hex.len() {
+                3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+                6 | 8 => hex.to_owned(),
+                _ => bail!("invalid colour {value}"),
+            };
+            if hex.len() == 6 {
+                hex.push_str("ff");
+            }
+            let hex =
+                u32::from_str_radix(&hex, 16).with_context(|| format!("invalid colour {value}"))?;
+            return Ok(Color::from_hex_rgba(hex));
+        }
+        if let Some((function, arguments)) = value
+            .strip_suffix(')')
+            .and_then(|value| value.split_once('('))
+        {
+            return parse_function(function.trim(), arguments)
+                .with_context(|| format!("invalid colour {value}"));
+        }
+
+        let hex = match value.to_ascii_lowercase().as_str() {
+            "black" => 0x000000,
+            "silver" => 0xC0C0C0,
+            "gray" | "grey" => 0x808080,
+            "white" => 0xFFFFFF,
+            "maroon" => 0x800000,
+            "red" => 0xFF0000,
+            "purple" => 0x800080,
+            "fuchsia" | "magenta" => 0xFF00FF,
+            "green" => 0x008000,
+            "lime" => 0x00FF00,
+            "olive" => 0x808000,
+            "yellow" => 0xFFFF00,
+            "navy" => 0x000080,
+            "blue" => 0x0000FF,
+            "teal" => 0x008080,
+            "aqua" | "cyan" => 0x00FFFF,
+            "orange" => 0xFFA500,
+            "brown" => 0xA52A2A,
+            "pink" => 0xFFC0CB,
+            "gold" => 0xFFD700,
+            "transparent" => return Ok(Color::TRANSPARENT),
+            _ => bail!("unknown colour {value}"),
+        };
+        Ok(Color::from_hex(hex))
+    }
+}
+
+/// Arguments separated by commas or by spaces with the alpha after a slash
+fn parse_function(function: &str, arguments: &str) -> anyhow::Result<Color> {
+    let arguments = arguments
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|argument| !argument.is_empty())
+        .collect::<Vec<_>>();
+    // Percentages map to 0 to 1, plain numbers are divided by `scale`
+    let number = |argument: &str, scale: f32| -> anyhow::Result<f32> {
+        Ok(match argument.strip_suffix('%') {
+            Some(percent) => percent.parse::<f32>()? / 100.,
+            None => argument.parse::<f32>()? / scale,
+        })
+    };
+    if !(3..=4).contains(&arguments.len()) {
+        bail!("expected 3 or 4 arguments");
+    }
+    let alpha = match arguments.get(3) {
+        Some(alpha) => number(alpha, 1.)?,
+        None => 1.,
+    };
+
+    let color = match function {
+        "rgb" | "rgba" => Color::from_rgb(
+            number(arguments[0], 255.)?,
+            number(arguments[1], 255.)?,
+            number(arguments[2], 255.)?,
+        ),
+        "hsl" | "hsla" => Color::from_hsl(
+            arguments[0].trim_end_matches("deg").parse()?,
+            number(arguments[1], 100.)?,
+            number(arguments[2], 100.)?,
+        ),
+        _ => bail!("unknown colour function {function}"),
+    };
+    Ok(color.set_alpha(alpha))
+}
+
+/// Component-wise, used for tinting
+impl ops::Mul for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: Color) -> Color {
+        Color {
+            r: self.r * rhs.r,
+            g: self.g * rhs.g,
+            b: self.b * rhs.b,
+            a: self.a * rhs.a,
+        }
+    }
+}
+
+impl ops::Mul<f32> for Color {
+    type Output = Color;
+
+    fn mul(self, rhs: f32) -> Color {
+        Color::from_rgba(self.r * rhs, self.g * rhs, self.b * rhs, self.a * rhs)
+    }
+}
+
+impl ops::Div<f32> for Color {
+    type Output = Color;
+
+    fn div(self, rhs: f32) -> Color {
+        Color::from_rgba(self.r / rhs, self.g / rhs, self.b / rhs, self.a / rhs)
+    }
+}
+
+impl ops::Add for Color {
+    type Output = Color;
+
+    fn add(self, rhs: Color) -> Color {
+        Color::from_rgba(
+            self.r + rhs.r,
+            self.g + rhs.g,
+            self.b + rhs.b,
+            self.a + rhs.a,
+        )
+    }
+}
+
+impl ops::Sub for Color {
+    type Output = Color;
+
+    fn sub(self, rhs: Color) -> Color {
+        Color::from_rgba(
+            self.r - rhs.r,
+            self.g - rhs.g,
+            self.b - rhs.b,
+            self.a - rhs.a,
+        )
+    }
 }
 
 impl Into<Color> for [f32; 3] {
@@ -96,3 +417,192 @@ impl Into<[f32; 4]> for Color {
         [self.r, self.g, self.b, self.a]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Color, b: Color) {
+        let (a_channels, b_channels): ([f32; 4], [f32; 4]) = (a.into(), b.into());
+        let close = a_channels
+            .iter()
+            .zip(b_channels)
+            .all(|(a, b)| (a - b).abs() < 1e-3);
+        assert!(close, "{a:?} != {b:?}");
+    }
+
+    fn colors() -> [Color; 6] {
+        [
+            Color::from_hex(0x336699),
+            Color::from_hex(0xFFA500),
+            Color::from_rgb(0.2, 0.9, 0.4),
+            Color::from_rgb(0.5, 0.5, 0.5),
+            Color::WHITE,
+            Color::BLACK,
+        ]
+    }
+
+    #[test]
+    fn hsv_round_trips() {
+        assert_close(Color::from_hsv(0., 1., 1.), Color::RED);
+        assert_close(Color::from_hsv(240., 1., 1.), Color::BLUE);
+        assert_close(Color::from_hsv(-240., 1., 1.), Color::GREEN);
+        let (h, s, v) = Color::from_hex(0x336699).to_hsv();
+        assert!((h - 210.).abs() < 1e-3 && (s - 2. / 3.).abs() < 1e-3 && (v - 0.6).abs() < 1e-3);
+        for color in colors() {
+            let (h, s, v) = color.to_hsv();
+            assert_close(Color::from_hsv(h, s, v), color);
+        }
+    }
+
+    #[test]
+    fn hsl_round_trips() {
+        assert_close(Color::from_hsl(120., 1., 0.5), Color::GREEN);
+        assert_close(Color::from_hsl(300., 1., 0.5), Color::PINK);
+        assert_close(
+            Color::from_hsl(42., 0., 0.5),
+            Color::from_rgb(0.5, 0.5, 0.5),
+        );
+        let (h, s, l) = Color::from_hex(0x336699).to_hsl();
+        assert!((h - 210.).abs() < 1e-3 && (s - 0.5).abs() < 1e-3 && (l - 0.4).abs() < 1e-3);
+        for color in colors() {
+            let (h, s, l) = color.to_hsl();
+            assert_close(Color::from_hsl(h, s, l), color);
+        }
+    }
+
+    #[test]
+    fn oklab_round_trips() {
+        let (l, a, b) = Color::WHITE.to_oklab();
+        assert!((l - 1.).abs() < 1e-3 && a.abs() < 1e-3 && b.abs() < 1e-3);
+        let (l, a, b) = Color::RED.to_oklab();
+        assert!((l - 0.628).abs() < 1e-3 && (a - 0.2249).abs() < 1e-3 && (b - 0.1258).abs() < 1e-3);
+        for color in colors() {
+            let (l, a, b) = color.to_oklab();
+            assert_close(Color::from_oklab(l, a, b), color);
+        }
+
+        assert_close(Color::RED.mix(Color::BLUE, 0.), Color::RED);
+        assert_close(Color::RED.mix(Color::BLUE, 1.), Color::BLUE);
+        let half = Color::BLACK.mix(Color::WHITE.set_alpha(0.), 0.5);
+        assert!((half.to_oklab().0 - 0.5).abs() < 1e-3 && (half.a - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn hex() {
+        let color = Color::from_hex(0x336699);
+        assert_close(color, Color::from_rgb(0.2, 0.4, 0.6));
+        assert_eq!(color.to_hex(), 0x336699);
+        assert_eq!(color.to_hex_rgba(), 0x336699FF);
+
+        let color = Color::from_hex_rgba(0x33669980);
+        assert_close(color, Color::from_rgba(0.2, 0.4, 0.6, 128. / 255.));
+        assert_eq!(color.to_hex(), 0x336699);
+        assert_eq!(color.to_hex_rgba(), 0x33669980);
+
+        // Out of range channels are clamped
+        assert_eq!(Color::from_rgba(2., -1., 0.5, 1.).to_hex_rgba(), 0xFF0080FF);
+    }
+
+    #[test]
+    fn srgb_round_trips() {
+        // The shader gets linear light and the sRGB surface encodes it again
+        let [r, g, b, a] = Color::from_hex(0x808080).to_linear();
+        assert!((r - 0.2158).abs() < 1e-4 && r == g && g == b && a == 1.);
+        assert_eq!((linear_to_srgb(r) * 255.).round(), 128.);
+
+        for channel in 0..=255 {
+            let color = Color::from_hex_rgba(channel * 0x01010101);
+            let [r, g, b, a] = color.to_linear();
+            assert_eq!(
+                Color::linear(r, g, b).set_alpha(a).to_hex_rgba(),
+                color.to_hex_rgba()
+            );
+        }
+        assert_eq!(srgb_to_linear(0.), 0.);
+        assert!((srgb_to_linear(1.) - 1.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn parses_css() {
+        for (css, color) in [
+            ("#369", Color::from_hex(0x336699)),
+            ("#3698", Color::from_hex_rgba(0x33669988)),
+            ("#336699", Color::from_hex(0x336699)),
+            (" #33669980 ", Color::from_hex_rgba(0x33669980)),
+            ("rgb(51, 102, 153)", Color::from_hex(0x336699)),
+            (
+                "rgba(51, 102, 153, 0.5)",
+                Color::from_hex(0x336699).set_alpha(0.5),
+            ),
+            (
+                "rgb(20% 40% 60% / 50%)",
+                Color::from_hex(0x336699).set_alpha(0.5),
+            ),
+            ("hsl(210, 50%, 40%)", Color::from_hex(0x336699)),
+            (
+                "hsla(210deg 50% 40% / 0.25)",
+                Color::from_hex(0x336699).set_alpha(0.25),
+            ),
+            ("Grey", Color::from_hex(0x808080)),
+            ("transparent", Color::TRANSPARENT),
+        ] {
+            assert_close(css.parse().unwrap(), color);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_css() {
+        for css in [
+            "",
+            "#",
+            "#12345",
+            "#ggg",
+            "#1234567890",
+            "rgb(1, 2)",
+            "rgb(1, 2, 3, 4, 5)",
+            "rgb(1, 2, x)",
+            "rgb(1, 2, 3",
+            "hsl(red, 50%, 50%)",
+            "lab(50% 20 30)",
+            "reddish",
+        ] {
+            assert!(css.parse::<Color>().is_err(), "{css}");
+        }
+    }
+
+    #[test]
+    fn operators() {
+        let a = Color::from_rgba(0.2, 0.4, 0.6, 0.8);
+        let b = Color::from_rgba(0.5, 0.5, 1., 0.5);
+        assert_close(a * b, Color::from_rgba(0.1, 0.2, 0.6, 0.4));
+        assert_close(a * 0.5, Color::from_rgba(0.1, 0.2, 0.3, 0.4));
+        assert_close(a / 2., Color::from_rgba(0.1, 0.2, 0.3, 0.4));
+        assert_close(a + b, Color::from_rgba(0.7, 0.9, 1.6, 1.3));
+        assert_close(a - b, Color::from_rgba(-0.3, -0.1, -0.4, 0.3));
+        assert_close(a * Color::WHITE, a);
+
+        let color: Color = [0.2, 0.4, 0.6].into();
+        assert_close(color, Color::from_rgb(0.2, 0.4, 0.6));
+        let channels: [f32; 4] = a.into();
+        assert_eq!(channels, [0.2, 0.4, 0.6, 0.8]);
+    }
+
+    #[test]
+    fn adjusts_hsl() {
+        let color = Color::from_hsl(210., 0.5, 0.4).set_alpha(0.5);
+        assert_close(
+            color.lighten(0.2),
+            Color::from_hsl(210., 0.5, 0.6).set_alpha(0.5),
+        );
+        assert_close(color.darken(1.), Color::BLACK.set_alpha(0.5));
+        assert_close(
+            color.desaturate(0.5),
+            Color::from_hsl(0., 0., 0.4).set_alpha(0.5),
+        );
+        assert_close(
+            color.saturate(0.25),
+            Color::from_hsl(210., 0.75, 0.4).set_alpha(0.5),
+        );
+    }
+}
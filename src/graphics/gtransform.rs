@@ -1,3 +1,5 @@
+use glam::{vec2, Affine2, Mat3};
+
 use super::*;
 
 #[derive(Clone, Copy, Debug)]
@@ -84,4 +86,177 @@ impl GTransform {
     pub fn inv_transform(&self, point: Vec2) -> Vec2 {
         Vec2::from_angle(-self.rotation).rotate(point - self.center) / self.scale
     }
+    /// Transform undoing this one, `None` if a scale is zero or the inverse shears, which it
+    /// does for rotated non-uniform scales. [`GTransform::inv_transform`] maps points back
+    /// even then, or invert the `Affine2`.
+    pub fn inverse(&self) -> Option<Self> {
+        let affine = Affine2::from(*self);
+        if affine.matrix2.determinant() == 0. {
+            return None;
+        }
+        affine.inverse().try_into().ok()
+    }
+}
+
+/// Whether the axes of the transform aren't perpendicular anymore
+fn shears(affine: Affine2) -> bool {
+    let (x_axis, y_axis) = (affine.matrix2.x_axis, affine.matrix2.y_axis);
+    x_axis.dot(y_axis).abs() > 1e-5 * x_axis.length() * y_axis.length()
+}
+
+/// `a * b` applies `b` first, like a child transform `b` under a parent `a`. `None` if the
+/// result shears, which a `GTransform` can't hold, as when a non-uniformly scaled parent
+/// rotates its child. Compose `Affine2`s for those.
+impl std::ops::Mul for GTransform {
+    type Output = Option<Self>;
+
+    fn mul(self, rhs: Self) -> Option<Self> {
+        (Affine2::from(self) * Affine2::from(rhs)).try_into().ok()
+    }
+}
+
+impl From<GTransform> for Affine2 {
+    fn from(gtransform: GTransform) -> Self {
+        Affine2::from_scale_angle_translation(
+            gtransform.scale,
+            gtransform.rotation,
+            gtransform.center,
+        )
+    }
+}
+
+impl From<GTransform> for Mat3 {
+    fn from(gtransform: GTransform) -> Self {
+        Affine2::from(gtransform).into()
+    }
+}
+
+/// Decomposes into rotation, scale and translation, mirroring ends up in the y scale. Fails
+/// for shearing transforms.
+impl TryFrom<Affine2> for GTransform {
+    type Error = anyhow::Error;
+
+    fn try_from(affine: Affine2) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            !shears(affine),
+            "the transform shears, which a GTransform can't represent"
+        );
+        let (x_axis, y_axis) = (affine.matrix2.x_axis, affine.matrix2.y_axis);
+        let scale_x = x_axis.length();
+        let (rotation, scale) = if scale_x > 0. {
+            let scale_y = affine.matrix2.determinant() / scale_x;
+            (x_axis.y.atan2(x_axis.x), vec2(scale_x, scale_y))
+        } else {
+            ((-y_axis.x).atan2(y_axis.y), vec2(0., y_axis.length()))
+        };
+        Ok(Self {
+            center: affine.translation,
+            rotation,
+            scale,
+        })
+    }
+}
+
+/// The last row is ignored, like for any 2D affine transform
+impl TryFrom<Mat3> for GTransform {
+    type Error = anyhow::Error;
+
+    fn try_from(matrix: Mat3) -> anyhow::Result<Self> {
+        Affine2::from_mat3(matrix).try_into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use super::*;
+
+    fn assert_same(a: GTransform, b: impl Fn(Vec2) -> Vec2) {
+        for p in [Vec2::ZERO, Vec2::X, vec2(-2., 3.), vec2(0.5, -4.)] {
+            let (p_a, p_b) = (a.transform(p), b(p));
+            assert!(p_a.distance(p_b) < 1e-4, "{p}: {p_a} != {p_b}");
+        }
+    }
+
+    fn gtransform(center: Vec2, rotation: f32, scale: Vec2) -> GTransform {
+        GTransform {
+            center,
+            rotation,
+            scale,
+        }
+    }
+
+    #[test]
+    fn mul() {
+        let parent = gtransform(vec2(1., 2.), 0.3, vec2(2., 3.));
+        let children = [
+            gtransform(vec2(-1., 0.5), 0., vec2(4., 0.5)),
+            gtransform(vec2(-1., 0.5), FRAC_PI_2, Vec2::splat(2.)),
+            gtransform(vec2(3., 0.), 0., vec2(-1., 1.)),
+        ];
+        for child in children {
+            let product = (parent * child).unwrap();
+            assert_same(product, |p| parent.transform(child.transform(p)));
+        }
+
+        // Non-uniform children under rotated uniformly scaled and mirrored parents
+        for parent in [
+            gtransform(vec2(1., 2.), 0.3, Vec2::splat(2.)),
+            gtransform(Vec2::ZERO, 0.3, vec2(2., -2.)),
+        ] {
+            let child = gtransform(vec2(-1., 0.5), 0.8, vec2(4., 0.5));
+            let product = (parent * child).unwrap();
+            assert_same(product, |p| parent.transform(child.transform(p)));
+        }
+
+        // Rotating a child under a non-uniformly scaled parent shears
+        let parent = GTransform::from_scale(vec2(3., 1.));
+        assert!((parent * GTransform::default().rotate(0.5)).is_none());
+    }
+
+    #[test]
+    fn inverse() {
+        for gtransform in [
+            gtransform(vec2(1., 2.), 0.3, Vec2::splat(2.)),
+            gtransform(vec2(1., 2.), 0., vec2(2., 0.5)),
+            gtransform(vec2(1., 2.), FRAC_PI_2, vec2(2., 0.5)),
+            gtransform(vec2(-3., 1.), 0.7, vec2(-1., 1.)),
+        ] {
+            let inverse = gtransform.inverse().unwrap();
+            assert_same(inverse, |p| gtransform.inv_transform(p));
+            assert_same((gtransform * inverse).unwrap(), |p| p);
+        }
+
+        let sheared = gtransform(Vec2::ZERO, 0.7, vec2(2., 0.5));
+        assert!(sheared.inverse().is_none());
+        let p = vec2(3., -1.);
+        assert!(sheared.inv_transform(sheared.transform(p)).distance(p) < 1e-5);
+        assert!(GTransform::from_scale(vec2(0., 1.)).inverse().is_none());
+    }
+
+    #[test]
+    fn affine_round_trips() {
+        for gtransform in [
+            gtransform(vec2(1., 2.), 0.3, vec2(2., 3.)),
+            gtransform(vec2(1., 2.), -2., vec2(2., -3.)),
+            gtransform(vec2(1., 2.), 2., vec2(-2., 3.)),
+            gtransform(vec2(1., 2.), 0.3, vec2(0., 3.)),
+        ] {
+            let affine = Affine2::from(gtransform);
+            assert_same(gtransform, |p| affine.transform_point2(p));
+            assert_same(GTransform::try_from(affine).unwrap(), |p| {
+                affine.transform_point2(p)
+            });
+
+            let matrix = Mat3::from(gtransform);
+            assert_same(GTransform::try_from(matrix).unwrap(), |p| {
+                matrix.transform_point2(p)
+            });
+        }
+
+        let shear = Affine2::from_cols(Vec2::X, vec2(1., 1.), Vec2::ZERO);
+        assert!(GTransform::try_from(shear).is_err());
+        assert!(GTransform::try_from(Mat3::from(shear)).is_err());
+    }
 }
@@ -0,0 +1,95 @@
+use std::f32::consts::{PI, TAU};
+
+use super::*;
+
+/// Linear interpolation, `t = 0` gives `self` and `t = 1` gives `other`
+pub trait Lerp {
+    fn lerp(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+/// Rounds down, so that going from frame 0 to 4 shows every frame in between for equally long
+impl Lerp for u32 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        (*self as f32).lerp(&(*other as f32), t).floor() as u32
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Vec2::lerp(*self, *other, t)
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Color::from_rgba(
+            self.r.lerp(&other.r, t),
+            self.g.lerp(&other.g, t),
+            self.b.lerp(&other.b, t),
+            self.a.lerp(&other.a, t),
+        )
+    }
+}
+
+/// Turns along the shorter arc, from 350° to 10° by 20° instead of back by 340°
+impl Lerp for GTransform {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let turn = (other.rotation - self.rotation + PI).rem_euclid(TAU) - PI;
+        Self {
+            center: self.center.lerp(other.center, t),
+            rotation: self.rotation + turn * t,
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+/// Piecewise linear curve over `[0, 1]`, like the lifetime of a particle or the stops of a
+/// gradient
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ramp<V> {
+    keys: Vec<(f32, V)>,
+}
+
+impl<V: Lerp + Clone> Ramp<V> {
+    pub fn constant(value: V) -> Self {
+        Self {
+            keys: vec![(0., value)],
+        }
+    }
+
+    pub fn new(start: V, end: V) -> Self {
+        Self {
+            keys: vec![(0., start), (1., end)],
+        }
+    }
+
+    /// `t` is clamped to `[0, 1]`, keys at the same `t` are replaced
+    pub fn add_key(mut self, t: f32, value: V) -> Self {
+        let t = t.clamp(0., 1.);
+        match self.keys.binary_search_by(|(key, _)| key.total_cmp(&t)) {
+            Ok(i) => self.keys[i].1 = value,
+            Err(i) => self.keys.insert(i, (t, value)),
+        }
+        self
+    }
+
+    /// Positions and values of the keys, sorted by position
+    pub fn keys(&self) -> &[(f32, V)] {
+        &self.keys
+    }
+
+    pub fn sample(&self, t: f32) -> V {
+        let next = self.keys.partition_point(|(key, _)| *key <= t);
+        match (self.keys.get(next.wrapping_sub(1)), self.keys.get(next)) {
+            (Some((t0, v0)), Some((t1, v1))) => v0.lerp(v1, (t - t0) / (t1 - t0)),
+            (Some((_, v)), None) | (None, Some((_, v))) => v.clone(),
+            (None, None) => unreachable!("ramps always have at least one key"),
+        }
+    }
+}
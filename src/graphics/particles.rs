@@ -0,0 +1,386 @@
+use std::collections::VecDeque;
+
+use glam::vec3;
+use rand::Rng;
+
+use super::*;
+
+pub use super::Ramp;
+
+#[derive(Clone, Copy, Debug)]
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    rotation: f32,
+    angular_velocity: f32,
+    age: f32,
+    lifetime: f32,
+}
+
+fn random_between(rng: &mut impl Rng, (min, max): (f32, f32)) -> f32 {
+    min + (max - min) * rng.gen::<f32>()
+}
+
+/// Spawns particles and simulates them on the CPU, call [`ParticleEmitter::update`] from
+/// `App::update` and [`ParticleEmitter::draw`] from `App::draw`.
+#[derive(Clone, Debug)]
+pub struct ParticleEmitter<T: Textures> {
+    pub position: Vec2,
+    /// Particles spawned per second while `emitting`
+    pub rate: f32,
+    pub emitting: bool,
+    /// Oldest particles are dropped once the limit is reached
+    pub max_particles: usize,
+    lifetime: (f32, f32),
+    spawn_radius: f32,
+    direction: f32,
+    spread: f32,
+    speed: (f32, f32),
+    angular_velocity: (f32, f32),
+    gravity: Vec2,
+    drag: f32,
+    size: Ramp<f32>,
+    color: Ramp<Color>,
+    z: f32,
+    /// Geometry of a single particle of size 1 centered at the origin
+    template: Geometry<T>,
+    particles: VecDeque<Particle>,
+    spawn_accumulator: f32,
+}
+
+impl<T: Textures> ParticleEmitter<T> {
+    /// Particles are drawn as `shape`, scaled by the size ramp and tinted by the colour ramp
+    pub fn from_shape(shape: Shape<T>) -> Self {
+        Self {
+            position: Vec2::ZERO,
+            rate: 10.,
+            emitting: true,
+            max_particles: 1000,
+            lifetime: (1., 1.),
+            spawn_radius: 0.,
+            direction: std::f32::consts::FRAC_PI_2,
+            spread: std::f32::consts::PI,
+            speed: (0.5, 0.5),
+            angular_velocity: (0., 0.),
+            gravity: Vec2::ZERO,
+            drag: 0.,
+            size: Ramp::constant(0.05),
+            color: Ramp::constant(Color::WHITE),
+            z: 0.,
+            template: shape.into(),
+            particles: VecDeque::new(),
+            spawn_accumulator: 0.,
+        }
+    }
+
+    /// Particles are drawn as squares with the texture stretched over them
+    pub fn from_texture(texture: impl Into<T>) -> Self {
+        Self::from_shape(Shape::from_square_centered().set_texture(texture))
+    }
+
+    pub fn set_position(mut self, position: Vec2) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn set_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn set_max_particles(mut self, max_particles: usize) -> Self {
+        self.max_particles = max_particles;
+        self
+    }
+
+    /// Lifetime in seconds is picked uniformly from `[min, max]` for every particle
+    pub fn set_lifetime(mut self, min: f32, max: f32) -> Self {
+        self.lifetime = (min, max);
+        self
+    }
+
+    /// Particles spawn uniformly inside a circle around `position`
+    pub fn set_spawn_radius(mut self, radius: f32) -> Self {
+        self.spawn_radius = radius;
+        self
+    }
+
+    /// Particles are launched within `spread` radians to either side of `direction`
+    pub fn set_cone(mut self, direction: f32, spread: f32) -> Self {
+        self.direction = direction;
+        self.spread = spread;
+        self
+    }
+
+    pub fn set_speed(mut self, min: f32, max: f32) -> Self {
+        self.speed = (min, max);
+        self
+    }
+
+    /// Radians per second, counter-clockwise
+    pub fn set_angular_velocity(mut self, min: f32, max: f32) -> Self {
+        self.angular_velocity = (min, max);
+        self
+    }
+
+    pub fn set_gravity(mut self, gravity: Vec2) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Velocity decays by `exp(-drag * dt)` every update
+    pub fn set_drag(mut self, drag: f32) -> Self {
+        self.drag = drag;
+        self
+    }
+
+    pub fn set_size(mut self, size: Ramp<f32>) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn set_color(mut self, color: Ramp<Color>) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn set_z(mut self, z: f32) -> Self {
+        self.z = z;
+        self
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    /// Spawns `count` particles at once, independently of `rate` and `emitting`
+    pub fn burst(&mut self, count: usize) {
+        let mut rng = rand::thread_rng();
+        for _ in 0..count {
+            self.spawn(&mut rng);
+        }
+    }
+
+    fn spawn(&mut self, rng: &mut impl Rng) {
+        if self.max_particles == 0 {
+            return;
+        }
+        if self.particles.len() >= self.max_particles {
+            self.particles.pop_front();
+        }
+
+        let angle = self.direction + random_between(rng, (-self.spread, self.spread));
+        let offset = Vec2::from_angle(rng.gen::<f32>() * std::f32::consts::TAU)
+            * self.spawn_radius
+            * rng.gen::<f32>().sqrt();
+
+        self.particles.push_back(Particle {
+            position: self.position + offset,
+            velocity: Vec2::from_angle(angle) * random_between(rng, self.speed),
+            rotation: 0.,
+            angular_velocity: random_between(rng, self.angular_velocity),
+            age: 0.,
+            lifetime: random_between(rng, self.lifetime).max(f32::EPSILON),
+        });
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let damping = (-self.drag * dt).exp();
+        for particle in &mut self.particles {
+            particle.age += dt;
+            particle.velocity = (particle.velocity + self.gravity * dt) * damping;
+            particle.position += particle.velocity * dt;
+            particle.rotation += particle.angular_velocity * dt;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+
+        if self.emitting {
+            self.spawn_accumulator += self.rate * dt;
+            let spawns = self.spawn_accumulator.floor();
+            self.spawn_accumulator -= spawns;
+            // Infinite rates leave nothing to carry over
+            if !self.spawn_accumulator.is_finite() {
+                self.spawn_accumulator = 0.;
+            }
+            // More spawns would only evict particles spawned in the same update
+            let mut rng = rand::thread_rng();
+            for _ in 0..(spawns as usize).min(self.max_particles) {
+                self.spawn(&mut rng);
+            }
+        } else {
+            self.spawn_accumulator = 0.;
+        }
+    }
+
+    /// All particles merged into a single geometry, `gtransform` is applied on top
+    pub fn geometry(&self, gtransform: GTransform) -> Geometry<T> {
+        let (template_vertices, template_indices) = &self.template;
+        let mut vertices = Vec::with_capacity(template_vertices.len() * self.particles.len());
+        let mut indices = Vec::with_capacity(template_indices.len() * self.particles.len());
+
+        for particle in &self.particles {
+            let t = particle.age / particle.lifetime;
+            let particle_gtransform = gtransform
+                .translate(particle.position)
+                .rotate(particle.rotation)
+                .inflate(self.size.sample(t));
+            let color = self.color.sample(t);
+
+            let index_offset = vertices.len() as u32;
+            vertices.extend(template_vertices.iter().map(|vertex| {
+                let mut vertex = vertex.clone();
+                let position = particle_gtransform.transform(vertex.position.truncate());
+                vertex.position = vec3(position.x, position.y, self.z);
+                vertex.color = vertex.color * color;
+                vertex
+            }));
+            indices.extend(template_indices.iter().map(|i| i + index_offset));
+        }
+
+        (vertices, indices)
+    }
+
+    pub fn draw(&self, graphics: &mut Graphics<T>, gtransform: GTransform) {
+        graphics.add_geometry(self.geometry(gtransform));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use super::*;
+
+    /// Particles launched to the right at speed 1 that live for a second
+    fn emitter() -> ParticleEmitter<TestTextures> {
+        ParticleEmitter::from_shape(Shape::from_square_centered())
+            .set_cone(0., 0.)
+            .set_speed(1., 1.)
+            .set_size(Ramp::constant(1.))
+    }
+
+    fn ages(emitter: &ParticleEmitter<TestTextures>) -> Vec<f32> {
+        emitter
+            .particles
+            .iter()
+            .map(|particle| particle.age)
+            .collect()
+    }
+
+    #[test]
+    fn spawn_rate_accumulates() {
+        let mut emitter = emitter().set_rate(10.);
+        emitter.update(0.05);
+        assert_eq!(emitter.particle_count(), 0);
+        emitter.update(0.05);
+        assert_eq!(emitter.particle_count(), 1);
+        for _ in 0..8 {
+            emitter.update(0.05);
+        }
+        assert_eq!(emitter.particle_count(), 5);
+
+        // Stopping drops the partial spawn
+        emitter.update(0.05);
+        emitter.emitting = false;
+        emitter.update(0.05);
+        emitter.emitting = true;
+        emitter.update(0.05);
+        assert_eq!(emitter.particle_count(), 5);
+    }
+
+    #[test]
+    fn huge_rates_are_capped() {
+        for rate in [f32::INFINITY, 1e30] {
+            let mut emitter = emitter().set_rate(rate).set_max_particles(10);
+            emitter.update(0.1);
+            assert_eq!(emitter.particle_count(), 10);
+            assert_eq!(emitter.spawn_accumulator, 0.);
+        }
+        let mut emitter = emitter().set_rate(f32::NAN);
+        emitter.update(0.1);
+        assert_eq!(emitter.particle_count(), 0);
+        emitter.rate = 10.;
+        emitter.update(0.1);
+        assert_eq!(emitter.particle_count(), 1);
+    }
+
+    #[test]
+    fn bursts_evict_the_oldest() {
+        let mut emitter = emitter().set_max_particles(3);
+        emitter.emitting = false;
+        emitter.burst(2);
+        emitter.update(0.5);
+        emitter.burst(2);
+        assert_eq!(ages(&emitter), [0.5, 0., 0.]);
+        emitter.burst(10);
+        assert_eq!(ages(&emitter), [0., 0., 0.]);
+
+        let mut emitter = emitter.set_max_particles(0);
+        emitter.clear();
+        emitter.burst(1);
+        assert_eq!(emitter.particle_count(), 0);
+    }
+
+    #[test]
+    fn particles_die_of_age() {
+        let mut emitter = emitter().set_lifetime(1., 1.);
+        emitter.emitting = false;
+        emitter.burst(1);
+        emitter.update(0.5);
+        emitter.burst(1);
+        emitter.update(0.4);
+        assert_eq!(emitter.particle_count(), 2);
+        emitter.update(0.2);
+        assert_eq!(ages(&emitter), [0.6]);
+        emitter.update(0.4);
+        assert_eq!(emitter.particle_count(), 0);
+    }
+
+    #[test]
+    fn gravity_and_drag() {
+        let mut emitter = emitter().set_position(vec2(1., 0.));
+        emitter.emitting = false;
+        emitter.burst(1);
+        emitter.update(0.5);
+        let particle = emitter.particles[0];
+        assert!(particle.position.distance(vec2(1.5, 0.)) < 1e-6);
+
+        let mut emitter = emitter.set_gravity(vec2(0., -10.)).set_drag(2.);
+        emitter.clear();
+        emitter.burst(1);
+        emitter.update(0.1);
+        let particle = emitter.particles[0];
+        let velocity = vec2(1., -1.) * (-0.2f32).exp();
+        assert!(particle.velocity.distance(velocity) < 1e-6);
+        assert!(particle.position.distance(vec2(1., 0.) + velocity * 0.1) < 1e-6);
+    }
+
+    #[test]
+    fn geometry_follows_the_ramps() {
+        let mut emitter = emitter()
+            .set_speed(0., 0.)
+            .set_lifetime(2., 2.)
+            .set_size(Ramp::new(1., 3.))
+            .set_color(Ramp::new(Color::WHITE, Color::BLACK))
+            .set_z(0.5);
+        emitter.emitting = false;
+        emitter.burst(2);
+        emitter.update(1.);
+
+        let (vertices, indices) = emitter.geometry(GTransform::from_translation(vec2(0., 1.)));
+        assert_eq!((vertices.len(), indices.len()), (8, 12));
+        assert!(indices[6..].iter().all(|i| *i >= 4));
+        for vertex in &vertices {
+            // Half way through their lives particles are twice as large and grey
+            let position = vertex.position.truncate() - vec2(0., 1.);
+            assert!((position.abs() - Vec2::ONE).length() < 1e-5, "{position}");
+            assert_eq!(vertex.position.z, 0.5);
+            assert_eq!(vertex.color, Color::from_rgb(0.5, 0.5, 0.5));
+        }
+    }
+}
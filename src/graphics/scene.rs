@@ -0,0 +1,323 @@
+use glam::Affine2;
+
+use super::*;
+
+/// Handle of a node in a [`Scene`], valid until the node is removed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeHandle(usize);
+
+/// Shapes placed by a transform relative to the parent node
+#[derive(Clone, Debug)]
+pub struct Node<T: Textures> {
+    pub gtransform: GTransform,
+    pub shapes: Vec<Shape<T>>,
+    parent: Option<NodeHandle>,
+    children: Vec<NodeHandle>,
+}
+
+impl<T: Textures> Default for Node<T> {
+    fn default() -> Self {
+        Self::new(GTransform::default())
+    }
+}
+
+impl<T: Textures> Node<T> {
+    pub fn new(gtransform: GTransform) -> Self {
+        Self {
+            gtransform,
+            shapes: vec![],
+            parent: None,
+            children: vec![],
+        }
+    }
+
+    pub fn add_shape(mut self, shape: Shape<T>) -> Self {
+        self.shapes.push(shape);
+        self
+    }
+
+    pub fn parent(&self) -> Option<NodeHandle> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeHandle] {
+        &self.children
+    }
+}
+
+/// Hierarchy of nodes whose transforms compose, so that a weapon attached to a ship follows
+/// it as it moves and turns. World transforms are composed as `Affine2`, which keeps the
+/// shear of rotated children under non-uniformly scaled parents.
+#[derive(Clone, Debug)]
+pub struct Scene<T: Textures> {
+    nodes: Vec<Option<Node<T>>>,
+}
+
+impl<T: Textures> Default for Scene<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Textures> Scene<T> {
+    pub fn new() -> Self {
+        Self { nodes: vec![] }
+    }
+
+    /// Nodes without a parent, or with one that doesn't exist, become roots
+    pub fn add_node(&mut self, mut node: Node<T>, parent: Option<NodeHandle>) -> NodeHandle {
+        let handle = NodeHandle(self.nodes.len());
+        node.parent = parent.filter(|parent| self.node(*parent).is_some());
+        node.children.clear();
+        if let Some(parent) = node.parent.and_then(|parent| self.node_mut(parent)) {
+            parent.children.push(handle);
+        }
+        self.nodes.push(Some(node));
+        handle
+    }
+
+    /// Children are removed with the node
+    pub fn remove_node(&mut self, handle: NodeHandle) -> Option<Node<T>> {
+        let node = self.nodes.get_mut(handle.0)?.take()?;
+        if let Some(parent) = node.parent.and_then(|parent| self.node_mut(parent)) {
+            parent.children.retain(|child| *child != handle);
+        }
+        let mut removed = node.children.clone();
+        while let Some(child) = removed.pop() {
+            if let Some(child) = self.nodes.get_mut(child.0).and_then(Option::take) {
+                removed.extend(child.children);
+            }
+        }
+        Some(node)
+    }
+
+    pub fn node(&self, handle: NodeHandle) -> Option<&Node<T>> {
+        self.nodes.get(handle.0)?.as_ref()
+    }
+
+    pub fn node_mut(&mut self, handle: NodeHandle) -> Option<&mut Node<T>> {
+        self.nodes.get_mut(handle.0)?.as_mut()
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeHandle, &Node<T>)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, node)| Some((NodeHandle(i), node.as_ref()?)))
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = NodeHandle> + '_ {
+        self.nodes()
+            .filter(|(_, node)| node.parent.is_none())
+            .map(|(handle, _)| handle)
+    }
+
+    /// Moves the node with its children under another parent, or makes it a root. The local
+    /// transform is kept, so the node moves along with the new parent.
+    pub fn set_parent(
+        &mut self,
+        handle: NodeHandle,
+        parent: Option<NodeHandle>,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(self.node(handle).is_some(), "Node doesn't exist");
+        if let Some(parent) = parent {
+            anyhow::ensure!(self.node(parent).is_some(), "Parent doesn't exist");
+            let mut ancestor = Some(parent);
+            while let Some(current) = ancestor {
+                anyhow::ensure!(current != handle, "Node can't become its own descendant");
+                ancestor = self.nodes[current.0].as_ref().and_then(|node| node.parent);
+            }
+        }
+
+        if let Some(old) = self.nodes[handle.0].as_ref().and_then(|node| node.parent) {
+            if let Some(old) = self.node_mut(old) {
+                old.children.retain(|child| *child != handle);
+            }
+        }
+        if let Some(parent) = parent.and_then(|parent| self.node_mut(parent)) {
+            parent.children.push(handle);
+        }
+        if let Some(node) = self.node_mut(handle) {
+            node.parent = parent;
+        }
+        Ok(())
+    }
+
+    /// Exact transform from the node's space to world space
+    pub fn world_affine(&self, handle: NodeHandle) -> Option<Affine2> {
+        let mut node = self.node(handle)?;
+        let mut affine = Affine2::from(node.gtransform);
+        while let Some(parent) = node.parent.and_then(|parent| self.node(parent)) {
+            affine = Affine2::from(parent.gtransform) * affine;
+            node = parent;
+        }
+        Some(affine)
+    }
+
+    /// World transform of the node, `None` if it shears, see [`GTransform::mul`]
+    pub fn world_gtransform(&self, handle: NodeHandle) -> Option<GTransform> {
+        self.world_affine(handle)?.try_into().ok()
+    }
+
+    /// Shapes of all nodes in world space, parents before their children
+    pub fn shapes(&self) -> Vec<Shape<T>> {
+        self.shapes_under(Affine2::IDENTITY)
+    }
+
+    fn shapes_under(&self, world: Affine2) -> Vec<Shape<T>> {
+        let mut shapes = vec![];
+        let mut stack = self.roots().map(|root| (root, world)).collect::<Vec<_>>();
+        stack.reverse();
+        while let Some((handle, parent)) = stack.pop() {
+            let Some(node) = self.node(handle) else {
+                continue;
+            };
+            let affine = parent * Affine2::from(node.gtransform);
+            shapes.extend(
+                node.shapes
+                    .iter()
+                    .map(|shape| shape.clone().apply_affine(affine)),
+            );
+            stack.extend(node.children.iter().rev().map(|child| (*child, affine)));
+        }
+        shapes
+    }
+
+    /// `camera` maps world space to screen space
+    pub fn draw(&self, graphics: &mut Graphics<T>, camera: GTransform) {
+        for shape in self.shapes_under(camera.into()) {
+            graphics.add_geometry(shape.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4, SQRT_2};
+
+    use glam::vec2;
+
+    use super::*;
+
+    type TestScene = Scene<TestTextures>;
+
+    fn node(gtransform: GTransform) -> Node<TestTextures> {
+        Node::new(gtransform)
+    }
+
+    fn assert_point(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 1e-5, "{a} instead of {b}");
+    }
+
+    #[test]
+    fn composes_world_transforms() {
+        let mut scene = TestScene::new();
+        let root_transform = GTransform::from_translation(vec2(10., 0.)).rotate(FRAC_PI_2);
+        let root = scene.add_node(node(root_transform), None);
+        let child = scene.add_node(node(GTransform::from_translation(Vec2::X)), Some(root));
+        let grandchild = scene.add_node(node(GTransform::from_inflation(2.)), Some(child));
+
+        // Scaled by the grandchild, moved by the child, then turned and moved by the root
+        let world = scene.world_affine(grandchild).unwrap();
+        assert_point(world.transform_point2(Vec2::X), vec2(10., 3.));
+        assert_point(world.transform_point2(Vec2::ZERO), vec2(10., 1.));
+        let gtransform = scene.world_gtransform(grandchild).unwrap();
+        assert_point(gtransform.center, vec2(10., 1.));
+        assert!((gtransform.rotation - FRAC_PI_2).abs() < 1e-5);
+        assert_point(gtransform.scale, Vec2::splat(2.));
+        assert!(scene.world_affine(NodeHandle(3)).is_none());
+    }
+
+    #[test]
+    fn keeps_the_shear_of_rotated_children() {
+        let mut scene = TestScene::new();
+        let parent = scene.add_node(node(GTransform::from_scale(vec2(2., 1.))), None);
+        let square = Shape::from_square();
+        let child = node(GTransform::default().rotate(FRAC_PI_4)).add_shape(square);
+        let child = scene.add_node(child, Some(parent));
+
+        let world = scene.world_affine(child).unwrap();
+        assert_point(world.transform_point2(Vec2::X), vec2(SQRT_2, FRAC_1_SQRT_2));
+        assert_point(
+            world.transform_point2(Vec2::Y),
+            vec2(-SQRT_2, FRAC_1_SQRT_2),
+        );
+        assert!(scene.world_gtransform(child).is_none());
+
+        // Shapes become parallelograms instead of rotated rectangles
+        let shapes = scene.shapes();
+        assert_point(shapes[0].points[2].0, vec2(0., SQRT_2));
+        assert!((shapes[0].area() - 2.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn removes_children_with_their_parent() {
+        let mut scene = TestScene::new();
+        let root = scene.add_node(Node::default(), None);
+        let a = scene.add_node(Node::default(), Some(root));
+        let b = scene.add_node(Node::default(), Some(a));
+        let c = scene.add_node(Node::default(), Some(root));
+        let d = scene.add_node(Node::default(), Some(b));
+
+        assert_eq!(scene.remove_node(a).unwrap().children(), [b]);
+        assert!([a, b, d].iter().all(|handle| scene.node(*handle).is_none()));
+        assert_eq!(scene.node(root).unwrap().children(), [c]);
+        assert_eq!(scene.nodes().count(), 2);
+        assert!(scene.remove_node(a).is_none());
+
+        // Handles of removed nodes aren't reused
+        let e = scene.add_node(Node::default(), Some(b));
+        assert_ne!(e, b);
+        assert_eq!(scene.node(e).unwrap().parent(), None);
+        assert_eq!(scene.roots().collect::<Vec<_>>(), [root, e]);
+    }
+
+    #[test]
+    fn reparents_without_cycles() {
+        let mut scene = TestScene::new();
+        let root = scene.add_node(node(GTransform::from_translation(Vec2::X)), None);
+        let a = scene.add_node(Node::default(), Some(root));
+        let b = scene.add_node(Node::default(), Some(a));
+        let other = scene.add_node(node(GTransform::from_translation(Vec2::Y)), None);
+
+        assert!(scene.set_parent(root, Some(b)).is_err());
+        assert!(scene.set_parent(a, Some(a)).is_err());
+        assert!(scene.set_parent(a, Some(NodeHandle(10))).is_err());
+        assert!(scene.set_parent(NodeHandle(10), None).is_err());
+        // Failed attempts leave the hierarchy alone
+        assert_eq!(scene.node(root).unwrap().parent(), None);
+        assert_eq!(scene.node(a).unwrap().children(), [b]);
+
+        scene.set_parent(a, Some(other)).unwrap();
+        assert!(scene.node(root).unwrap().children().is_empty());
+        assert_eq!(scene.node(other).unwrap().children(), [a]);
+        // The child comes along and follows its new grandparent
+        assert_point(
+            scene.world_affine(b).unwrap().transform_point2(Vec2::ZERO),
+            Vec2::Y,
+        );
+
+        scene.set_parent(a, None).unwrap();
+        assert_eq!(scene.roots().collect::<Vec<_>>(), [root, a, other]);
+        assert!(scene.node(other).unwrap().children().is_empty());
+    }
+
+    #[test]
+    fn lists_parents_before_children() {
+        // Every node gets a polygon with a different number of sides, in the expected order
+        let mut scene = TestScene::new();
+        let polygon = |sides| Node::default().add_shape(Shape::from_polygon(sides));
+        let first = scene.add_node(polygon(3), None);
+        let a = scene.add_node(polygon(4), Some(first));
+        scene.add_node(polygon(7), None);
+        scene.add_node(polygon(5), Some(a));
+        scene.add_node(polygon(6), Some(first));
+
+        let sides = scene
+            .shapes()
+            .iter()
+            .map(|shape| shape.points.len())
+            .collect::<Vec<_>>();
+        assert_eq!(sides, [3, 4, 5, 6, 7]);
+    }
+}
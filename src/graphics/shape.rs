@@ -1,11 +1,104 @@
-use glam::{vec2, vec3};
+use glam::{vec2, vec3, DVec2};
 
 use super::*;
 
+mod boolean;
+
+mod gradient;
+pub use gradient::Gradient;
+
+mod path;
+pub use path::Path;
+
+mod polyline;
+pub use polyline::{LineCap, LineJoin, Polyline};
+
+mod query;
+pub use query::{Aabb, RayHit};
+
+mod segments;
+pub use segments::Segments;
+
+mod stroke;
+pub use stroke::StrokeAlignment;
+
+mod svg;
+
+mod triangulate;
+pub use triangulate::{FillRule, TriangulationError};
+
+/// Positive for counter-clockwise contours
+fn signed_area(contour: &[Vec2]) -> f32 {
+    let doubled: f32 = contour
+        .iter()
+        .zip(contour.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum();
+    doubled / 2.
+}
+
+/// `segments + 1` points on the ellipse around `center`, from `start_angle` rotating
+/// counter-clockwise by `sweep` radians
+fn arc_points(
+    center: Vec2,
+    radii: Vec2,
+    start_angle: f32,
+    sweep: f32,
+    segments: usize,
+) -> impl DoubleEndedIterator<Item = Vec2> {
+    let segments = segments.max(1);
+    (0..=segments).map(move |i| {
+        let angle = start_angle + sweep * i as f32 / segments as f32;
+        center + Vec2::from_angle(angle) * radii
+    })
+}
+
+/// Number of times the closed contour winds counter-clockwise around `p`, in double
+/// precision so that points close to an edge are classified consistently
+fn winding_number(contour: impl Iterator<Item = DVec2> + Clone, p: DVec2) -> i32 {
+    contour
+        .clone()
+        .zip(contour.cycle().skip(1))
+        .map(|(a, b)| {
+            let side = (b - a).perp_dot(p - a);
+            match (a.y <= p.y, b.y <= p.y) {
+                (true, false) if side > 0. => 1,
+                (false, true) if side < 0. => -1,
+                _ => 0,
+            }
+        })
+        .sum()
+}
+
+/// The contour without consecutive points at the same `position`, also across the closing
+/// edge
+fn distinct<P: Copy>(contour: impl IntoIterator<Item = P>, position: impl Fn(P) -> Vec2) -> Vec<P> {
+    let same = |a: P, b: P| position(a).distance(position(b)) <= f32::EPSILON;
+    let mut distinct: Vec<P> = vec![];
+    for point in contour {
+        if distinct.last().map_or(true, |last| !same(*last, point)) {
+            distinct.push(point);
+        }
+    }
+    while distinct.len() > 1 && same(distinct[0], distinct[distinct.len() - 1]) {
+        distinct.pop();
+    }
+    distinct
+}
+
 #[derive(Clone, Debug)]
 pub struct Shape<T: Textures> {
     pub points: Vec<(Vec2, Vec2)>,
+    /// Further contours cut out of or, depending on the fill rule, added to the outline
+    pub holes: Vec<Vec<(Vec2, Vec2)>>,
+    fill_rule: FillRule,
+    /// Triangles over `points`, when `None` the points form an outline which gets filled
+    indices: Option<Vec<u32>>,
+    /// Colour of every point including holes, multiplied with `color`
+    point_colors: Option<Vec<Color>>,
     texture: T,
+    /// Region of the texture the texture coordinates of the points refer to
+    uv_rect: (Vec2, Vec2),
     color: Color,
     z: f32,
 }
@@ -14,20 +107,36 @@ impl<T: Textures> Shape<T> {
     pub fn new(points: Vec<Vec2>) -> Self {
         Self {
             points: points.into_iter().map(|p| (p, Vec2::ZERO)).collect(),
+            holes: vec![],
+            fill_rule: FillRule::default(),
+            indices: None,
+            point_colors: None,
             texture: Default::default(),
+            uv_rect: (Vec2::ZERO, Vec2::ONE),
             color: Color::WHITE,
             z: 0.,
         }
         .update_texture_coords()
     }
 
-    pub fn from_circle(segments: usize) -> Self {
-        let mut points = Vec::with_capacity(segments);
-        for i in 0..segments {
-            let angle = (i as f32 / segments as f32) * 2. * std::f32::consts::PI;
-            points.push(Vec2::new(angle.cos(), angle.sin()));
+    /// Already triangulated shape, every three indices into `points` form a triangle
+    pub fn from_mesh(points: Vec<Vec2>, indices: Vec<u32>) -> Self {
+        Self {
+            indices: Some(indices),
+            ..Self::new(points)
         }
-        Self::new(points)
+    }
+
+    /// Circle of radius 1, approximated by a regular polygon
+    pub fn from_circle(segments: impl Into<Segments>) -> Self {
+        Self::from_ellipse(Vec2::ONE, segments)
+    }
+    /// Ellipse centered at the origin
+    pub fn from_ellipse(radii: Vec2, segments: impl Into<Segments>) -> Self {
+        let tau = std::f32::consts::TAU;
+        let segments = segments.into().count(radii.max_element(), tau);
+        let points = arc_points(Vec2::ZERO, radii, 0., tau, segments);
+        Self::new(points.take(segments).collect())
     }
     pub fn from_square() -> Self {
         Self::new(vec![
@@ -45,6 +154,19 @@ impl<T: Textures> Shape<T> {
             Vec2::new(-0.5, 0.5),
         ])
     }
+    /// Upright quad centered at the origin showing the whole texture, `pixels_per_unit` pixels
+    /// of the image span one unit
+    pub fn from_texture(texture: impl Into<T>, pixels_per_unit: f32) -> Self {
+        let texture = texture.into();
+        let size = image_size(&texture.bytes()).unwrap_or_else(|err| {
+            log::warn!("Can't read the size of a texture: {err}");
+            UVec2::ONE
+        });
+        Self::from_square_centered()
+            .apply(GTransform::from_scale(size.as_vec2() / pixels_per_unit))
+            .flip_y()
+            .set_texture(texture)
+    }
     pub fn from_triangle() -> Self {
         Self::new(vec![vec2(0., 0.5), vec2(-0.5, -0.5), vec2(0.5, -0.5)])
     }
@@ -57,44 +179,283 @@ impl<T: Textures> Shape<T> {
 
         square.apply(gtransform)
     }
+    /// Connected line segments through `points`, see [`Polyline`] for closed loops and
+    /// varying width or colour along the line
+    pub fn from_polyline(points: &[Vec2], thickness: f32, join: LineJoin, cap: LineCap) -> Self {
+        Polyline::new(points.to_vec())
+            .set_thickness(thickness)
+            .set_join(join)
+            .set_cap(cap)
+            .into()
+    }
+
+    /// Concatenates already triangulated shapes, see [`Shape::from_mesh`]
+    fn from_meshes(meshes: impl IntoIterator<Item = Shape<T>>) -> Self {
+        let mut points = vec![];
+        let mut indices = vec![];
+        for mesh in meshes {
+            let offset = points.len() as u32;
+            indices.extend(mesh.indices.into_iter().flatten().map(|i| i + offset));
+            points.extend(mesh.points.into_iter().map(|(p, _)| p));
+        }
+        Self::from_mesh(points, indices)
+    }
+
+    /// Regular polygon of radius 1 with a corner on the positive x axis
     pub fn from_polygon(sides: usize) -> Self {
-        let mut points = Vec::with_capacity(sides);
-        for i in 0..sides {
-            let angle = (i as f32 / sides as f32) * 2. * std::f32::consts::PI;
-            points.push(Vec2::new(angle.cos(), angle.sin()));
+        Self::from_ellipse(Vec2::ONE, sides)
+    }
+
+    /// Rectangle of `size` centered at the origin. The corner radii are given
+    /// counter-clockwise starting at the bottom left corner, and all of them shrink by the
+    /// same factor when neighbouring ones don't fit. `segments` is per corner.
+    pub fn from_rounded_rect(size: Vec2, radii: [f32; 4], segments: impl Into<Segments>) -> Self {
+        let segments = segments.into();
+        let half = size / 2.;
+        let radii = radii.map(|radius| radius.max(0.));
+        let sides = [
+            (0, 1, size.x),
+            (1, 2, size.y),
+            (2, 3, size.x),
+            (3, 0, size.y),
+        ];
+        let fit = sides
+            .into_iter()
+            .map(|(a, b, side)| side / (radii[a] + radii[b]))
+            .fold(1., f32::min);
+
+        let pi = std::f32::consts::PI;
+        let corners = [
+            (vec2(-1., -1.), pi),
+            (vec2(1., -1.), 1.5 * pi),
+            (vec2(1., 1.), 0.),
+            (vec2(-1., 1.), 0.5 * pi),
+        ];
+        // Arcs meet without a straight edge between them when the radii fill a side
+        let epsilon = size.max_element() * f32::EPSILON * 4.;
+        let mut points: Vec<Vec2> = vec![];
+        for ((sign, start_angle), radius) in corners.into_iter().zip(radii) {
+            let radius = radius * fit;
+            let corner = if radius > 0. {
+                let center = sign * (half - radius);
+                let radii = Vec2::splat(radius);
+                let segments = segments.count(radius, pi / 2.);
+                arc_points(center, radii, start_angle, pi / 2., segments).collect()
+            } else {
+                vec![sign * half]
+            };
+            for point in corner {
+                if points.last().map_or(true, |p| p.distance(point) > epsilon) {
+                    points.push(point);
+                }
+            }
+        }
+        if points.len() > 1 && points[0].distance(points[points.len() - 1]) <= epsilon {
+            points.pop();
         }
         Self::new(points)
     }
 
+    /// Band of `thickness` along the circle of `radius` around the origin, from
+    /// `start_angle` rotating counter-clockwise by `sweep` radians
+    pub fn from_arc(
+        radius: f32,
+        thickness: f32,
+        start_angle: f32,
+        sweep: f32,
+        segments: impl Into<Segments>,
+    ) -> Self {
+        let outer = radius + thickness / 2.;
+        let inner = (radius - thickness / 2.).max(0.);
+        if sweep.abs() >= std::f32::consts::TAU {
+            return Self::from_ring(outer, inner, segments);
+        }
+
+        let segments = segments.into().count(outer, sweep);
+        let arc = |radius| {
+            arc_points(
+                Vec2::ZERO,
+                Vec2::splat(radius),
+                start_angle,
+                sweep,
+                segments,
+            )
+        };
+        Self::new(arc(outer).chain(arc(inner).rev()).collect())
+    }
+
+    /// Pie slice of the circle of `radius` around the origin, see [`Shape::from_arc`]
+    pub fn from_sector(
+        radius: f32,
+        start_angle: f32,
+        sweep: f32,
+        segments: impl Into<Segments>,
+    ) -> Self {
+        if sweep.abs() >= std::f32::consts::TAU {
+            return Self::from_ellipse(Vec2::splat(radius), segments);
+        }
+        let segments = segments.into().count(radius, sweep);
+        let radii = Vec2::splat(radius);
+        let arc = arc_points(Vec2::ZERO, radii, start_angle, sweep, segments);
+        Self::new(std::iter::once(Vec2::ZERO).chain(arc).collect())
+    }
+
+    /// Annulus around the origin
+    pub fn from_ring(outer_radius: f32, inner_radius: f32, segments: impl Into<Segments>) -> Self {
+        let segments = segments.into();
+        let hole = Self::from_ellipse(Vec2::splat(inner_radius), segments);
+        Self::from_ellipse(Vec2::splat(outer_radius), segments)
+            .add_hole(hole.points.into_iter().map(|(p, _)| p).collect())
+    }
+
+    /// Star with `points` tips on `outer_radius` and one of them facing up
+    pub fn from_star(points: usize, outer_radius: f32, inner_radius: f32) -> Self {
+        let step = std::f32::consts::PI / points as f32;
+        Self::new(
+            (0..points * 2)
+                .map(|i| {
+                    let radius = if i % 2 == 0 {
+                        outer_radius
+                    } else {
+                        inner_radius
+                    };
+                    Vec2::from_angle(std::f32::consts::FRAC_PI_2 + step * i as f32) * radius
+                })
+                .collect(),
+        )
+    }
+
+    /// Rectangle of `length` along the x axis centered at the origin, with half circles of
+    /// `radius` at both ends. `segments` is per half circle.
+    pub fn from_capsule(length: f32, radius: f32, segments: impl Into<Segments>) -> Self {
+        let pi = std::f32::consts::PI;
+        let segments = segments.into().count(radius, pi);
+        let radii = Vec2::splat(radius);
+        let end = vec2(length / 2., 0.);
+        let right = arc_points(end, radii, -pi / 2., pi, segments);
+        let left = arc_points(-end, radii, pi / 2., pi, segments);
+        Self::new(right.chain(left).collect())
+    }
+
+    /// Adds a contour which is cut out of the shape with the default fill rule. Holes must
+    /// not cross the outline or each other, but may contain further islands.
+    pub fn add_hole(mut self, points: Vec<Vec2>) -> Self {
+        if let Some(colors) = &mut self.point_colors {
+            colors.extend(std::iter::repeat(Color::WHITE).take(points.len()));
+        }
+        self.holes
+            .push(points.into_iter().map(|p| (p, Vec2::ZERO)).collect());
+        self.update_texture_coords()
+    }
+
+    pub fn set_fill_rule(mut self, fill_rule: FillRule) -> Self {
+        self.fill_rule = fill_rule;
+        self
+    }
+
+    /// The outline followed by the holes, without consecutive duplicate points
+    fn contours(&self) -> impl Iterator<Item = Vec<Vec2>> + '_ {
+        std::iter::once(&self.points)
+            .chain(&self.holes)
+            .map(|contour| distinct(contour.iter().map(|(p, _)| *p), |p| p))
+    }
+
+    /// All points, the outline followed by the holes
+    fn all_points_mut(&mut self) -> impl Iterator<Item = &mut (Vec2, Vec2)> {
+        self.points
+            .iter_mut()
+            .chain(self.holes.iter_mut().flatten())
+    }
+
     /// Rotation is after scale
     pub fn apply(mut self, gtransform: GTransform) -> Shape<T> {
-        for (point, _) in &mut self.points {
+        for (point, _) in self.all_points_mut() {
             *point = gtransform.transform(*point);
         }
         self
     }
 
+    /// Like [`Shape::apply`] for any affine transform, including the shear of a rotated
+    /// non-uniform scale
+    pub fn apply_affine(mut self, affine: glam::Affine2) -> Shape<T> {
+        for (point, _) in self.all_points_mut() {
+            *point = affine.transform_point2(*point);
+        }
+        self
+    }
+
     pub fn update_texture_coords(mut self) -> Self {
         let mut left_lower_point = Vec2::new(std::f32::MAX, std::f32::MAX);
         let mut right_upper_point = Vec2::new(std::f32::MIN, std::f32::MIN);
 
-        for (point, _) in &self.points {
+        for (point, _) in self.all_points_mut() {
             left_lower_point = left_lower_point.min(*point);
             right_upper_point = right_upper_point.max(*point);
         }
 
-        for (point, tex_coord) in &mut self.points {
+        for (point, tex_coord) in self.all_points_mut() {
             *tex_coord = (*point - left_lower_point) / (right_upper_point - left_lower_point);
         }
 
         self
     }
 
-    pub fn set_texture(mut self, t: T) -> Self {
-        self.texture = t;
+    /// Maps texture coordinates from 0 to 1 to the top left and bottom right of a part of the
+    /// texture, like a sprite in an atlas or [`Tileset::uv_rect`](crate::tilemap::Tileset::uv_rect)
+    pub fn set_uv_rect(mut self, uv_rect: (Vec2, Vec2)) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
+    pub fn flip_x(mut self) -> Self {
+        for (_, tex_coord) in self.all_points_mut() {
+            tex_coord.x = 1. - tex_coord.x;
+        }
+        self
+    }
+
+    pub fn flip_y(mut self) -> Self {
+        for (_, tex_coord) in self.all_points_mut() {
+            tex_coord.y = 1. - tex_coord.y;
+        }
+        self
+    }
+
+    /// Repeats the texture or its uv rect the given number of times across the current
+    /// texture coordinates
+    pub fn tile(mut self, repeat_x: f32, repeat_y: f32) -> Self {
+        for (_, tex_coord) in self.all_points_mut() {
+            *tex_coord *= Vec2::new(repeat_x, repeat_y);
+        }
+        self
+    }
+
+    /// Texture coordinates from the positions of the points, one repetition of the texture
+    /// covers `size` starting at the origin. Neighbouring shapes line up when projected after
+    /// `apply`.
+    pub fn project_uv(mut self, size: Vec2) -> Self {
+        for (point, tex_coord) in self.all_points_mut() {
+            *tex_coord = *point / size;
+        }
+        self
+    }
+
+    /// Transforms the texture coordinates around the middle of the texture, so that scaling
+    /// zooms in and rotating turns the texture on the shape in place
+    pub fn transform_uv(mut self, gtransform: GTransform) -> Self {
+        let middle = Vec2::splat(0.5);
+        for (_, tex_coord) in self.all_points_mut() {
+            *tex_coord = gtransform.transform(*tex_coord - middle) + middle;
+        }
+        self
+    }
+
+    /// Accepts textures of any set combined into `T` with [`texture_set!`](crate::texture_set)
+    pub fn set_texture(mut self, t: impl Into<T>) -> Self {
+        self.texture = t.into();
         self
     }
-    
+
     pub fn reset_texture(mut self) -> Self {
         self.texture = T::default();
         self
@@ -105,6 +466,57 @@ impl<T: Textures> Shape<T> {
         self
     }
 
+    /// One colour per point, holes included, interpolated across the shape and tinted by `color`
+    pub fn set_point_colors(mut self, colors: Vec<Color>) -> Self {
+        let count = self.points.len() + self.holes.iter().map(Vec::len).sum::<usize>();
+        assert_eq!(colors.len(), count, "expected one colour per point");
+        self.point_colors = Some(colors);
+        self
+    }
+
+    pub fn indices(&self) -> Option<&[u32]> {
+        self.indices.as_deref()
+    }
+
+    /// Triangles over `points` followed by the points of the holes, in counter-clockwise
+    /// order. Contours may be concave and wind either way, but must not intersect.
+    pub fn triangulate(&self) -> Result<Vec<u32>, TriangulationError> {
+        if let Some(indices) = &self.indices {
+            return Ok(indices.clone());
+        }
+
+        let mut points = Vec::with_capacity(self.points.len());
+        let mut contours = vec![];
+        for contour in std::iter::once(&self.points).chain(&self.holes) {
+            let start = points.len() as u32;
+            points.extend(contour.iter().map(|(p, _)| *p));
+            contours.push((start..points.len() as u32).collect());
+        }
+        triangulate::triangulate(&points, contours, self.fill_rule)
+    }
+
+    pub fn try_into_geometry(self) -> Result<Geometry<T>, TriangulationError> {
+        let indices = self.triangulate()?;
+
+        let mut vertices: Vec<Vertex<T>> = self
+            .points
+            .into_iter()
+            .chain(self.holes.into_iter().flatten())
+            .map(|(p, tc)| (vec3(p.x, p.y, self.z), tc).into())
+            .collect();
+
+        for (i, vertex) in vertices.iter_mut().enumerate() {
+            vertex.texture = self.texture.clone();
+            vertex.texture_rect = self.uv_rect;
+            vertex.color = match &self.point_colors {
+                Some(colors) => colors[i] * self.color,
+                None => self.color,
+            };
+        }
+
+        Ok((vertices, indices))
+    }
+
     pub fn set_z(mut self, z: f32) -> Self {
         self.z = z;
         self
@@ -112,27 +524,136 @@ impl<T: Textures> Shape<T> {
 }
 
 impl<T: Textures> Into<(Vec<Vertex<T>>, Vec<u32>)> for Shape<T> {
+    /// Shapes which can't be triangulated are logged and produce no triangles
     fn into(self) -> (Vec<Vertex<T>>, Vec<u32>) {
-        let points = self
-            .points
-            .into_iter()
-            .map(|(p, tc)| (vec3(p.x, p.y, self.z), tc))
-            .collect::<Vec<_>>();
-        let mut vertices: Vec<Vertex<T>> = vec![points[0].into(), points[1].into()];
-        let mut indices = vec![];
+        self.try_into_geometry().unwrap_or_else(|err| {
+            log::warn!("Skipping shape: {err}");
+            (vec![], vec![])
+        })
+    }
+}
 
-        for i in 2..points.len() {
-            vertices.push(points[i].into());
-            indices.push(0);
-            indices.push(i as u32 - 1);
-            indices.push(i as u32);
-        }
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
 
-        for vertex in &mut vertices {
-            vertex.texture = self.texture.clone();
-            vertex.color = self.color;
+    use super::*;
+
+    #[test]
+    fn winding_numbers() {
+        let square = [vec2(0., 0.), vec2(2., 0.), vec2(2., 2.), vec2(0., 2.)].map(|p| p.as_dvec2());
+        let inside = DVec2::ONE;
+        assert_eq!(winding_number(square.iter().copied(), inside), 1);
+        assert_eq!(winding_number(square.iter().rev().copied(), inside), -1);
+        assert_eq!(winding_number(square.iter().copied(), DVec2::splat(3.)), 0);
+        assert_eq!(
+            winding_number(square.iter().chain(&square).copied(), inside),
+            2
+        );
+        assert_eq!(winding_number([].into_iter(), inside), 0);
+    }
+
+    #[test]
+    fn distinct_points() {
+        let points = [0., 0., 1., 1., 1. + f32::EPSILON / 2., 2., 0.].map(|x| vec2(x, x * x));
+        assert_eq!(distinct(0..7, |i| points[i]), vec![0, 2, 5]);
+        assert_eq!(distinct([0, 1], |i| points[i]), vec![0]);
+        assert!(distinct([], |i: usize| points[i]).is_empty());
+    }
+
+    type TestShape = Shape<TestTextures>;
+
+    const WHOLE: (Vec2, Vec2) = (Vec2::ZERO, Vec2::ONE);
+    const CORNERS: [Vec2; 4] = [Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y];
+
+    /// Texture coordinates of the vertices, which must all map to `uv_rect`
+    fn texture_coords(shape: TestShape, uv_rect: (Vec2, Vec2)) -> Vec<Vec2> {
+        let (vertices, _) = shape.try_into_geometry().unwrap();
+        assert!(vertices.iter().all(|vertex| vertex.texture_rect == uv_rect));
+        vertices
+            .iter()
+            .map(|vertex| vertex.texture_coords)
+            .collect()
+    }
+
+    fn rect() -> TestShape {
+        TestShape::from_square().apply(GTransform::from_scale(vec2(4., 2.)))
+    }
+
+    #[test]
+    fn textured_quads() {
+        // The fixture of 4 × 2 pixels spans 2 × 1 units, with the top row of the image up
+        let quad = TestShape::from_texture(TestTextures::Tiles, 2.);
+        assert_eq!(quad.texture, TestTextures::Tiles);
+        let aabb = quad.aabb().unwrap();
+        assert_eq!((aabb.min, aabb.max), (vec2(-1., -0.5), vec2(1., 0.5)));
+        let uv = |point: Vec2| quad.points.iter().find(|(p, _)| *p == point).unwrap().1;
+        assert_eq!(uv(vec2(-1., 0.5)), Vec2::ZERO);
+        assert_eq!(uv(vec2(1., -0.5)), Vec2::ONE);
+
+        // Images without a readable size get a square
+        let aabb = TestShape::from_texture(TestTextures::White, 4.)
+            .aabb()
+            .unwrap();
+        assert_eq!(
+            (aabb.min, aabb.max),
+            (Vec2::splat(-0.125), Vec2::splat(0.125))
+        );
+    }
+
+    #[test]
+    fn uv_rects_and_flips() {
+        assert_eq!(texture_coords(rect(), WHOLE), CORNERS);
+        let sprite = (vec2(0.25, 0.5), vec2(0.5, 0.75));
+        assert_eq!(texture_coords(rect().set_uv_rect(sprite), sprite), CORNERS);
+
+        let flipped_x = CORNERS.map(|uv| vec2(1. - uv.x, uv.y));
+        assert_eq!(texture_coords(rect().flip_x(), WHOLE), flipped_x);
+        let flipped_y = CORNERS.map(|uv| vec2(uv.x, 1. - uv.y));
+        let shape = rect().set_uv_rect(sprite).flip_y();
+        assert_eq!(texture_coords(shape, sprite), flipped_y);
+        assert_eq!(texture_coords(rect().flip_x().flip_x(), WHOLE), CORNERS);
+
+        // Holes are flipped along with the outline
+        let hole = vec![vec2(1., 0.5), vec2(1., 1.5), vec2(3., 1.5), vec2(3., 0.5)];
+        let framed = texture_coords(rect().add_hole(hole).flip_y(), WHOLE);
+        assert_eq!(framed[4..6], [vec2(0.25, 0.75), vec2(0.25, 0.25)]);
+    }
+
+    #[test]
+    fn tiles_and_projects() {
+        let tiled = texture_coords(rect().tile(3., 0.5), WHOLE);
+        assert_eq!(tiled, CORNERS.map(|uv| uv * vec2(3., 0.5)));
+
+        // Projected coordinates continue across neighbouring shapes
+        let shape = rect().apply(GTransform::from_translation(vec2(4., 0.)));
+        let projected = texture_coords(shape.project_uv(Vec2::splat(2.)), WHOLE);
+        assert_eq!(
+            projected,
+            CORNERS.map(|uv| uv * vec2(2., 1.) + vec2(2., 0.))
+        );
+        let shape = rect().project_uv(vec2(8., 4.)).tile(2., 2.);
+        assert_eq!(texture_coords(shape, WHOLE), CORNERS);
+    }
+
+    #[test]
+    fn transforms_uvs_around_the_middle() {
+        let zoomed = rect().transform_uv(GTransform::from_inflation(0.5));
+        assert_eq!(
+            texture_coords(zoomed, WHOLE),
+            CORNERS.map(|uv| uv / 2. + 0.25)
+        );
+
+        let turned = rect().transform_uv(GTransform::default().rotate(FRAC_PI_2));
+        let expected = [Vec2::X, Vec2::ONE, Vec2::Y, Vec2::ZERO];
+        for (uv, expected) in texture_coords(turned, WHOLE).into_iter().zip(expected) {
+            assert!(uv.distance(expected) < 1e-6, "{uv} instead of {expected}");
         }
 
-        (vertices, indices)
+        let shifted = rect().transform_uv(GTransform::from_translation(vec2(0.5, 0.)));
+        assert_eq!(
+            texture_coords(shifted, WHOLE),
+            CORNERS.map(|uv| uv + vec2(0.5, 0.))
+        );
     }
 }
@@ -0,0 +1,411 @@
+use std::collections::{HashMap, HashSet};
+
+use glam::DVec2;
+
+use super::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operation {
+    Union,
+    Intersection,
+    Difference,
+    Xor,
+}
+
+impl Operation {
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            Self::Union => a || b,
+            Self::Intersection => a && b,
+            Self::Difference => a && !b,
+            Self::Xor => a != b,
+        }
+    }
+}
+
+/// Which winding numbers count as filled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Winding {
+    EvenOdd,
+    NonZero,
+    /// Drops the areas offset contours wind around clockwise where an inset collapsed
+    Positive,
+}
+
+impl From<FillRule> for Winding {
+    fn from(fill_rule: FillRule) -> Self {
+        match fill_rule {
+            FillRule::EvenOdd => Self::EvenOdd,
+            FillRule::NonZero => Self::NonZero,
+        }
+    }
+}
+
+/// Filled area of a shape, in double precision to classify points close to its edges
+struct Region {
+    contours: Vec<Vec<DVec2>>,
+    winding: Winding,
+}
+
+impl Region {
+    fn new<T: Textures>(shape: &Shape<T>) -> Self {
+        match &shape.indices {
+            // Triangles of meshes may overlap, so they must not cancel out by winding
+            // opposite ways
+            Some(indices) => Self {
+                contours: indices
+                    .chunks_exact(3)
+                    .map(|triangle| {
+                        let mut triangle = triangle
+                            .iter()
+                            .map(|i| shape.points[*i as usize].0.as_dvec2())
+                            .collect::<Vec<_>>();
+                        if (triangle[1] - triangle[0]).perp_dot(triangle[2] - triangle[0]) < 0. {
+                            triangle.reverse();
+                        }
+                        triangle
+                    })
+                    .collect(),
+                winding: Winding::NonZero,
+            },
+            None => Self {
+                contours: shape
+                    .contours()
+                    .map(|contour| contour.iter().map(Vec2::as_dvec2).collect())
+                    .collect(),
+                winding: shape.fill_rule.into(),
+            },
+        }
+    }
+
+    fn empty() -> Self {
+        Self {
+            contours: vec![],
+            winding: Winding::NonZero,
+        }
+    }
+
+    fn contains(&self, p: DVec2) -> bool {
+        let winding: i32 = self
+            .contours
+            .iter()
+            .map(|contour| winding_number(contour.iter().copied(), p))
+            .sum();
+        match self.winding {
+            Winding::EvenOdd => winding % 2 != 0,
+            Winding::NonZero => winding != 0,
+            Winding::Positive => winding > 0,
+        }
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (DVec2, DVec2)> + '_ {
+        self.contours.iter().flat_map(|contour| {
+            let n = contour.len();
+            (0..n).map(move |i| (contour[i], contour[(i + 1) % n]))
+        })
+    }
+}
+
+/// Points where the edges meet, including the end points of collinear overlaps
+fn intersections(a0: DVec2, a1: DVec2, b0: DVec2, b1: DVec2) -> Vec<DVec2> {
+    let (r, s) = (a1 - a0, b1 - b0);
+    let offset = b0 - a0;
+    let denominator = r.perp_dot(s);
+
+    if denominator.abs() > 1e-12 * r.length() * s.length() {
+        let t = offset.perp_dot(s) / denominator;
+        let u = offset.perp_dot(r) / denominator;
+        return match (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u) {
+            true => vec![a0 + r * t],
+            false => vec![],
+        };
+    }
+    if offset.perp_dot(r).abs() > 1e-12 * r.length() * offset.length() {
+        return vec![];
+    }
+
+    let on = |p: DVec2, q0: DVec2, q1: DVec2| {
+        let d = q1 - q0;
+        (0. ..=1.).contains(&((p - q0).dot(d) / d.length_squared()))
+    };
+    [a0, a1]
+        .into_iter()
+        .filter(|p| on(*p, b0, b1))
+        .chain([b0, b1].into_iter().filter(|p| on(*p, a0, a1)))
+        .collect()
+}
+
+/// Merges points closer than `epsilon`, so that edges meeting at a computed intersection
+/// share the exact same vertex
+struct Vertices {
+    points: Vec<DVec2>,
+    /// Points in cells of `epsilon` size, merged points are at most one cell apart
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    epsilon: f64,
+}
+
+impl Vertices {
+    fn new(epsilon: f64) -> Self {
+        Self {
+            points: vec![],
+            cells: HashMap::new(),
+            epsilon,
+        }
+    }
+
+    fn index(&mut self, p: DVec2) -> usize {
+        let cell = (p / self.epsilon).floor();
+        let (x, y) = (cell.x as i64, cell.y as i64);
+        let existing = (x.saturating_sub(1)..=x.saturating_add(1))
+            .flat_map(|x| (y.saturating_sub(1)..=y.saturating_add(1)).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(|i| self.points[*i].distance(p) <= self.epsilon)
+            .min();
+
+        existing.unwrap_or_else(|| {
+            self.points.push(p);
+            self.cells
+                .entry((x, y))
+                .or_default()
+                .push(self.points.len() - 1);
+            self.points.len() - 1
+        })
+    }
+}
+
+/// Contours around the area where `operation` holds, counter-clockwise around filled areas
+/// and clockwise around holes. Runs in quadratic time in the number of edges.
+fn combine(a: &Region, b: &Region, operation: Operation) -> Vec<Vec<Vec2>> {
+    let edges = a
+        .edges()
+        .chain(b.edges())
+        .filter(|(p, q)| p != q)
+        .collect::<Vec<_>>();
+    let (min, max) = edges.iter().fold(
+        (DVec2::splat(f64::MAX), DVec2::splat(f64::MIN)),
+        |(min, max), (p, q)| (min.min(p.min(*q)), max.max(p.max(*q))),
+    );
+    let scale = (max - min).max_element().max(f64::MIN_POSITIVE);
+
+    // Split all edges where they meet
+    let mut splits = edges.iter().map(|(p, q)| vec![*p, *q]).collect::<Vec<_>>();
+    for (i, &(a0, a1)) in edges.iter().enumerate() {
+        for (j, &(b0, b1)) in edges.iter().enumerate().skip(i + 1) {
+            let disjoint = a0.max(a1).cmplt(b0.min(b1)).any() || b0.max(b1).cmplt(a0.min(a1)).any();
+            if disjoint {
+                continue;
+            }
+            for p in intersections(a0, a1, b0, b1) {
+                splits[i].push(p);
+                splits[j].push(p);
+            }
+        }
+    }
+
+    let mut vertices = Vertices::new(scale * 1e-9);
+    let mut segments = HashSet::new();
+    for (split, (p, q)) in splits.iter_mut().zip(&edges) {
+        let direction = *q - *p;
+        split.sort_by(|a, b| {
+            (*a - *p)
+                .dot(direction)
+                .total_cmp(&(*b - *p).dot(direction))
+        });
+        let indices = split.iter().map(|p| vertices.index(*p)).collect::<Vec<_>>();
+        for pair in indices.windows(2) {
+            if pair[0] != pair[1] {
+                segments.insert((pair[0].min(pair[1]), pair[0].max(pair[1])));
+            }
+        }
+    }
+    let points = vertices.points;
+
+    // Keep the segments separating the result from the rest, with the result to their left
+    let inside = |p: DVec2| operation.apply(a.contains(p), b.contains(p));
+    let mut kept = vec![];
+    for (start, end) in segments {
+        let direction = points[end] - points[start];
+        let length = direction.length();
+        let middle = (points[start] + points[end]) / 2.;
+        let normal = direction.perp() / length * (length * 1e-4).min(scale * 1e-7);
+        match (inside(middle + normal), inside(middle - normal)) {
+            (true, false) => kept.push((start, end)),
+            (false, true) => kept.push((end, start)),
+            _ => {}
+        }
+    }
+
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, (start, _)) in kept.iter().enumerate() {
+        outgoing.entry(*start).or_default().push(i);
+    }
+
+    // Taking the leftmost turn at shared corners keeps touching contours apart
+    let mut used = vec![false; kept.len()];
+    let mut contours = vec![];
+    for first_edge in 0..kept.len() {
+        if used[first_edge] {
+            continue;
+        }
+        used[first_edge] = true;
+        let (first, mut current) = kept[first_edge];
+        let mut direction = points[current] - points[first];
+        let mut contour = vec![points[first]];
+
+        while current != first {
+            contour.push(points[current]);
+            let turn =
+                |edge: &usize| direction.angle_between(points[kept[*edge].1] - points[current]);
+            let Some(next) = outgoing[&current]
+                .iter()
+                .copied()
+                .filter(|edge| !used[*edge])
+                .max_by(|a, b| turn(a).total_cmp(&turn(b)))
+            else {
+                // Rounding left a dead end, the partial contour would cut into the result
+                log::warn!("Dropping an open contour of a boolean operation");
+                contour.clear();
+                break;
+            };
+            used[next] = true;
+            direction = points[kept[next].1] - points[current];
+            current = kept[next].1;
+        }
+
+        if contour.len() >= 3 {
+            contours.push(contour.into_iter().map(|p| p.as_vec2()).collect());
+        }
+    }
+    contours
+}
+
+/// The area the contours wind around counter-clockwise more often than clockwise, as
+/// contours without crossings
+pub(super) fn positive_area(contours: &[Vec<Vec2>]) -> Vec<Vec<Vec2>> {
+    let region = Region {
+        contours: contours
+            .iter()
+            .map(|contour| contour.iter().map(Vec2::as_dvec2).collect())
+            .collect(),
+        winding: Winding::Positive,
+    };
+    combine(&region, &Region::empty(), Operation::Union)
+}
+
+impl<T: Textures> Shape<T> {
+    /// Contours around the filled area, with the fill rule, overlapping triangles of meshes
+    /// and self-intersections resolved. Filled areas wind counter-clockwise and holes
+    /// clockwise.
+    pub(super) fn outline(&self) -> Vec<Vec<Vec2>> {
+        combine(&Region::new(self), &Region::empty(), Operation::Union)
+    }
+
+    /// Shape filling `contours` as returned by [`combine`], keeps texture, colour and z of
+    /// `self`
+    pub(super) fn with_contours(&self, mut contours: Vec<Vec<Vec2>>) -> Self {
+        // The largest contour becomes the outline, everything else is added as holes
+        contours.sort_by(|a, b| signed_area(b).abs().total_cmp(&signed_area(a).abs()));
+
+        let mut contours = contours.into_iter();
+        let outline = contours.next().unwrap_or_default();
+        Self {
+            texture: self.texture.clone(),
+            uv_rect: self.uv_rect,
+            color: self.color,
+            z: self.z,
+            ..contours.fold(Shape::new(outline), Shape::add_hole)
+        }
+    }
+
+    fn combine(&self, other: &Self, operation: Operation) -> Self {
+        self.with_contours(combine(&Region::new(self), &Region::new(other), operation))
+    }
+
+    /// Area covered by either shape. Shapes are combined according to their fill rules, the
+    /// result keeps texture, colour and z of `self` and may consist of several contours.
+    pub fn union(&self, other: &Self) -> Self {
+        self.combine(other, Operation::Union)
+    }
+
+    /// Area covered by both shapes, see [`Shape::union`]
+    pub fn intersect(&self, other: &Self) -> Self {
+        self.combine(other, Operation::Intersection)
+    }
+
+    /// Area of `self` not covered by `other`, see [`Shape::union`]
+    pub fn difference(&self, other: &Self) -> Self {
+        self.combine(other, Operation::Difference)
+    }
+
+    /// Area covered by exactly one of the shapes, see [`Shape::union`]
+    pub fn xor(&self, other: &Self) -> Self {
+        self.combine(other, Operation::Xor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestShape = Shape<TestTextures>;
+
+    fn square(min: Vec2, size: f32) -> TestShape {
+        TestShape::new(vec![
+            min,
+            min + vec2(size, 0.),
+            min + Vec2::splat(size),
+            min + vec2(0., size),
+        ])
+    }
+
+    #[test]
+    fn operations() {
+        let (a, b) = (square(Vec2::ZERO, 2.), square(Vec2::ONE, 2.));
+        for (result, area) in [
+            (a.union(&b), 7.),
+            (a.intersect(&b), 1.),
+            (a.difference(&b), 3.),
+            (a.xor(&b), 6.),
+        ] {
+            assert!((result.area() - area).abs() < 1e-4, "{result:?}");
+            assert!(result.contours().all(|contour| contour.len() >= 3));
+        }
+        assert!(a.intersect(&square(Vec2::splat(3.), 1.)).points.is_empty());
+    }
+
+    #[test]
+    fn holes_and_touching_corners() {
+        let frame = square(Vec2::ZERO, 3.).difference(&square(Vec2::ONE, 1.));
+        assert!((frame.area() - 8.).abs() < 1e-4);
+        assert_eq!(frame.holes.len(), 1);
+
+        // Squares sharing a corner stay two separate contours
+        let corners = square(Vec2::ZERO, 1.).union(&square(Vec2::ONE, 1.));
+        assert!((corners.area() - 2.).abs() < 1e-4);
+        assert!(corners.contours().all(|contour| contour.len() == 4));
+    }
+
+    #[test]
+    fn merges_close_vertices() {
+        let mut vertices = Vertices::new(0.1);
+        assert_eq!(vertices.index(DVec2::new(0.99, 0.99)), 0);
+        // Across cell borders and in either direction
+        assert_eq!(vertices.index(DVec2::new(1.01, 1.01)), 0);
+        assert_eq!(vertices.index(DVec2::new(0.95, 1.)), 0);
+        assert_eq!(vertices.index(DVec2::new(1.2, 1.)), 1);
+        assert_eq!(vertices.index(DVec2::new(-1., -1.)), 2);
+        assert_eq!(vertices.index(DVec2::new(1.15, 1.)), 1);
+        assert_eq!(vertices.points.len(), 3);
+    }
+
+    #[test]
+    fn meshes_wind_either_way() {
+        // The last triangle repeats the first one clockwise
+        let points = vec![vec2(0., 0.), vec2(1., 0.), vec2(1., 1.), vec2(0., 1.)];
+        let mesh = TestShape::from_mesh(points, vec![0, 1, 2, 0, 2, 3, 2, 1, 0]);
+        assert!((mesh.union(&TestShape::new(vec![])).area() - 1.).abs() < 1e-4);
+        assert!((mesh.intersect(&square(Vec2::splat(0.5), 1.)).area() - 0.25).abs() < 1e-4);
+    }
+}
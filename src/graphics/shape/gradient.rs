@@ -0,0 +1,533 @@
+use std::collections::{HashMap, HashSet};
+use std::f32::consts::TAU;
+
+use super::*;
+
+/// Largest colour error between the vertices of subdivided triangles, per channel in linear
+/// light
+const TOLERANCE: f32 = 1. / 64.;
+/// Edges shorter than this fraction of the shape size aren't subdivided further
+const MIN_EDGE: f32 = 1. / 128.;
+/// Subdivision rounds at most, each splits the edges which are still too coarse in half
+const MAX_SUBDIVISIONS: usize = 12;
+
+/// Colour transition across a shape, `stops` map positions from 0 to 1 along the gradient to
+/// colours
+#[derive(Clone, Debug, PartialEq)]
+pub enum Gradient {
+    /// From `start` to `end`, constant along lines perpendicular to them. The last stop fills
+    /// the shape when both are the same.
+    Linear {
+        start: Vec2,
+        end: Vec2,
+        stops: Ramp<Color>,
+    },
+    /// From `center` outwards up to `radius`, the last stop fills the shape when it isn't
+    /// positive
+    Radial {
+        center: Vec2,
+        radius: f32,
+        stops: Ramp<Color>,
+    },
+    /// Counter-clockwise around `center` in one full turn, starting at `start_angle`
+    Angular {
+        center: Vec2,
+        start_angle: f32,
+        stops: Ramp<Color>,
+    },
+}
+
+impl Gradient {
+    pub fn linear(start: Vec2, end: Vec2, stops: Ramp<Color>) -> Self {
+        Self::Linear { start, end, stops }
+    }
+
+    pub fn radial(center: Vec2, radius: f32, stops: Ramp<Color>) -> Self {
+        Self::Radial {
+            center,
+            radius,
+            stops,
+        }
+    }
+
+    pub fn angular(center: Vec2, start_angle: f32, stops: Ramp<Color>) -> Self {
+        Self::Angular {
+            center,
+            start_angle,
+            stops,
+        }
+    }
+
+    fn stops(&self) -> &Ramp<Color> {
+        match self {
+            Self::Linear { stops, .. }
+            | Self::Radial { stops, .. }
+            | Self::Angular { stops, .. } => stops,
+        }
+    }
+
+    /// Position along the gradient, `None` at the center of angular gradients
+    fn position(&self, point: Vec2) -> Option<f32> {
+        match *self {
+            Self::Linear { start, end, .. } if start == end => Some(1.),
+            Self::Linear { start, end, .. } => {
+                let direction = end - start;
+                Some((point - start).dot(direction) / direction.length_squared())
+            }
+            Self::Radial { radius, .. } if radius <= 0. => Some(1.),
+            Self::Radial { center, radius, .. } => Some(point.distance(center) / radius),
+            Self::Angular {
+                center,
+                start_angle,
+                ..
+            } => {
+                let offset = point - center;
+                (offset != Vec2::ZERO)
+                    .then(|| (offset.y.atan2(offset.x) - start_angle).rem_euclid(TAU) / TAU)
+            }
+        }
+    }
+
+    pub fn sample(&self, point: Vec2) -> Color {
+        self.stops().sample(self.position(point).unwrap_or(0.))
+    }
+
+    /// Lines `normal · p = offset` along which the colour changes abruptly
+    fn split_lines(&self) -> Vec<(Vec2, f32)> {
+        match *self {
+            Self::Linear { start, end, .. } if start == end => vec![],
+            Self::Linear {
+                start,
+                end,
+                ref stops,
+            } => {
+                let normal = end - start;
+                stops
+                    .keys()
+                    .iter()
+                    .map(|(t, _)| (normal, normal.dot(start + normal * *t)))
+                    .collect()
+            }
+            Self::Radial { .. } => vec![],
+            Self::Angular {
+                center,
+                start_angle,
+                ref stops,
+            } => std::iter::once(0.)
+                .chain(stops.keys().iter().map(|(t, _)| *t))
+                .map(|t| {
+                    let normal = Vec2::from_angle(start_angle + t * TAU).perp();
+                    (normal, normal.dot(center))
+                })
+                .collect(),
+        }
+    }
+
+    /// Positions of the corners of a triangle without a jump between them. Angular positions
+    /// are unwrapped around the seam and the center takes the position of the other corners.
+    fn triangle_positions(&self, corners: [Vec2; 3]) -> [f32; 3] {
+        let positions = corners.map(|corner| self.position(corner));
+        if !matches!(self, Self::Angular { .. }) {
+            return positions.map(Option::unwrap_or_default);
+        }
+
+        // The corner furthest from the seam decides the side of the others
+        let Some(reference) = positions
+            .iter()
+            .flatten()
+            .copied()
+            .max_by(|a, b| a.min(1. - a).total_cmp(&b.min(1. - b)))
+        else {
+            return [0.; 3];
+        };
+        let unwrapped = positions.map(|p| p.map(|p| p - (p - reference).round()));
+        let known = unwrapped.iter().flatten().copied().collect::<Vec<_>>();
+        let center = known.iter().sum::<f32>() / known.len() as f32;
+        unwrapped.map(|p| p.unwrap_or(center))
+    }
+}
+
+/// Largest difference of any channel in linear light, where vertex colours are blended
+fn difference(a: [f32; 4], b: [f32; 4]) -> f32 {
+    a.iter()
+        .zip(b)
+        .fold(0., |max, (a, b)| (a - b).abs().max(max))
+}
+
+/// Colour halfway between the vertex colours, as the GPU blends them
+fn midpoint(a: Color, b: Color) -> [f32; 4] {
+    let (a, b) = (a.to_linear(), b.to_linear());
+    [0, 1, 2, 3].map(|k| (a[k] + b[k]) / 2.)
+}
+
+/// Triangles of a shape being cut up, new points interpolate their neighbours
+struct Mesh {
+    points: Vec<(Vec2, Vec2)>,
+    triangles: Vec<[u32; 3]>,
+}
+
+impl Mesh {
+    /// Point at `t` from `a` to `b`, shared by both triangles along the edge
+    fn between(&mut self, cache: &mut HashMap<(u32, u32), u32>, a: u32, b: u32, t: f32) -> u32 {
+        let points = &mut self.points;
+        *cache.entry((a.min(b), a.max(b))).or_insert_with(|| {
+            let ((pa, ta), (pb, tb)) = (points[a as usize], points[b as usize]);
+            points.push((pa.lerp(pb, t), ta.lerp(tb, t)));
+            points.len() as u32 - 1
+        })
+    }
+
+    fn size(&self) -> f32 {
+        let (min, max) = self.points.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), (p, _)| (min.min(*p), max.max(*p)),
+        );
+        (max - min).max_element().max(0.)
+    }
+
+    /// Cuts every triangle crossing the line `normal · p = offset` in two or three
+    fn split(&mut self, normal: Vec2, offset: f32) {
+        let epsilon = 1e-6 * self.size() * normal.length();
+        let mut cache = HashMap::new();
+        for triangle in std::mem::take(&mut self.triangles) {
+            let side = triangle.map(|i| match normal.dot(self.points[i as usize].0) - offset {
+                d if d.abs() <= epsilon => 0.,
+                d => d,
+            });
+            if !(side.iter().any(|d| *d > 0.) && side.iter().any(|d| *d < 0.)) {
+                self.triangles.push(triangle);
+                continue;
+            }
+
+            // Start at the corner on the line, or else at the one alone on its side
+            let k = (0..3)
+                .find(|k| side[*k] == 0.)
+                .or_else(|| (0..3).find(|k| (side[(k + 1) % 3] > 0.) == (side[(k + 2) % 3] > 0.)))
+                .unwrap_or(0);
+            let [a, b, c] = [0, 1, 2].map(|j| triangle[(k + j) % 3]);
+            let [da, db, dc] = [0, 1, 2].map(|j| side[(k + j) % 3]);
+
+            if da == 0. {
+                let m = self.between(&mut cache, b, c, db / (db - dc));
+                self.triangles.extend([[a, b, m], [a, m, c]]);
+            } else {
+                let m1 = self.between(&mut cache, a, b, da / (da - db));
+                let m2 = self.between(&mut cache, c, a, dc / (dc - da));
+                self.triangles
+                    .extend([[a, m1, m2], [m1, b, c], [m1, c, m2]]);
+            }
+        }
+    }
+
+    /// Halves edges along which the colour deviates from a straight blend
+    fn subdivide(&mut self, gradient: &Gradient) {
+        let min_edge = self.size() * MIN_EDGE;
+        let stops = gradient.stops();
+
+        for _ in 0..MAX_SUBDIVISIONS {
+            let mut coarse = HashSet::new();
+            for triangle in &self.triangles {
+                let corners = triangle.map(|i| self.points[i as usize].0);
+                let center = corners.iter().position(|p| gradient.position(*p).is_none());
+                for (i, j) in [(0, 1), (1, 2), (2, 0)] {
+                    let (a, b) = (corners[i], corners[j]);
+                    if a.distance(b) <= min_edge || center.is_some_and(|k| k == i || k == j) {
+                        continue;
+                    }
+                    let error = match center {
+                        // The colour of the center blends the whole opposite edge into the
+                        // triangle, which is only accurate if the edge barely changes colour
+                        Some(_) => {
+                            let positions = gradient.triangle_positions(corners);
+                            let [ca, cb] = [i, j].map(|k| stops.sample(positions[k]).to_linear());
+                            difference(ca, cb) / 2.
+                        }
+                        None => {
+                            let [pa, pb, pm] = gradient.triangle_positions([a, b, (a + b) / 2.]);
+                            let blend = midpoint(stops.sample(pa), stops.sample(pb));
+                            difference(blend, stops.sample(pm).to_linear())
+                        }
+                    };
+                    if error > TOLERANCE {
+                        coarse.insert((triangle[i].min(triangle[j]), triangle[i].max(triangle[j])));
+                    }
+                }
+            }
+            if coarse.is_empty() {
+                break;
+            }
+
+            let mut cache = HashMap::new();
+            for triangle in std::mem::take(&mut self.triangles) {
+                let split = [(0, 1), (1, 2), (2, 0)].map(|(i, j)| {
+                    let (a, b) = (triangle[i], triangle[j]);
+                    coarse.contains(&(a.min(b), a.max(b)))
+                });
+                // Rotate so that the split edges come first
+                let k = (0..3)
+                    .find(|k| split[*k] && !split[(k + 2) % 3])
+                    .unwrap_or(0);
+                let [a, b, c] = [0, 1, 2].map(|j| triangle[(k + j) % 3]);
+                let [ab, bc, ca] = [0, 1, 2].map(|j| split[(k + j) % 3]);
+
+                match (ab, bc, ca) {
+                    (false, false, false) => self.triangles.push(triangle),
+                    (true, false, false) => {
+                        let m = self.between(&mut cache, a, b, 0.5);
+                        self.triangles.extend([[a, m, c], [m, b, c]]);
+                    }
+                    (true, true, false) => {
+                        let m1 = self.between(&mut cache, a, b, 0.5);
+                        let m2 = self.between(&mut cache, b, c, 0.5);
+                        self.triangles
+                            .extend([[a, m1, m2], [m1, b, m2], [a, m2, c]]);
+                    }
+                    _ => {
+                        let m1 = self.between(&mut cache, a, b, 0.5);
+                        let m2 = self.between(&mut cache, b, c, 0.5);
+                        let m3 = self.between(&mut cache, c, a, 0.5);
+                        self.triangles.extend([
+                            [a, m1, m3],
+                            [m1, b, m2],
+                            [m3, m2, c],
+                            [m1, m2, m3],
+                        ]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T: Textures> Shape<T> {
+    /// Colours the shape with the gradient, replacing point colours. The shape becomes a mesh
+    /// cut along the stops and subdivided where vertex colours can't follow the gradient.
+    /// Shapes which can't be triangulated only get their points coloured.
+    pub fn set_gradient(self, gradient: Gradient) -> Self {
+        let points = self
+            .points
+            .iter()
+            .chain(self.holes.iter().flatten())
+            .copied()
+            .collect::<Vec<_>>();
+        let Ok(indices) = self.triangulate() else {
+            let colors = points.iter().map(|(p, _)| gradient.sample(*p)).collect();
+            return self.set_point_colors(colors);
+        };
+
+        let mut mesh = Mesh {
+            points,
+            triangles: indices
+                .chunks_exact(3)
+                .map(|t| [t[0], t[1], t[2]])
+                .collect(),
+        };
+        for (normal, offset) in gradient.split_lines() {
+            mesh.split(normal, offset);
+        }
+        mesh.subdivide(&gradient);
+
+        // Corners on the seam of angular gradients need a different colour per side
+        let mut positions: Vec<Option<f32>> = vec![None; mesh.points.len()];
+        let mut indices = Vec::with_capacity(mesh.triangles.len() * 3);
+        for triangle in &mesh.triangles {
+            let corners = triangle.map(|i| mesh.points[i as usize].0);
+            for (i, position) in triangle.iter().zip(gradient.triangle_positions(corners)) {
+                let i = match positions[*i as usize] {
+                    None => {
+                        positions[*i as usize] = Some(position);
+                        *i
+                    }
+                    Some(existing) if (existing - position).abs() <= 1e-6 => *i,
+                    Some(_) => {
+                        mesh.points.push(mesh.points[*i as usize]);
+                        positions.push(Some(position));
+                        mesh.points.len() as u32 - 1
+                    }
+                };
+                indices.push(i);
+            }
+        }
+
+        let stops = gradient.stops();
+        Self {
+            point_colors: Some(
+                positions
+                    .into_iter()
+                    .map(|p| stops.sample(p.unwrap_or_default()))
+                    .collect(),
+            ),
+            points: mesh.points,
+            holes: vec![],
+            indices: Some(indices),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use super::*;
+
+    type TestShape = Shape<TestTextures>;
+
+    fn square() -> TestShape {
+        TestShape::from_square_centered().apply(GTransform::from_inflation(4.))
+    }
+
+    fn stops() -> Ramp<Color> {
+        Ramp::new(Color::RED, Color::BLUE).add_key(0.5, Color::YELLOW)
+    }
+
+    /// Positions and colours of the triangles the shape is drawn with
+    fn triangles(shape: &TestShape) -> Vec<[(Vec2, Color); 3]> {
+        let colors = shape.point_colors.as_ref().unwrap();
+        shape
+            .indices()
+            .unwrap()
+            .chunks_exact(3)
+            .map(|t| [0, 1, 2].map(|k| (shape.points[t[k] as usize].0, colors[t[k] as usize])))
+            .collect()
+    }
+
+    fn assert_color(a: Color, b: Color, tolerance: f32) {
+        let error = difference(a.to_linear(), b.to_linear());
+        assert!(error <= tolerance, "{a:?} instead of {b:?}");
+    }
+
+    #[test]
+    fn samples_positions() {
+        let linear = Gradient::linear(vec2(-1., 0.), vec2(1., 0.), stops());
+        assert_eq!(linear.sample(vec2(-1., 5.)), Color::RED);
+        assert_eq!(linear.sample(vec2(0., -5.)), Color::YELLOW);
+        assert_eq!(linear.sample(vec2(3., 0.)), Color::BLUE);
+
+        let radial = Gradient::radial(Vec2::ONE, 2., stops());
+        assert_eq!(radial.sample(Vec2::ONE), Color::RED);
+        assert_eq!(radial.sample(vec2(1., 0.)), Color::YELLOW);
+        assert_eq!(radial.sample(vec2(-5., 1.)), Color::BLUE);
+
+        let angular = Gradient::angular(Vec2::ZERO, std::f32::consts::FRAC_PI_2, stops());
+        assert_eq!(angular.sample(vec2(0., 1.)), Color::RED);
+        assert_eq!(angular.sample(vec2(0., -1.)), Color::YELLOW);
+        assert_eq!(angular.sample(Vec2::ZERO), Color::RED);
+
+        // Degenerate gradients show the last stop instead of NaN
+        let point = Gradient::linear(Vec2::ONE, Vec2::ONE, stops());
+        assert_eq!(point.sample(Vec2::ZERO), Color::BLUE);
+        assert_eq!(point.sample(Vec2::ONE), Color::BLUE);
+        let radial = Gradient::radial(Vec2::ZERO, 0., stops());
+        assert_eq!(radial.sample(Vec2::ZERO), Color::BLUE);
+        assert_eq!(radial.sample(Vec2::ONE), Color::BLUE);
+        for gradient in [point, radial] {
+            let colored = square().set_gradient(gradient);
+            let colors = colored.point_colors.as_ref().unwrap();
+            assert!(colors.iter().all(|color| *color == Color::BLUE));
+        }
+    }
+
+    #[test]
+    fn vertices_take_the_gradient_colour() {
+        let gradients = [
+            Gradient::linear(vec2(-2., -2.), vec2(2., 1.), stops()),
+            Gradient::radial(vec2(0.5, 0.), 3., stops()),
+            // Centered outside of the square, so that the seam doesn't cross it
+            Gradient::angular(vec2(3., 0.5), 0.3, stops()),
+        ];
+        for gradient in gradients {
+            let colored = square().set_gradient(gradient.clone());
+            assert!(colored.holes.is_empty());
+            assert!((colored.area() - 16.).abs() < 1e-3);
+            for [a, b, c] in triangles(&colored) {
+                assert!((b.0 - a.0).perp_dot(c.0 - a.0) >= 0.);
+                for (p, color) in [a, b, c] {
+                    assert_color(color, gradient.sample(p), 1e-5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn splits_along_stops() {
+        let gradient = Gradient::linear(vec2(-2., 0.), vec2(2., 0.), stops());
+        let colored = square().set_gradient(gradient.clone());
+        for triangle in triangles(&colored) {
+            let positions = triangle.map(|(p, _)| gradient.position(p).unwrap());
+            let (min, max) = (
+                positions[0].min(positions[1]),
+                positions[0].max(positions[1]),
+            );
+            let (min, max) = (min.min(positions[2]), max.max(positions[2]));
+            assert!(min >= 0.5 - 1e-6 || max <= 0.5 + 1e-6, "{positions:?}");
+        }
+        // Both sides of the square got cut at the yellow stop
+        let cuts = triangles(&colored)
+            .into_iter()
+            .flatten()
+            .filter(|(p, _)| p.x.abs() < 1e-6 && p.y.abs() > 2. - 1e-6)
+            .map(|(_, color)| color)
+            .collect::<Vec<_>>();
+        assert!(!cuts.is_empty() && cuts.iter().all(|color| *color == Color::YELLOW));
+    }
+
+    #[test]
+    fn duplicates_vertices_on_the_seam() {
+        let gradient = Gradient::angular(Vec2::ZERO, 0., stops());
+        let colored = square().set_gradient(gradient);
+        let seam = triangles(&colored)
+            .into_iter()
+            .flatten()
+            .filter(|(p, _)| p.y == 0. && p.x > 0.)
+            .collect::<Vec<_>>();
+        // Above the seam the gradient starts, below it ends
+        for color in [Color::RED, Color::BLUE] {
+            assert!(seam.iter().any(|(p, c)| *c == color && p.x == 2.));
+        }
+        let at_end = |p: Vec2| seam.iter().any(|(q, c)| *q == p && *c == Color::BLUE);
+        assert!(seam.iter().all(|(p, _)| at_end(*p)));
+    }
+
+    #[test]
+    fn subdivides_within_tolerance() {
+        let stops = Ramp::new(Color::RED, Color::BLUE).add_key(0.3, Color::GREEN);
+        let gradients = [
+            Gradient::radial(vec2(-1., 0.5), 5., stops.clone()),
+            Gradient::angular(Vec2::ZERO, 0.7, stops),
+        ];
+        for gradient in gradients {
+            let colored = square().set_gradient(gradient.clone());
+            assert!(triangles(&colored).len() > 32);
+            let min_edge = 4. * MIN_EDGE;
+            for triangle in triangles(&colored) {
+                for (i, j) in [(0, 1), (1, 2), (2, 0)] {
+                    let ((a, ca), (b, cb)) = (triangle[i], triangle[j]);
+                    let middle = (a + b) / 2.;
+                    // The center of angular gradients has no colour of its own
+                    if a.distance(b) <= min_edge || a == Vec2::ZERO || b == Vec2::ZERO {
+                        continue;
+                    }
+                    let [.., position] = gradient.triangle_positions([a, b, middle]);
+                    // Vertex colours are blended in linear light
+                    let sampled = gradient.stops().sample(position).to_linear();
+                    let error = difference(midpoint(ca, cb), sampled);
+                    assert!(error <= TOLERANCE + 1e-5, "{error} between {a} and {b}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn colours_points_of_shapes_without_triangles() {
+        let bowtie = TestShape::new(vec![vec2(0., 0.), vec2(1., 1.), vec2(1., 0.), vec2(0., 1.)]);
+        let gradient = Gradient::linear(Vec2::ZERO, vec2(1., 0.), stops());
+        let colored = bowtie.clone().set_gradient(gradient.clone());
+        assert!(colored.indices().is_none());
+        assert_eq!(colored.points, bowtie.points);
+        let colors = colored.point_colors.unwrap();
+        for ((p, _), color) in bowtie.points.iter().zip(colors) {
+            assert_eq!(color, gradient.sample(*p));
+        }
+    }
+}
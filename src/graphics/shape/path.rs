@@ -0,0 +1,507 @@
+use super::*;
+
+/// Upper bound for the segments a single curve is flattened into
+const MAX_CURVE_SEGMENTS: f32 = 1024.;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Segment {
+    Line(Vec2),
+    Quad(Vec2, Vec2),
+    Cubic(Vec2, Vec2, Vec2),
+    /// Elliptical arc, angles are measured before rotating the ellipse
+    Arc {
+        center: Vec2,
+        radii: Vec2,
+        rotation: f32,
+        start_angle: f32,
+        sweep: f32,
+    },
+}
+
+fn ellipse_point(center: Vec2, radii: Vec2, rotation: f32, angle: f32) -> Vec2 {
+    center + Vec2::from_angle(rotation).rotate(Vec2::from_angle(angle) * radii)
+}
+
+impl Segment {
+    fn end(&self) -> Vec2 {
+        match *self {
+            Self::Line(to) | Self::Quad(_, to) | Self::Cubic(_, _, to) => to,
+            Self::Arc {
+                center,
+                radii,
+                rotation,
+                start_angle,
+                sweep,
+            } => ellipse_point(center, radii, rotation, start_angle + sweep),
+        }
+    }
+
+    /// Appends the points after `from` with at most `tolerance` distance to the curve
+    fn flatten(&self, from: Vec2, tolerance: f32, points: &mut Vec<Vec2>) {
+        let steps = |deviation: f32| {
+            (deviation / tolerance)
+                .sqrt()
+                .ceil()
+                .clamp(1., MAX_CURVE_SEGMENTS) as usize
+        };
+
+        match *self {
+            Self::Line(to) => points.push(to),
+            Self::Quad(control, to) => {
+                let n = steps((from - 2. * control + to).length() / 4.);
+                points.extend((1..=n).map(|i| {
+                    let t = i as f32 / n as f32;
+                    let s = 1. - t;
+                    s * s * from + 2. * s * t * control + t * t * to
+                }));
+            }
+            Self::Cubic(control1, control2, to) => {
+                let curvature = (from - 2. * control1 + control2)
+                    .length()
+                    .max((control1 - 2. * control2 + to).length());
+                let n = steps(curvature * 3. / 4.);
+                points.extend((1..=n).map(|i| {
+                    let t = i as f32 / n as f32;
+                    let s = 1. - t;
+                    s * s * s * from
+                        + 3. * s * s * t * control1
+                        + 3. * s * t * t * control2
+                        + t * t * t * to
+                }));
+            }
+            Self::Arc {
+                center,
+                radii,
+                rotation,
+                start_angle,
+                sweep,
+            } => {
+                // Largest angle whose chord stays within the tolerance on the wider side
+                let radius = radii.x.abs().max(radii.y.abs());
+                let step = 2. * (1. - (tolerance / radius).min(1.)).acos();
+                let n = (sweep.abs() / step.max(f32::EPSILON))
+                    .ceil()
+                    .clamp(1., MAX_CURVE_SEGMENTS) as usize;
+                points.extend((1..=n).map(|i| {
+                    let angle = start_angle + sweep * i as f32 / n as f32;
+                    ellipse_point(center, radii, rotation, angle)
+                }));
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct SubPath {
+    start: Vec2,
+    segments: Vec<Segment>,
+    closed: bool,
+}
+
+impl SubPath {
+    fn end(&self) -> Vec2 {
+        self.segments.last().map_or(self.start, Segment::end)
+    }
+}
+
+/// Canvas-style builder for outlines made of lines, Bézier curves and arcs. Curves are
+/// flattened when the path is filled into a [`Shape`] or stroked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    subpaths: Vec<SubPath>,
+    tolerance: f32,
+}
+
+impl Default for Path {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self {
+            subpaths: vec![],
+            tolerance: 0.001,
+        }
+    }
+
+    /// Maximum distance between the flattened lines and the curves
+    pub fn set_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Starts a new subpath at `point`
+    pub fn move_to(mut self, point: Vec2) -> Self {
+        self.subpaths.push(SubPath {
+            start: point,
+            segments: vec![],
+            closed: false,
+        });
+        self
+    }
+
+    /// The subpath segments are added to, drawing after `close` continues from its start
+    fn current(&mut self, from: Vec2) -> &mut SubPath {
+        let start = match self.subpaths.last() {
+            None => Some(from),
+            Some(subpath) if subpath.closed => Some(subpath.start),
+            Some(_) => None,
+        };
+        if let Some(start) = start {
+            self.subpaths.push(SubPath {
+                start,
+                segments: vec![],
+                closed: false,
+            });
+        }
+        self.subpaths.last_mut().unwrap()
+    }
+
+    /// Point the next segment starts at
+    pub fn position(&self) -> Option<Vec2> {
+        self.subpaths.last().map(|subpath| match subpath.closed {
+            true => subpath.start,
+            false => subpath.end(),
+        })
+    }
+
+    fn push(mut self, from: Vec2, segment: Segment) -> Self {
+        self.current(from).segments.push(segment);
+        self
+    }
+
+    pub fn line_to(self, point: Vec2) -> Self {
+        self.push(point, Segment::Line(point))
+    }
+
+    /// Quadratic Bézier curve, starts at `control` when the path is empty
+    pub fn quad_to(self, control: Vec2, point: Vec2) -> Self {
+        self.push(control, Segment::Quad(control, point))
+    }
+
+    /// Cubic Bézier curve, starts at `control1` when the path is empty
+    pub fn cubic_to(self, control1: Vec2, control2: Vec2, point: Vec2) -> Self {
+        self.push(control1, Segment::Cubic(control1, control2, point))
+    }
+
+    /// Circular arc around `center` starting at `start_angle` and rotating counter-clockwise
+    /// by `sweep` radians, connected to the current point with a line
+    pub fn arc(self, center: Vec2, radius: f32, start_angle: f32, sweep: f32) -> Self {
+        self.ellipse_arc(center, Vec2::splat(radius), 0., start_angle, sweep)
+    }
+
+    /// Arc of the ellipse with the given radii, rotated by `rotation` around `center`.
+    /// Angles are measured on the unrotated ellipse.
+    pub fn ellipse_arc(
+        self,
+        center: Vec2,
+        radii: Vec2,
+        rotation: f32,
+        start_angle: f32,
+        sweep: f32,
+    ) -> Self {
+        let start = ellipse_point(center, radii, rotation, start_angle);
+        let path = match self.position() {
+            Some(position) if position.distance(start) <= f32::EPSILON => self,
+            _ => self.line_to(start),
+        };
+        path.push(
+            start,
+            Segment::Arc {
+                center,
+                radii,
+                rotation,
+                start_angle,
+                sweep,
+            },
+        )
+    }
+
+    /// Rounds the corner at `corner` between the lines from the current point to `corner`
+    /// and from `corner` to `point` with an arc of `radius`, like `arcTo` of the HTML canvas.
+    /// The path ends where the arc touches the second line.
+    pub fn arc_to(self, corner: Vec2, point: Vec2, radius: f32) -> Self {
+        let Some(position) = self.position() else {
+            return self.move_to(corner);
+        };
+        let (d0, d1) = (position - corner, point - corner);
+        if radius <= 0. || d0.perp_dot(d1).abs() <= f32::EPSILON * d0.length() * d1.length() {
+            return self.line_to(corner);
+        }
+
+        let (d0, d1) = (d0.normalize(), d1.normalize());
+        let half_angle = d0.angle_between(d1).abs() / 2.;
+        let bisector = (d0 + d1).normalize();
+        let center = corner + bisector * radius / half_angle.sin();
+        let tangent0 = corner + d0 * radius / half_angle.tan();
+        let tangent1 = corner + d1 * radius / half_angle.tan();
+
+        let from = tangent0 - center;
+        self.arc(
+            center,
+            radius,
+            from.y.atan2(from.x),
+            from.angle_between(tangent1 - center),
+        )
+    }
+
+    /// Connects the current subpath back to its start
+    pub fn close(mut self) -> Self {
+        if let Some(subpath) = self.subpaths.last_mut() {
+            subpath.closed = true;
+        }
+        self
+    }
+
+    /// Every subpath flattened into points, together with whether it is closed
+    pub fn flatten(&self) -> Vec<(Vec<Vec2>, bool)> {
+        // Rounding errors, like an arc ending slightly off its start, must not produce
+        // degenerate edges
+        let epsilon = self.tolerance / 100.;
+
+        self.subpaths
+            .iter()
+            .map(|subpath| {
+                let mut curve = vec![subpath.start];
+                let mut from = subpath.start;
+                for segment in &subpath.segments {
+                    segment.flatten(from, self.tolerance, &mut curve);
+                    from = segment.end();
+                }
+
+                let mut points: Vec<Vec2> = vec![];
+                for point in curve {
+                    if points
+                        .last()
+                        .map_or(true, |last| last.distance(point) > epsilon)
+                    {
+                        points.push(point);
+                    }
+                }
+                while subpath.closed
+                    && points.len() > 1
+                    && points[0].distance(points[points.len() - 1]) <= epsilon
+                {
+                    points.pop();
+                }
+                (points, subpath.closed)
+            })
+            .collect()
+    }
+
+    /// Area enclosed by the subpaths, open ones are closed implicitly. Further subpaths become
+    /// holes of the first one, use [`Shape::set_fill_rule`] to decide how they overlap.
+    pub fn fill<T: Textures>(&self) -> Shape<T> {
+        let mut contours = self
+            .flatten()
+            .into_iter()
+            .map(|(points, _)| points)
+            .filter(|points| points.len() >= 3);
+        let outline = contours.next().unwrap_or_default();
+        contours.fold(Shape::new(outline), Shape::add_hole)
+    }
+
+    /// Lines of the given thickness along every subpath, caps are only added to open ones
+    pub fn stroke<T: Textures>(&self, thickness: f32, join: LineJoin, cap: LineCap) -> Shape<T> {
+        Shape::from_meshes(self.flatten().into_iter().map(|(points, closed)| {
+            Polyline::new(points)
+                .set_thickness(thickness)
+                .set_join(join)
+                .set_cap(cap)
+                .set_closed(closed)
+                .into()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use glam::vec2;
+
+    use super::*;
+
+    type TestShape = Shape<TestTextures>;
+
+    fn distance_to_polyline(points: &[Vec2], p: Vec2) -> f32 {
+        points
+            .windows(2)
+            .map(|line| {
+                let (a, b) = (line[0], line[1]);
+                let t = ((p - a).dot(b - a) / (b - a).length_squared()).clamp(0., 1.);
+                p.distance(a + (b - a) * t)
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Flattens the path with the only segment and compares it to the curve sampled densely
+    fn assert_within_tolerance(path: Path, tolerance: f32, curve: impl Fn(f32) -> Vec2) {
+        let coarse = path.clone().set_tolerance(tolerance * 100.).flatten();
+        let flattened = path.set_tolerance(tolerance).flatten();
+        let [(points, false)] = &flattened[..] else {
+            panic!("expected a single open subpath");
+        };
+        assert!(points.len() > coarse[0].0.len());
+        assert_eq!(points[0], curve(0.));
+        assert!(points[points.len() - 1].distance(curve(1.)) < 1e-4);
+
+        for i in 0..=1000 {
+            let p = curve(i as f32 / 1000.);
+            let distance = distance_to_polyline(points, p);
+            assert!(distance <= tolerance * 1.01, "{p} is {distance} away");
+        }
+    }
+
+    #[test]
+    fn flattens_within_tolerance() {
+        let (from, control, to) = (vec2(0., 0.), vec2(5., 10.), vec2(10., 0.));
+        assert_within_tolerance(Path::new().move_to(from).quad_to(control, to), 0.01, |t| {
+            let s = 1. - t;
+            s * s * from + 2. * s * t * control + t * t * to
+        });
+
+        let [control1, control2] = [vec2(0., 10.), vec2(10., -10.)];
+        let path = Path::new().move_to(from).cubic_to(control1, control2, to);
+        assert_within_tolerance(path, 0.01, |t| {
+            let s = 1. - t;
+            s * s * s * from
+                + 3. * s * s * t * control1
+                + 3. * s * t * t * control2
+                + t * t * t * to
+        });
+
+        let path = Path::new().arc(Vec2::ZERO, 10., 0., 1.5 * PI);
+        assert_within_tolerance(path, 0.01, |t| Vec2::from_angle(1.5 * PI * t) * 10.);
+        let path = Path::new().ellipse_arc(Vec2::ZERO, vec2(10., 5.), FRAC_PI_2, 0., -PI);
+        assert_within_tolerance(path, 0.01, |t| {
+            Vec2::from_angle(FRAC_PI_2).rotate(Vec2::from_angle(-PI * t) * vec2(10., 5.))
+        });
+    }
+
+    #[test]
+    fn arcs_connect_to_the_current_point() {
+        let flattened = Path::new()
+            .move_to(vec2(-1., 0.))
+            .arc(Vec2::ZERO, 2., 0., PI)
+            .flatten();
+        let points = &flattened[0].0;
+        assert_eq!(points[..2], [vec2(-1., 0.), vec2(2., 0.)]);
+        assert!(points[points.len() - 1].distance(vec2(-2., 0.)) < 1e-5);
+    }
+
+    #[test]
+    fn arc_to_touches_both_lines() {
+        let path = Path::new()
+            .move_to(vec2(0., 0.))
+            .arc_to(vec2(2., 0.), vec2(2., 2.), 1.);
+        assert!(path.position().unwrap().distance(vec2(2., 1.)) < 1e-5);
+        let points = &path.flatten()[0].0;
+        assert_eq!(points[0], Vec2::ZERO);
+        assert!(points[1].distance(vec2(1., 0.)) < 1e-5);
+        for p in &points[1..] {
+            assert!((p.distance(vec2(1., 1.)) - 1.).abs() < 1e-5);
+        }
+
+        // Obtuse corners are touched closer to the corner
+        let (corner, radius) = (vec2(4., 0.), 0.5);
+        let path = Path::new()
+            .move_to(Vec2::ZERO)
+            .arc_to(corner, vec2(8., 4.), radius);
+        let tangent = radius / (3. * PI / 8.).tan();
+        let points = &path.flatten()[0].0;
+        assert!(points[1].distance(corner - vec2(tangent, 0.)) < 1e-5);
+        let end = path.position().unwrap();
+        assert!((end.distance(corner) - tangent).abs() < 1e-5);
+        assert!((end - corner).normalize().distance(Vec2::ONE.normalize()) < 1e-5);
+
+        // Straight lines and empty paths only reach the corner
+        let straight = Path::new()
+            .move_to(Vec2::ZERO)
+            .arc_to(vec2(1., 0.), vec2(2., 0.), 1.);
+        assert_eq!(straight.flatten()[0].0, [Vec2::ZERO, vec2(1., 0.)]);
+        assert_eq!(
+            Path::new()
+                .arc_to(vec2(1., 0.), vec2(2., 0.), 1.)
+                .position(),
+            Some(vec2(1., 0.))
+        );
+    }
+
+    fn square(min: Vec2, size: f32) -> Path {
+        Path::new()
+            .move_to(min)
+            .line_to(min + vec2(size, 0.))
+            .line_to(min + size)
+            .line_to(min + vec2(0., size))
+            .close()
+    }
+
+    #[test]
+    fn closes_subpaths() {
+        let path = Path::new()
+            .move_to(vec2(0., 0.))
+            .line_to(vec2(1., 0.))
+            .line_to(vec2(1., 1.))
+            .close();
+        assert_eq!(path.position(), Some(Vec2::ZERO));
+
+        // Drawing on continues from the start of the closed subpath
+        let path = path.line_to(vec2(0., 2.));
+        assert_eq!(
+            path.flatten(),
+            [
+                (vec![vec2(0., 0.), vec2(1., 0.), vec2(1., 1.)], true),
+                (vec![vec2(0., 0.), vec2(0., 2.)], false),
+            ]
+        );
+
+        // Closing points duplicating the start are dropped
+        let circle = Path::new().arc(Vec2::ZERO, 1., 0., 2. * PI).close();
+        let points = &circle.flatten()[0].0;
+        assert!(points[0].distance(points[points.len() - 1]) > 0.01);
+    }
+
+    #[test]
+    fn fills_subpaths_as_holes() {
+        let outer = square(Vec2::ZERO, 4.);
+        let mut path = outer.clone();
+        path.subpaths.extend(square(Vec2::ONE, 2.).subpaths);
+        let framed: TestShape = path.fill();
+        assert!((framed.area() - 12.).abs() < 1e-5);
+        assert!(!framed.contains_point(Vec2::splat(2.)));
+        assert!(framed.contains_point(Vec2::splat(0.5)));
+
+        // Open subpaths are closed implicitly, lines are dropped
+        let open = Path::new()
+            .move_to(Vec2::ZERO)
+            .line_to(vec2(2., 0.))
+            .line_to(vec2(0., 2.))
+            .move_to(vec2(5., 5.))
+            .line_to(vec2(6., 6.));
+        assert!((open.fill::<TestTextures>().area() - 2.).abs() < 1e-5);
+        assert_eq!(Path::new().fill::<TestTextures>().area(), 0.);
+    }
+
+    #[test]
+    fn strokes_every_subpath() {
+        let mut path = square(Vec2::ZERO, 4.);
+        path.subpaths.extend(
+            Path::new()
+                .move_to(vec2(10., 0.))
+                .line_to(vec2(10., 2.))
+                .subpaths,
+        );
+
+        let butt: TestShape = path.stroke(0.2, LineJoin::Miter, LineCap::Butt);
+        let ring = 4.2 * 4.2 - 3.8 * 3.8;
+        assert!((butt.area() - (ring + 0.4)).abs() < 1e-4);
+        // Caps only extend the open subpath
+        let square_caps: TestShape = path.stroke(0.2, LineJoin::Miter, LineCap::Square);
+        assert!((square_caps.area() - (ring + 0.44)).abs() < 1e-4);
+        assert!(square_caps.contains_point(vec2(10., 2.05)));
+        assert!(!square_caps.contains_point(vec2(0., -0.15)));
+    }
+}
@@ -0,0 +1,493 @@
+use super::*;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineJoin {
+    /// Sharp corner, falls back to `Bevel` above the miter limit
+    #[default]
+    Miter,
+    Bevel,
+    Round,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineCap {
+    /// Line ends exactly at the end point
+    #[default]
+    Butt,
+    /// Line continues half of its width past the end point
+    Square,
+    Round,
+}
+
+/// Segments used for half a turn of round joins and caps
+pub(super) const ROUND_SEGMENTS: f32 = 8.;
+
+pub(super) const DEFAULT_MITER_LIMIT: f32 = 4.;
+
+/// Builder for lines with joins, caps and optionally varying width and colour,
+/// converted into a [`Shape`] with `into()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polyline {
+    points: Vec<Vec2>,
+    thickness: f32,
+    widths: Option<Vec<f32>>,
+    colors: Option<Vec<Color>>,
+    join: LineJoin,
+    cap: LineCap,
+    closed: bool,
+    miter_limit: f32,
+}
+
+impl Polyline {
+    pub fn new(points: Vec<Vec2>) -> Self {
+        Self {
+            points,
+            thickness: 0.01,
+            widths: None,
+            colors: None,
+            join: LineJoin::default(),
+            cap: LineCap::default(),
+            closed: false,
+            miter_limit: DEFAULT_MITER_LIMIT,
+        }
+    }
+
+    pub fn set_thickness(mut self, thickness: f32) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    /// Width at every point, replaces `thickness`
+    pub fn set_widths(mut self, widths: Vec<f32>) -> Self {
+        assert_eq!(
+            widths.len(),
+            self.points.len(),
+            "expected one width per point"
+        );
+        self.widths = Some(widths);
+        self
+    }
+
+    pub fn set_colors(mut self, colors: Vec<Color>) -> Self {
+        assert_eq!(
+            colors.len(),
+            self.points.len(),
+            "expected one colour per point"
+        );
+        self.colors = Some(colors);
+        self
+    }
+
+    pub fn set_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    /// Caps are not used for closed lines
+    pub fn set_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    /// Connects the last point back to the first one
+    pub fn set_closed(mut self, closed: bool) -> Self {
+        self.closed = closed;
+        self
+    }
+
+    /// Maximum ratio between the miter length and half of the line width
+    pub fn set_miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    fn tessellate(&self) -> Mesh {
+        let mut mesh = Mesh::default();
+
+        // Points with their half width, duplicates would have no direction
+        let mut nodes: Vec<(Vec2, f32, Color)> = vec![];
+        for (i, point) in self.points.iter().enumerate() {
+            let width = self
+                .widths
+                .as_ref()
+                .map_or(self.thickness, |widths| widths[i]);
+            let color = self
+                .colors
+                .as_ref()
+                .map_or(Color::WHITE, |colors| colors[i]);
+            if nodes
+                .last()
+                .map_or(true, |(last, ..)| last.distance(*point) > f32::EPSILON)
+            {
+                nodes.push((*point, width / 2., color));
+            }
+        }
+        if self.closed
+            && nodes.len() > 2
+            && nodes[0].0.distance(nodes[nodes.len() - 1].0) <= f32::EPSILON
+        {
+            nodes.pop();
+        }
+        if nodes.len() < 2 {
+            return mesh;
+        }
+
+        let n = nodes.len();
+        let segment_count = if self.closed { n } else { n - 1 };
+        let segment = |i: usize| nodes[(i + 1) % n].0 - nodes[i].0;
+
+        // Left and right vertex at the start and at the end of every segment
+        let mut starts = vec![(0, 0); segment_count];
+        let mut ends = vec![(0, 0); segment_count];
+
+        for (i, &(p, w, color)) in nodes.iter().enumerate() {
+            let incoming = (self.closed || i > 0).then(|| (i + n - 1) % n);
+            let outgoing = (self.closed || i < n - 1).then_some(i);
+
+            match (incoming, outgoing) {
+                (Some(a), Some(b)) => {
+                    let (end, start) = self.join(&mut mesh, p, w, color, segment(a), segment(b));
+                    ends[a] = end;
+                    starts[b] = start;
+                }
+                (None, Some(b)) => {
+                    let dir = segment(b).normalize();
+                    starts[b] = self.cap(&mut mesh, p, w, color, -dir);
+                }
+                (Some(a), None) => {
+                    let dir = segment(a).normalize();
+                    let (right, left) = self.cap(&mut mesh, p, w, color, dir);
+                    ends[a] = (left, right);
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+
+        for (&(start_left, start_right), &(end_left, end_right)) in starts.iter().zip(&ends) {
+            mesh.triangle(start_left, start_right, end_right);
+            mesh.triangle(start_left, end_right, end_left);
+        }
+
+        mesh
+    }
+
+    /// Adds the cap facing `outward` and returns the vertices to the left and right of
+    /// the line, looking at the cap from the inside
+    fn cap(&self, mesh: &mut Mesh, p: Vec2, w: f32, color: Color, outward: Vec2) -> (u32, u32) {
+        // Left and right as seen when looking along `outward`
+        let normal = outward.perp() * w;
+        let left = mesh.vertex(p + normal, color);
+        let right = mesh.vertex(p - normal, color);
+
+        match self.cap {
+            LineCap::Butt => {}
+            LineCap::Square => {
+                let outer_left = mesh.vertex(p + normal + outward * w, color);
+                let outer_right = mesh.vertex(p - normal + outward * w, color);
+                mesh.triangle(left, right, outer_right);
+                mesh.triangle(left, outer_right, outer_left);
+            }
+            LineCap::Round => {
+                let center = mesh.vertex(p, color);
+                mesh.arc(center, p, normal, -std::f32::consts::PI, color);
+            }
+        }
+
+        (right, left)
+    }
+
+    /// Adds the join between the incoming segment `d0` and the outgoing segment `d1` and
+    /// returns the left and right vertex at the end of `d0` and at the start of `d1`
+    fn join(
+        &self,
+        mesh: &mut Mesh,
+        p: Vec2,
+        w: f32,
+        color: Color,
+        d0: Vec2,
+        d1: Vec2,
+    ) -> ((u32, u32), (u32, u32)) {
+        let (len0, len1) = (d0.length(), d1.length());
+        let (n0, n1) = ((d0 / len0).perp(), (d1 / len1).perp());
+
+        let turn = d0.perp_dot(d1);
+        if turn.abs() <= f32::EPSILON * len0 * len1 && d0.dot(d1) > 0. {
+            let left = mesh.vertex(p + n0 * w, color);
+            let right = mesh.vertex(p - n0 * w, color);
+            return ((left, right), (left, right));
+        }
+
+        // The outer side of the corner is to the right when turning left
+        let side = if turn > 0. { -1. } else { 1. };
+        let outer0 = mesh.vertex(p + n0 * w * side, color);
+        let outer1 = mesh.vertex(p + n1 * w * side, color);
+        let center = mesh.vertex(p, color);
+
+        let bisector = (n0 + n1).normalize_or_zero();
+        let cos_half = bisector.dot(n0);
+        let miter_offset = bisector * side * w / cos_half;
+
+        // Both segments share the inner corner unless one of them is too short
+        let inner_along = (w / cos_half) * (1. - cos_half * cos_half).sqrt();
+        let (inner0, inner1) = if cos_half > f32::EPSILON && inner_along <= len0.min(len1) {
+            let inner = mesh.vertex(p - miter_offset, color);
+            mesh.triangle(inner, outer0, center);
+            mesh.triangle(inner, center, outer1);
+            (inner, inner)
+        } else {
+            (
+                mesh.vertex(p - n0 * w * side, color),
+                mesh.vertex(p - n1 * w * side, color),
+            )
+        };
+
+        match self.join {
+            LineJoin::Miter if cos_half > f32::EPSILON && 1. / cos_half <= self.miter_limit => {
+                let miter = mesh.vertex(p + miter_offset, color);
+                mesh.triangle(center, outer0, miter);
+                mesh.triangle(center, miter, outer1);
+            }
+            LineJoin::Miter | LineJoin::Bevel => mesh.triangle(center, outer0, outer1),
+            LineJoin::Round => {
+                // Clockwise around the outside when turning right, the angle alone can't tell
+                // for a full reversal
+                let sweep = -side * n0.angle_between(n1).abs();
+                mesh.arc(center, p, n0 * side * w, sweep, color);
+            }
+        }
+
+        if side > 0. {
+            ((outer0, inner0), (outer1, inner1))
+        } else {
+            ((inner0, outer0), (inner1, outer1))
+        }
+    }
+}
+
+#[derive(Default)]
+struct Mesh {
+    points: Vec<Vec2>,
+    colors: Vec<Color>,
+    indices: Vec<u32>,
+}
+
+impl Mesh {
+    fn vertex(&mut self, point: Vec2, color: Color) -> u32 {
+        self.points.push(point);
+        self.colors.push(color);
+        self.points.len() as u32 - 1
+    }
+
+    /// Adds the triangle in counter-clockwise order
+    fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        let [pa, pb, pc] = [a, b, c].map(|i| self.points[i as usize]);
+        if (pb - pa).perp_dot(pc - pa) < 0. {
+            self.indices.extend([a, c, b]);
+        } else {
+            self.indices.extend([a, b, c]);
+        }
+    }
+
+    /// Fan around `center` starting at `center_point + from`, rotating by `sweep` radians
+    fn arc(&mut self, center: u32, center_point: Vec2, from: Vec2, sweep: f32, color: Color) {
+        let steps = (sweep.abs() / std::f32::consts::PI * ROUND_SEGMENTS)
+            .ceil()
+            .max(1.) as usize;
+        let mut previous = self.vertex(center_point + from, color);
+        for step in 1..=steps {
+            let rotation = Vec2::from_angle(sweep * step as f32 / steps as f32);
+            let current = self.vertex(center_point + rotation.rotate(from), color);
+            self.triangle(center, previous, current);
+            previous = current;
+        }
+    }
+}
+
+impl<T: Textures> From<Polyline> for Shape<T> {
+    fn from(polyline: Polyline) -> Self {
+        let Mesh {
+            points,
+            colors,
+            indices,
+        } = polyline.tessellate();
+        let shape = Shape::from_mesh(points, indices);
+        match polyline.colors {
+            Some(_) => shape.set_point_colors(colors),
+            None => shape,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+
+    fn shape(polyline: Polyline) -> Shape<TestTextures> {
+        let shape: Shape<TestTextures> = polyline.into();
+        assert!(shape.points.iter().all(|(p, _)| p.is_finite()));
+        for triangle in shape.indices().unwrap().chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| shape.points[triangle[i] as usize].0);
+            assert!(
+                (b - a).perp_dot(c - a) >= 0.,
+                "clockwise triangle {a} {b} {c}"
+            );
+        }
+        shape
+    }
+
+    fn assert_area(shape: &Shape<TestTextures>, area: f32) {
+        assert!(
+            (shape.area() - area).abs() < 1e-4,
+            "{} != {area}",
+            shape.area()
+        );
+    }
+
+    fn line(cap: LineCap) -> Shape<TestTextures> {
+        shape(
+            Polyline::new(vec![Vec2::ZERO, vec2(2., 0.)])
+                .set_thickness(0.5)
+                .set_cap(cap),
+        )
+    }
+
+    /// Area of the regular polygon round joins and caps approximate circles of radius `r` with
+    fn round_area(r: f32) -> f32 {
+        let segments = 2. * ROUND_SEGMENTS;
+        segments / 2. * r * r * (2. * PI / segments).sin()
+    }
+
+    #[test]
+    fn caps() {
+        let butt = line(LineCap::Butt);
+        assert_area(&butt, 1.);
+        assert_eq!(
+            butt.aabb().unwrap(),
+            Aabb::new(vec2(0., -0.25), vec2(2., 0.25))
+        );
+
+        let square = line(LineCap::Square);
+        assert_area(&square, 1.25);
+        assert_eq!(
+            square.aabb().unwrap(),
+            Aabb::new(vec2(-0.25, -0.25), vec2(2.25, 0.25))
+        );
+
+        let round = line(LineCap::Round);
+        assert_area(&round, 1. + round_area(0.25));
+        assert!(round.contains_point(vec2(2.2, 0.)) && !round.contains_point(vec2(2.2, 0.2)));
+    }
+
+    #[test]
+    fn joins() {
+        let corner = |join| {
+            shape(
+                Polyline::new(vec![Vec2::ZERO, Vec2::X, Vec2::ONE])
+                    .set_thickness(0.2)
+                    .set_join(join),
+            )
+        };
+        let outer_corner = vec2(1.09, -0.09);
+
+        let miter = corner(LineJoin::Miter);
+        assert_area(&miter, 0.4);
+        assert!(miter.contains_point(outer_corner));
+
+        let bevel = corner(LineJoin::Bevel);
+        assert_area(&bevel, 0.395);
+        assert!(!bevel.contains_point(outer_corner) && bevel.contains_point(vec2(1.04, -0.04)));
+
+        let round = corner(LineJoin::Round);
+        assert_area(&round, 0.39 + round_area(0.1) / 4.);
+        assert!(!round.contains_point(outer_corner) && round.contains_point(vec2(1.06, -0.06)));
+
+        // Turning right mirrors the corner
+        let right = shape(
+            Polyline::new(vec![Vec2::ZERO, Vec2::X, vec2(1., -1.)])
+                .set_thickness(0.2)
+                .set_join(LineJoin::Miter),
+        );
+        assert_area(&right, 0.4);
+        assert!(right.contains_point(vec2(1.09, 0.09)));
+    }
+
+    #[test]
+    fn miter_limit() {
+        let sharp = |miter_limit| {
+            shape(
+                Polyline::new(vec![Vec2::ZERO, Vec2::X, vec2(0., 0.1)])
+                    .set_thickness(0.2)
+                    .set_miter_limit(miter_limit),
+            )
+        };
+        assert!(sharp(DEFAULT_MITER_LIMIT).aabb().unwrap().max.x < 1.1);
+        assert!(sharp(100.).aabb().unwrap().max.x > 2.);
+    }
+
+    #[test]
+    fn closed() {
+        let square = vec![Vec2::ZERO, Vec2::X, Vec2::ONE, Vec2::Y];
+        let frame = |points: Vec<Vec2>| {
+            shape(
+                Polyline::new(points)
+                    .set_thickness(0.2)
+                    .set_cap(LineCap::Round)
+                    .set_closed(true),
+            )
+        };
+        // Caps aren't used and a repeated first point is ignored
+        assert_area(&frame(square.clone()), 1.2 * 1.2 - 0.8 * 0.8);
+        assert_area(
+            &frame([square.clone(), vec![Vec2::ZERO]].concat()),
+            1.2 * 1.2 - 0.8 * 0.8,
+        );
+        assert!(!frame(square).contains_point(Vec2::splat(0.5)));
+    }
+
+    #[test]
+    fn degenerate_input() {
+        for points in [vec![], vec![Vec2::ONE], vec![Vec2::ONE, Vec2::ONE]] {
+            assert!(shape(Polyline::new(points.clone())).points.is_empty());
+            assert!(shape(Polyline::new(points).set_closed(true))
+                .points
+                .is_empty());
+        }
+
+        let repeated = shape(
+            Polyline::new(vec![Vec2::ZERO, Vec2::ZERO, vec2(2., 0.), vec2(2., 0.)])
+                .set_thickness(0.5),
+        );
+        assert_area(&repeated, 1.);
+
+        // Turning back onto the line
+        let turn = |join| {
+            shape(
+                Polyline::new(vec![Vec2::ZERO, Vec2::X, vec2(0.5, 0.)])
+                    .set_thickness(0.2)
+                    .set_join(join),
+            )
+        };
+        for join in [LineJoin::Miter, LineJoin::Bevel] {
+            assert!(turn(join).aabb().unwrap().max.x <= 1. + 1e-6);
+        }
+        let round = turn(LineJoin::Round);
+        assert!((round.aabb().unwrap().max.x - 1.1).abs() < 1e-6);
+        assert!(round.contains_point(vec2(1.05, 0.)));
+    }
+
+    #[test]
+    fn widths_and_colors() {
+        let shape = shape(
+            Polyline::new(vec![Vec2::ZERO, vec2(2., 0.)])
+                .set_widths(vec![1., 0.])
+                .set_colors(vec![Color::RED, Color::BLUE]),
+        );
+        assert_area(&shape, 1.);
+        let colors = shape.point_colors.unwrap();
+        for ((p, _), color) in shape.points.iter().zip(colors) {
+            assert_eq!(color, if p.x == 0. { Color::RED } else { Color::BLUE });
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+
+use super::*;
+
+/// Axis-aligned bounding box
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Aabb {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Aabb {
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min, max }
+    }
+
+    /// Smallest box containing all `points`, `None` if there are none
+    pub fn from_points(points: impl IntoIterator<Item = Vec2>) -> Option<Self> {
+        points
+            .into_iter()
+            .map(|p| Self::new(p, p))
+            .reduce(|a, b| a.union(b))
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) / 2.
+    }
+
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Whether the boxes overlap, touching counts
+    pub fn intersects(&self, other: Aabb) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    /// Smallest box containing both
+    pub fn union(self, other: Aabb) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// Box grown by `margin` on every side
+    pub fn expand(self, margin: f32) -> Self {
+        Self::new(self.min - margin, self.max + margin)
+    }
+}
+
+/// Where a ray first crosses the outline of a shape
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub point: Vec2,
+    /// Unit normal of the crossed edge, facing against the ray
+    pub normal: Vec2,
+    /// Distance from the ray origin in multiples of the ray direction
+    pub distance: f32,
+}
+
+fn closest_on_segment(a: Vec2, b: Vec2, p: Vec2) -> Vec2 {
+    let d = b - a;
+    let t = match d.length_squared() {
+        length if length > 0. => ((p - a).dot(d) / length).clamp(0., 1.),
+        _ => 0.,
+    };
+    a + d * t
+}
+
+impl<T: Textures> Shape<T> {
+    /// Filled triangles, shapes which can't be triangulated are empty like when drawn
+    fn triangles(&self) -> impl Iterator<Item = [Vec2; 3]> + '_ {
+        let indices = self.triangulate().unwrap_or_default();
+        let points = self.points.iter().chain(self.holes.iter().flatten());
+        let points = points.map(|(p, _)| *p).collect::<Vec<_>>();
+        (0..indices.len() / 3).map(move |i| [0, 1, 2].map(|j| points[indices[i * 3 + j] as usize]))
+    }
+
+    /// Edges of the outline and the holes. For meshes these are the triangle edges not shared
+    /// with another triangle.
+    fn edges(&self) -> Vec<(Vec2, Vec2)> {
+        let Some(indices) = &self.indices else {
+            return self
+                .contours()
+                .flat_map(|contour| {
+                    let n = contour.len();
+                    (0..n).map(move |i| (contour[i], contour[(i + 1) % n]))
+                })
+                .collect();
+        };
+
+        let mut edges: HashMap<(u32, u32), (u32, u32, usize)> = HashMap::new();
+        for triangle in indices.chunks_exact(3) {
+            for (a, b) in [(0, 1), (1, 2), (2, 0)].map(|(i, j)| (triangle[i], triangle[j])) {
+                edges.entry((a.min(b), a.max(b))).or_insert((a, b, 0)).2 += 1;
+            }
+        }
+        edges
+            .into_values()
+            .filter(|(_, _, count)| *count == 1)
+            .map(|(a, b, _)| (self.points[a as usize].0, self.points[b as usize].0))
+            .collect()
+    }
+
+    /// Whether `point` lies in the filled area, holes and the fill rule included
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        if self.indices.is_some() {
+            return self.triangles().any(|[a, b, c]| {
+                let sides = [(a, b), (b, c), (c, a)].map(|(p, q)| (q - p).perp_dot(point - p));
+                sides.iter().all(|side| *side >= 0.) || sides.iter().all(|side| *side <= 0.)
+            });
+        }
+
+        let winding: i32 = self
+            .contours()
+            .map(|contour| winding_number(contour.iter().map(|p| p.as_dvec2()), point.as_dvec2()))
+            .sum();
+        match self.fill_rule {
+            FillRule::EvenOdd => winding % 2 != 0,
+            FillRule::NonZero => winding != 0,
+        }
+    }
+
+    /// Bounds of all points, `None` for shapes without points
+    pub fn aabb(&self) -> Option<Aabb> {
+        Aabb::from_points(
+            self.points
+                .iter()
+                .chain(self.holes.iter().flatten())
+                .map(|(p, _)| *p),
+        )
+    }
+
+    /// Filled area, overlapping mesh triangles are counted repeatedly
+    pub fn area(&self) -> f32 {
+        self.triangles()
+            .map(|[a, b, c]| (b - a).perp_dot(c - a).abs() / 2.)
+            .sum()
+    }
+
+    /// Center of mass of the filled area, `None` if it is empty
+    pub fn centroid(&self) -> Option<Vec2> {
+        let (weighted, area) = self
+            .triangles()
+            .fold((Vec2::ZERO, 0.), |(sum, area), [a, b, c]| {
+                let triangle = (b - a).perp_dot(c - a).abs() / 2.;
+                (sum + (a + b + c) / 3. * triangle, area + triangle)
+            });
+        (area > 0.).then(|| weighted / area)
+    }
+
+    /// Length of the outline and of all holes
+    pub fn perimeter(&self) -> f32 {
+        self.edges().iter().map(|(a, b)| a.distance(*b)).sum()
+    }
+
+    /// Nearest point of the filled area, which is `point` itself if it lies inside.
+    /// `None` for shapes without edges.
+    pub fn closest_point(&self, point: Vec2) -> Option<Vec2> {
+        if self.contains_point(point) {
+            return Some(point);
+        }
+        self.edges()
+            .into_iter()
+            .map(|(a, b)| closest_on_segment(a, b, point))
+            .min_by(|a, b| {
+                a.distance_squared(point)
+                    .total_cmp(&b.distance_squared(point))
+            })
+    }
+
+    /// First crossing of the outline or a hole by the ray from `origin` along `direction`.
+    /// Rays starting inside the shape hit the edge they leave it through.
+    pub fn ray_intersection(&self, origin: Vec2, direction: Vec2) -> Option<RayHit> {
+        self.edges()
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let edge = b - a;
+                let denominator = direction.perp_dot(edge);
+                if denominator == 0. {
+                    return None;
+                }
+                let distance = (a - origin).perp_dot(edge) / denominator;
+                let t = (a - origin).perp_dot(direction) / denominator;
+                if distance < 0. || !(0. ..=1.).contains(&t) {
+                    return None;
+                }
+
+                let normal = edge.perp().normalize();
+                Some(RayHit {
+                    point: origin + direction * distance,
+                    normal: match normal.dot(direction) > 0. {
+                        true => -normal,
+                        false => normal,
+                    },
+                    distance,
+                })
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestShape = Shape<TestTextures>;
+
+    fn framed(fill_rule: FillRule) -> TestShape {
+        // The hole winds the same way as the outline
+        TestShape::new(vec![vec2(0., 0.), vec2(3., 0.), vec2(3., 3.), vec2(0., 3.)])
+            .add_hole(vec![vec2(1., 1.), vec2(2., 1.), vec2(2., 2.), vec2(1., 2.)])
+            .set_fill_rule(fill_rule)
+    }
+
+    #[test]
+    fn contains_points() {
+        let (even_odd, non_zero) = (framed(FillRule::EvenOdd), framed(FillRule::NonZero));
+        assert!(even_odd.contains_point(vec2(0.5, 0.5)));
+        assert!(!even_odd.contains_point(vec2(1.5, 1.5)));
+        assert!(non_zero.contains_point(vec2(1.5, 1.5)));
+        assert!(!non_zero.contains_point(vec2(4., 1.5)));
+
+        let mesh = TestShape::from_mesh(
+            vec![vec2(0., 0.), vec2(1., 0.), vec2(0., 1.)],
+            vec![0, 1, 2],
+        );
+        assert!(mesh.contains_point(vec2(0.25, 0.25)));
+        assert!(!mesh.contains_point(vec2(0.75, 0.75)));
+    }
+
+    #[test]
+    fn measures() {
+        let even_odd = framed(FillRule::EvenOdd);
+        assert!((even_odd.area() - 8.).abs() < 1e-5);
+        assert!((framed(FillRule::NonZero).area() - 9.).abs() < 1e-5);
+        assert!((even_odd.perimeter() - 16.).abs() < 1e-5);
+        assert_eq!(
+            even_odd.aabb(),
+            Some(Aabb::new(Vec2::ZERO, Vec2::splat(3.)))
+        );
+        assert!(even_odd
+            .centroid()
+            .unwrap()
+            .abs_diff_eq(Vec2::splat(1.5), 1e-5));
+        assert_eq!(even_odd.closest_point(vec2(1.5, 1.2)), Some(vec2(1.5, 1.)));
+    }
+
+    #[test]
+    fn rays() {
+        let hit = framed(FillRule::EvenOdd)
+            .ray_intersection(vec2(-1., 1.5), Vec2::X)
+            .unwrap();
+        assert_eq!(hit.point, vec2(0., 1.5));
+        assert_eq!(hit.normal, -Vec2::X);
+        assert_eq!(hit.distance, 1.);
+        assert!(framed(FillRule::EvenOdd)
+            .ray_intersection(vec2(-1., 1.5), -Vec2::X)
+            .is_none());
+    }
+}
@@ -0,0 +1,220 @@
+use super::*;
+
+/// Upper bound for adaptive segment counts
+const MAX_SEGMENTS: f32 = 1024.;
+
+/// How many segments the curved parts of a shape are approximated with, constructors taking
+/// it also accept a plain `usize`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Segments {
+    /// Fixed count, see the constructor for which part of the shape it covers
+    Fixed(usize),
+    /// Enough segments to keep the outline within `tolerance` pixels of the curve, where one
+    /// unit of the shape is `pixels_per_unit` pixels large on screen
+    Adaptive {
+        pixels_per_unit: f32,
+        tolerance: f32,
+    },
+}
+
+impl From<usize> for Segments {
+    fn from(segments: usize) -> Self {
+        Self::Fixed(segments)
+    }
+}
+
+impl Segments {
+    /// Adaptive segments for shapes drawn with `gtransform`, the transform from shape units to
+    /// clip space including the camera, on a surface of the size of `graphics`
+    pub fn adaptive<T: Textures>(
+        graphics: &Graphics<T>,
+        gtransform: GTransform,
+        tolerance: f32,
+    ) -> Self {
+        let size = vec2(graphics.size.width as f32, graphics.size.height as f32);
+        Self::for_surface(size, gtransform, tolerance)
+    }
+
+    /// Adaptive segments on a surface of `size` pixels, see [`Segments::adaptive`]
+    fn for_surface(size: Vec2, gtransform: GTransform, tolerance: f32) -> Self {
+        // Clip space spans two units across the surface
+        Self::Adaptive {
+            pixels_per_unit: (gtransform.scale.abs() * size / 2.).max_element(),
+            tolerance,
+        }
+    }
+
+    /// Segments for an arc of `radius` sweeping `sweep` radians, at least one per quarter
+    /// turn when adaptive
+    pub fn count(&self, radius: f32, sweep: f32) -> usize {
+        match *self {
+            Self::Fixed(segments) => segments,
+            Self::Adaptive {
+                pixels_per_unit,
+                tolerance,
+            } => {
+                let radius = radius.abs() * pixels_per_unit;
+                // Largest angle whose chord stays within the tolerance
+                let step = 2. * (1. - (tolerance / radius).min(1.)).acos();
+                (sweep.abs() / step.min(std::f32::consts::FRAC_PI_2))
+                    .ceil()
+                    .clamp(1., MAX_SEGMENTS) as usize
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+    use super::*;
+
+    type TestShape = Shape<TestTextures>;
+
+    fn points(shape: &TestShape) -> Vec<Vec2> {
+        shape.points.iter().map(|(p, _)| *p).collect()
+    }
+
+    fn assert_close(a: f32, b: f32, epsilon: f32) {
+        assert!((a - b).abs() < epsilon, "{a} instead of {b}");
+    }
+
+    #[test]
+    fn adaptive_uses_the_larger_scale() {
+        let gtransform = GTransform::from_scale(vec2(0.01, -0.02));
+        assert_eq!(
+            Segments::for_surface(vec2(800., 600.), gtransform, 0.5),
+            Segments::Adaptive {
+                pixels_per_unit: 6.,
+                tolerance: 0.5,
+            }
+        );
+    }
+
+    #[test]
+    fn counts_stay_within_tolerance() {
+        assert_eq!(Segments::from(7).count(100., TAU), 7);
+
+        let segments = Segments::Adaptive {
+            pixels_per_unit: 10.,
+            tolerance: 0.1,
+        };
+        // Distance between the curve and the chord in the middle of each segment
+        let error =
+            |radius: f32, sweep: f32, n: usize| radius * 10. * (1. - (sweep / n as f32 / 2.).cos());
+        for (radius, sweep) in [(10., TAU), (-10., TAU), (3., PI), (50., -FRAC_PI_2)] {
+            let n = segments.count(radius, sweep);
+            assert!(error(radius.abs(), sweep, n) <= 0.1);
+            assert!(error(radius.abs(), sweep, n - 1) > 0.1);
+        }
+
+        // Tiny curves get a segment per quarter turn, huge ones are capped
+        assert_eq!(segments.count(0.001, TAU), 4);
+        assert_eq!(segments.count(0., PI), 2);
+        assert_eq!(segments.count(1., 0.), 1);
+        assert_eq!(segments.count(1e9, TAU), MAX_SEGMENTS as usize);
+    }
+
+    #[test]
+    fn rounded_rects() {
+        let rect = TestShape::from_rounded_rect(vec2(4., 2.), [0.5; 4], 4);
+        assert_eq!(rect.points.len(), 4 * 5);
+        assert!(points(&rect)[0].distance(vec2(-2., -0.5)) < 1e-6);
+        // The corners form a 16-gon of radius 0.5
+        assert_close(
+            rect.area(),
+            8. - 4. * 0.25 + 8. * 0.25 * (TAU / 16.).sin(),
+            1e-4,
+        );
+
+        // Radii filling the sides share the end points of their arcs
+        let circle = TestShape::from_rounded_rect(vec2(2., 2.), [1.; 4], 4);
+        assert_eq!(circle.points.len(), 16);
+        let square = TestShape::from_rounded_rect(vec2(4., 2.), [0., -1., 0., 0.], 4);
+        assert_eq!(
+            points(&square),
+            [(-2., -1.), (2., -1.), (2., 1.), (-2., 1.)].map(Vec2::from)
+        );
+
+        // All radii shrink until the largest one fits, leaving a quarter circle
+        let quarter = TestShape::from_rounded_rect(vec2(2., 2.), [3., 0., 0., 0.], 64);
+        assert_close(quarter.area(), PI, 0.01);
+    }
+
+    #[test]
+    fn arcs_and_sectors() {
+        let arc = TestShape::from_arc(2., 1., 0., PI, 8);
+        let outline = points(&arc);
+        assert_eq!(outline.len(), 2 * 9);
+        // Both ends of the sweep are included
+        assert_eq!(outline[0], vec2(2.5, 0.));
+        assert!(outline[8].distance(vec2(-2.5, 0.)) < 1e-5);
+        assert!(outline[9].distance(vec2(-1.5, 0.)) < 1e-5);
+        assert_close(arc.area(), PI / 2. * (2.5 * 2.5 - 1.5 * 1.5), 0.2);
+        assert_close(
+            TestShape::from_arc(2., 1., 0., -PI, 8).area(),
+            arc.area(),
+            1e-4,
+        );
+        assert_eq!(TestShape::from_arc(2., 1., 0., TAU, 8).holes.len(), 1);
+
+        let sector = TestShape::from_sector(1., 0., FRAC_PI_2, 4);
+        let outline = points(&sector);
+        assert_eq!(outline.len(), 6);
+        assert_eq!(outline[..2], [Vec2::ZERO, vec2(1., 0.)]);
+        assert!(outline[5].distance(vec2(0., 1.)) < 1e-5);
+        assert_eq!(TestShape::from_sector(1., 0., -TAU, 12).points.len(), 12);
+    }
+
+    #[test]
+    fn rings_stars_and_capsules() {
+        let ring = TestShape::from_ring(2., 1., 16);
+        assert_eq!(ring.points.len(), 16);
+        assert_eq!(ring.holes.len(), 1);
+        assert_eq!(ring.holes[0].len(), 16);
+        assert!(!ring.contains_point(Vec2::ZERO) && ring.contains_point(vec2(1.5, 0.)));
+
+        let star = TestShape::from_star(5, 1., 0.5);
+        let outline = points(&star);
+        assert_eq!(outline.len(), 10);
+        assert!(outline[0].distance(vec2(0., 1.)) < 1e-6);
+        assert_close(outline[1].length(), 0.5, 1e-6);
+        assert!(star.contains_point(Vec2::ZERO));
+
+        let capsule = TestShape::from_capsule(2., 1., 8);
+        assert_eq!(capsule.points.len(), 2 * 9);
+        assert_close(capsule.area(), 4. + PI, 0.1);
+        let aabb = capsule.aabb().unwrap();
+        assert!(aabb.min.distance(vec2(-2., -1.)) < 1e-5);
+        assert!(aabb.max.distance(vec2(2., 1.)) < 1e-5);
+    }
+
+    #[test]
+    fn degenerate_radii() {
+        let adaptive = Segments::Adaptive {
+            pixels_per_unit: 100.,
+            tolerance: 0.5,
+        };
+        for segments in [Segments::Fixed(0), Segments::Fixed(8), adaptive] {
+            for radius in [0., -1.] {
+                let shapes = [
+                    TestShape::from_circle(segments).apply(GTransform::from_inflation(radius)),
+                    TestShape::from_rounded_rect(Vec2::splat(radius), [radius; 4], segments),
+                    TestShape::from_arc(radius, 1., 0., PI, segments),
+                    TestShape::from_arc(1., radius, 0., TAU, segments),
+                    TestShape::from_sector(radius, 0., PI, segments),
+                    TestShape::from_ring(1., radius, segments),
+                    TestShape::from_ring(radius, radius, segments),
+                    TestShape::from_star(5, radius, radius),
+                    TestShape::from_capsule(radius, radius, segments),
+                ];
+                for shape in shapes {
+                    assert!(shape.points.iter().all(|(p, _)| p.is_finite()));
+                    let _ = shape.try_into_geometry();
+                }
+            }
+        }
+        assert!(TestShape::from_star(0, 1., 0.5).points.is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+use super::boolean::positive_area;
+use super::polyline::{DEFAULT_MITER_LIMIT, ROUND_SEGMENTS};
+use super::*;
+
+/// Where the stroke lies relative to the outline of the shape
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StrokeAlignment {
+    Inside,
+    #[default]
+    Center,
+    Outside,
+}
+
+/// Moves every edge of the closed `contour` by `distance` along its outward normal, corners
+/// opening up a gap are filled according to `join`. Works for either winding order.
+fn offset_contour(contour: &[Vec2], distance: f32, join: LineJoin) -> Vec<Vec2> {
+    let n = contour.len();
+    if n < 3 || distance == 0. {
+        return contour.to_vec();
+    }
+
+    // Left normals point inwards for counter-clockwise contours
+    let orientation = signed_area(contour).signum();
+    let mut offset = Vec::with_capacity(n);
+
+    for i in 0..n {
+        let (previous, p, next) = (contour[(i + n - 1) % n], contour[i], contour[(i + 1) % n]);
+        let (d0, d1) = ((p - previous).normalize(), (next - p).normalize());
+        let (m0, m1) = (-d0.perp() * orientation, -d1.perp() * orientation);
+
+        let bisector = (m0 + m1).normalize_or_zero();
+        let cos_half = bisector.dot(m0);
+        // Convex corners open up when growing and reflex ones when shrinking
+        let opens = d0.perp_dot(d1) * orientation * distance > 0.;
+
+        match join {
+            _ if !opens && cos_half > f32::EPSILON => {
+                offset.push(p + bisector * distance / cos_half)
+            }
+            LineJoin::Miter if cos_half > f32::EPSILON && 1. / cos_half <= DEFAULT_MITER_LIMIT => {
+                offset.push(p + bisector * distance / cos_half)
+            }
+            LineJoin::Miter | LineJoin::Bevel => {
+                offset.extend([p + m0 * distance, p + m1 * distance])
+            }
+            LineJoin::Round => {
+                let sweep = m0.angle_between(m1);
+                let steps = (sweep.abs() / std::f32::consts::PI * ROUND_SEGMENTS)
+                    .ceil()
+                    .max(1.) as usize;
+                offset.extend((0..=steps).map(|step| {
+                    let rotation = Vec2::from_angle(sweep * step as f32 / steps as f32);
+                    p + rotation.rotate(m0) * distance
+                }));
+            }
+        }
+    }
+
+    offset
+}
+
+impl<T: Textures> Shape<T> {
+    /// Contours of the filled area moved outwards by `distance`, overlaps at concave corners
+    /// are merged
+    fn offset_outline(&self, distance: f32, join: LineJoin) -> Vec<Vec<Vec2>> {
+        let outline = self.outline();
+        if distance == 0. {
+            return outline;
+        }
+        let offset = outline
+            .iter()
+            .map(|contour| {
+                // Holes wind clockwise, so they shrink while the shape grows
+                offset_contour(contour, distance * signed_area(contour).signum(), join)
+            })
+            .collect::<Vec<_>>();
+        positive_area(&offset)
+    }
+
+    /// Grows the filled area by `distance`, or shrinks it when negative, holes shrink or grow
+    /// accordingly. Meshes are offset around the outline of their triangles. Insetting by
+    /// more than the shape is thick leaves an empty shape. Texture coordinates are
+    /// recalculated.
+    pub fn offset(&self, distance: f32, join: LineJoin) -> Self {
+        // Contours moved inwards turn inside out once they pass the middle of the shape
+        if distance < 0. {
+            return self
+                .difference(&self.band(-distance, join))
+                .set_fill_rule(FillRule::NonZero);
+        }
+        self.with_contours(self.offset_outline(distance, join))
+            .set_fill_rule(FillRule::NonZero)
+    }
+
+    /// Mesh covering everything within `distance` of the outline and the holes
+    fn band(&self, distance: f32, join: LineJoin) -> Self {
+        Self::from_meshes(self.outline().into_iter().map(|contour| {
+            Polyline::new(contour)
+                .set_thickness(2. * distance)
+                .set_join(join)
+                .set_closed(true)
+                .into()
+        }))
+    }
+
+    /// Rings of the given width following the outline and the holes, keeps texture, colour
+    /// and z. Inside strokes fill the parts of the shape thinner than `width`.
+    pub fn stroke(&self, width: f32, alignment: StrokeAlignment, join: LineJoin) -> Self {
+        let center_distance = match alignment {
+            StrokeAlignment::Inside => return self.intersect(&self.band(width, join)),
+            StrokeAlignment::Center => 0.,
+            StrokeAlignment::Outside => width / 2.,
+        };
+
+        let rings = self
+            .offset_outline(center_distance, join)
+            .into_iter()
+            .map(|center_line| {
+                Polyline::new(center_line)
+                    .set_thickness(width)
+                    .set_join(join)
+                    .set_closed(true)
+                    .into()
+            });
+
+        Self::from_meshes(rings)
+            .set_texture(self.texture.clone())
+            .set_color(self.color)
+            .set_z(self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use super::*;
+
+    type TestShape = Shape<TestTextures>;
+
+    fn l_shape() -> TestShape {
+        Shape::new(vec![
+            vec2(0., 0.),
+            vec2(2., 0.),
+            vec2(2., 1.),
+            vec2(1., 1.),
+            vec2(1., 2.),
+            vec2(0., 2.),
+        ])
+    }
+
+    fn assert_area(shape: &TestShape, area: f32) {
+        assert!(shape.clone().try_into_geometry().is_ok());
+        assert!(
+            (shape.area() - area).abs() < 1e-3,
+            "area {} instead of {area}",
+            shape.area()
+        );
+    }
+
+    #[test]
+    fn grows_concave_shapes() {
+        let grown = l_shape().offset(0.1, LineJoin::Miter);
+        assert_area(&grown, 2.2 * 2.2 - 1.);
+        assert!(grown.contains_point(vec2(1.05, 1.05)));
+        assert!(!grown.contains_point(vec2(1.15, 1.15)));
+
+        // Clockwise contours grow the same way
+        let mut points = l_shape()
+            .points
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect::<Vec<_>>();
+        points.reverse();
+        assert_area(
+            &Shape::new(points).offset(0.1, LineJoin::Miter),
+            2.2 * 2.2 - 1.,
+        );
+    }
+
+    #[test]
+    fn insets_concave_shapes() {
+        assert_area(&l_shape().offset(-0.1, LineJoin::Miter), 1.8 * 1.8 - 1.);
+        for join in [LineJoin::Bevel, LineJoin::Round] {
+            assert!(l_shape()
+                .offset(-0.1, join)
+                .clone()
+                .try_into_geometry()
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn drops_collapsed_parts() {
+        // A square with a thin tab on top, which disappears when insetting
+        let tabbed = TestShape::new(vec![
+            vec2(0., 0.),
+            vec2(4., 0.),
+            vec2(4., 4.),
+            vec2(2.2, 4.),
+            vec2(2.2, 5.),
+            vec2(1.8, 5.),
+            vec2(1.8, 4.),
+            vec2(0., 4.),
+        ]);
+        let inset = tabbed.offset(-0.3, LineJoin::Miter);
+        assert_area(&inset, 3.4 * 3.4);
+        assert!(!inset.contains_point(vec2(2., 4.5)));
+
+        // Also when the contours would pass each other
+        for distance in [0.6, 1.2, 5.] {
+            let collapsed = l_shape().offset(-distance, LineJoin::Miter);
+            assert!(collapsed.points.is_empty());
+            assert_eq!(collapsed.area(), 0.);
+        }
+    }
+
+    #[test]
+    fn offsets_holes_and_meshes() {
+        let framed = TestShape::from_square_centered()
+            .apply(GTransform::from_inflation(4.))
+            .add_hole(vec![
+                vec2(-1., -1.),
+                vec2(1., -1.),
+                vec2(1., 1.),
+                vec2(-1., 1.),
+            ]);
+        assert_area(&framed.offset(0.1, LineJoin::Miter), 4.2 * 4.2 - 1.8 * 1.8);
+
+        let mesh = TestShape::from_mesh(
+            vec![vec2(0., 0.), vec2(1., 0.), vec2(1., 1.), vec2(0., 1.)],
+            vec![0, 1, 2, 0, 2, 3],
+        );
+        assert_area(&mesh.offset(0.1, LineJoin::Miter), 1.2 * 1.2);
+    }
+
+    #[test]
+    fn strokes_follow_the_outline() {
+        let square = TestShape::from_mesh(
+            vec![vec2(0., 0.), vec2(1., 0.), vec2(1., 1.), vec2(0., 1.)],
+            vec![0, 1, 2, 0, 2, 3],
+        );
+        let inside = square.stroke(0.2, StrokeAlignment::Inside, LineJoin::Miter);
+        assert!((inside.area() - (1. - 0.6 * 0.6)).abs() < 1e-3);
+        let outside = square.stroke(0.2, StrokeAlignment::Outside, LineJoin::Miter);
+        assert!((outside.area() - (1.4 * 1.4 - 1.)).abs() < 1e-3);
+        assert_eq!(inside.color, square.color);
+
+        // Strokes wider than the L is thick fill it
+        for width in [1.2, 5.] {
+            let filled = l_shape().stroke(width, StrokeAlignment::Inside, LineJoin::Miter);
+            assert_area(&filled, 3.);
+        }
+        // The tab is thinner than the stroke and filled, the square gets a ring
+        let tabbed = TestShape::new(vec![
+            vec2(0., 0.),
+            vec2(4., 0.),
+            vec2(4., 4.),
+            vec2(2.2, 4.),
+            vec2(2.2, 5.),
+            vec2(1.8, 5.),
+            vec2(1.8, 4.),
+            vec2(0., 4.),
+        ]);
+        let ring = tabbed.stroke(0.6, StrokeAlignment::Inside, LineJoin::Miter);
+        assert_area(&ring, 16. + 0.4 - 2.8 * 2.8);
+        assert!(ring.contains_point(vec2(2., 4.8)) && !ring.contains_point(Vec2::splat(2.)));
+    }
+}
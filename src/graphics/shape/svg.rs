@@ -0,0 +1,750 @@
+use anyhow::{anyhow, bail, ensure, Context};
+use glam::{Affine2, Mat2};
+use roxmltree::Node;
+
+use super::*;
+
+/// Curves are flattened to this distance in SVG user units
+const SVG_TOLERANCE: f32 = 0.1;
+
+fn parse_number(value: &str) -> anyhow::Result<f32> {
+    let value = value.trim();
+    value
+        .strip_suffix("px")
+        .unwrap_or(value)
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid number {value}"))
+}
+
+/// Numbers in SVG attributes can be separated by whitespace, commas or nothing at all
+struct Tokens<'a> {
+    source: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source: source.as_bytes(),
+            position: 0,
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        while let Some(c) = self.source.get(self.position) {
+            if !c.is_ascii_whitespace() && *c != b',' {
+                return Some(*c);
+            }
+            self.position += 1;
+        }
+        None
+    }
+
+    fn has_number(&mut self) -> bool {
+        matches!(self.peek(), Some(b'0'..=b'9' | b'-' | b'+' | b'.'))
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        let c = self.peek().filter(u8::is_ascii_alphabetic)?;
+        self.position += 1;
+        Some(c)
+    }
+
+    fn number(&mut self) -> anyhow::Result<f32> {
+        self.peek();
+        let start = self.position;
+        let digits = |tokens: &mut Self| {
+            while tokens
+                .source
+                .get(tokens.position)
+                .map_or(false, u8::is_ascii_digit)
+            {
+                tokens.position += 1;
+            }
+        };
+
+        if matches!(self.source.get(self.position), Some(b'-' | b'+')) {
+            self.position += 1;
+        }
+        digits(self);
+        if self.source.get(self.position) == Some(&b'.') {
+            self.position += 1;
+            digits(self);
+        }
+        if matches!(self.source.get(self.position), Some(b'e' | b'E'))
+            && matches!(
+                self.source.get(self.position + 1),
+                Some(b'0'..=b'9' | b'-' | b'+')
+            )
+        {
+            self.position += 2;
+            digits(self);
+        }
+
+        let number = std::str::from_utf8(&self.source[start..self.position])?;
+        number
+            .parse()
+            .with_context(|| format!("expected a number at {number:?}"))
+    }
+
+    fn point(&mut self) -> anyhow::Result<Vec2> {
+        Ok(vec2(self.number()?, self.number()?))
+    }
+
+    /// Arc flags may be written without separators, like `a1 1 0 00 1 1`
+    fn flag(&mut self) -> anyhow::Result<bool> {
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => bail!("expected an arc flag"),
+        };
+        self.position += 1;
+        Ok(flag)
+    }
+
+    fn numbers(mut self) -> anyhow::Result<Vec<f32>> {
+        let mut numbers = vec![];
+        while self.peek().is_some() {
+            numbers.push(self.number()?);
+        }
+        Ok(numbers)
+    }
+}
+
+/// Elliptical arc in endpoint notation, converted to center notation as described in the
+/// implementation notes of the SVG specification
+fn svg_arc(
+    path: Path,
+    from: Vec2,
+    radii: Vec2,
+    rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    to: Vec2,
+) -> Path {
+    if from == to {
+        return path;
+    }
+    let mut radii = radii.abs();
+    if radii.x == 0. || radii.y == 0. {
+        return path.line_to(to);
+    }
+
+    let d = Vec2::from_angle(-rotation).rotate((from - to) / 2.);
+    let lambda = (d / radii).length_squared();
+    if lambda > 1. {
+        radii *= lambda.sqrt();
+    }
+
+    let r2 = radii * radii;
+    let numerator = r2.x * r2.y - r2.x * d.y * d.y - r2.y * d.x * d.x;
+    let denominator = r2.x * d.y * d.y + r2.y * d.x * d.x;
+    let sign = if large_arc == sweep { -1. } else { 1. };
+    let coefficient = sign * (numerator / denominator).max(0.).sqrt();
+    let center_rotated = coefficient * vec2(radii.x * d.y / radii.y, -radii.y * d.x / radii.x);
+    let center = Vec2::from_angle(rotation).rotate(center_rotated) + (from + to) / 2.;
+
+    let u = (d - center_rotated) / radii;
+    let v = (-d - center_rotated) / radii;
+    let mut delta = u.angle_between(v);
+    if !sweep && delta > 0. {
+        delta -= std::f32::consts::TAU;
+    } else if sweep && delta < 0. {
+        delta += std::f32::consts::TAU;
+    }
+
+    path.ellipse_arc(center, radii, rotation, u.y.atan2(u.x), delta)
+}
+
+impl Path {
+    /// Parses the `d` attribute of an SVG `<path>`, coordinates stay in SVG space with y
+    /// pointing down
+    pub fn from_svg_data(data: &str) -> anyhow::Result<Self> {
+        let mut tokens = Tokens::new(data);
+        let mut path = Path::new();
+        let mut command = None;
+        // Control point of the previous curve, reflected by the smooth curve commands
+        let mut last_control: Option<(u8, Vec2)> = None;
+
+        while tokens.peek().is_some() {
+            if let Some(c) = tokens.command() {
+                command = Some(c);
+            } else if command.is_none() || !tokens.has_number() {
+                bail!("invalid path data {data:?}");
+            }
+            let c = command.unwrap();
+
+            let position = path.position().unwrap_or(Vec2::ZERO);
+            let relative = c.is_ascii_lowercase();
+            let base = if relative { position } else { Vec2::ZERO };
+            let reflected = |kinds: &[u8]| match last_control {
+                Some((kind, control)) if kinds.contains(&kind) => 2. * position - control,
+                _ => position,
+            };
+
+            let mut control = None;
+            path = match c.to_ascii_uppercase() {
+                b'M' => {
+                    // Further coordinate pairs are implicit line commands
+                    command = Some(if relative { b'l' } else { b'L' });
+                    path.move_to(base + tokens.point()?)
+                }
+                b'L' => path.line_to(base + tokens.point()?),
+                b'H' => path.line_to(vec2(base.x + tokens.number()?, position.y)),
+                b'V' => path.line_to(vec2(position.x, base.y + tokens.number()?)),
+                b'C' => {
+                    let (c1, c2) = (base + tokens.point()?, base + tokens.point()?);
+                    control = Some((b'C', c2));
+                    path.cubic_to(c1, c2, base + tokens.point()?)
+                }
+                b'S' => {
+                    let c1 = reflected(b"C");
+                    let c2 = base + tokens.point()?;
+                    control = Some((b'C', c2));
+                    path.cubic_to(c1, c2, base + tokens.point()?)
+                }
+                b'Q' => {
+                    let c1 = base + tokens.point()?;
+                    control = Some((b'Q', c1));
+                    path.quad_to(c1, base + tokens.point()?)
+                }
+                b'T' => {
+                    let c1 = reflected(b"Q");
+                    control = Some((b'Q', c1));
+                    path.quad_to(c1, base + tokens.point()?)
+                }
+                b'A' => {
+                    let radii = tokens.point()?;
+                    let rotation = tokens.number()?.to_radians();
+                    let (large_arc, sweep) = (tokens.flag()?, tokens.flag()?);
+                    let to = base + tokens.point()?;
+                    svg_arc(path, position, radii, rotation, large_arc, sweep, to)
+                }
+                b'Z' => {
+                    command = None;
+                    path.close()
+                }
+                _ => bail!("unknown path command {}", c as char),
+            };
+            last_control = control;
+        }
+
+        Ok(path)
+    }
+}
+
+/// Presentation attributes, inherited from the parent elements
+#[derive(Clone, Debug)]
+struct Style {
+    fill: Option<Color>,
+    fill_rule: FillRule,
+    fill_opacity: f32,
+    stroke: Option<Color>,
+    stroke_opacity: f32,
+    stroke_width: f32,
+    join: LineJoin,
+    cap: LineCap,
+    miter_limit: f32,
+    /// Opacity of the element multiplied with the one of its groups
+    opacity: f32,
+    current_color: Color,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fill: Some(Color::BLACK),
+            fill_rule: FillRule::NonZero,
+            fill_opacity: 1.,
+            stroke: None,
+            stroke_opacity: 1.,
+            stroke_width: 1.,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.,
+            opacity: 1.,
+            current_color: Color::BLACK,
+        }
+    }
+}
+
+impl Style {
+    /// Value of a property from the `style` attribute or the presentation attribute
+    fn property<'n>(node: Node<'n, '_>, name: &str) -> Option<&'n str> {
+        let declared = node.attribute("style").and_then(|style| {
+            style.split(';').find_map(|declaration| {
+                let (property, value) = declaration.split_once(':')?;
+                (property.trim() == name).then(|| value.trim())
+            })
+        });
+        declared
+            .or_else(|| node.attribute(name))
+            .filter(|value| *value != "inherit")
+    }
+
+    fn paint(&self, value: &str) -> anyhow::Result<Option<Color>> {
+        Ok(match value {
+            "none" => None,
+            "currentColor" => Some(self.current_color),
+            value if value.starts_with("url(") => {
+                log::warn!("SVG paint servers are not supported, ignoring {value}");
+                None
+            }
+            value => Some(value.parse()?),
+        })
+    }
+
+    /// The style of `node`, `None` if it isn't displayed
+    fn inherit(&self, node: Node) -> anyhow::Result<Option<Self>> {
+        if Self::property(node, "display") == Some("none") {
+            return Ok(None);
+        }
+
+        let mut style = self.clone();
+        let number = |name: &str| Self::property(node, name).map(parse_number).transpose();
+
+        if let Some(color) = Self::property(node, "color") {
+            style.current_color = color.parse()?;
+        }
+        if let Some(fill) = Self::property(node, "fill") {
+            style.fill = style.paint(fill)?;
+        }
+        if let Some(stroke) = Self::property(node, "stroke") {
+            style.stroke = style.paint(stroke)?;
+        }
+        match Self::property(node, "fill-rule") {
+            Some("evenodd") => style.fill_rule = FillRule::EvenOdd,
+            Some("nonzero") => style.fill_rule = FillRule::NonZero,
+            _ => {}
+        }
+        match Self::property(node, "stroke-linejoin") {
+            Some("miter") => style.join = LineJoin::Miter,
+            Some("bevel") => style.join = LineJoin::Bevel,
+            Some("round") => style.join = LineJoin::Round,
+            _ => {}
+        }
+        match Self::property(node, "stroke-linecap") {
+            Some("butt") => style.cap = LineCap::Butt,
+            Some("square") => style.cap = LineCap::Square,
+            Some("round") => style.cap = LineCap::Round,
+            _ => {}
+        }
+        style.fill_opacity = number("fill-opacity")?.unwrap_or(style.fill_opacity);
+        style.stroke_opacity = number("stroke-opacity")?.unwrap_or(style.stroke_opacity);
+        style.stroke_width = number("stroke-width")?.unwrap_or(style.stroke_width);
+        style.miter_limit = number("stroke-miterlimit")?.unwrap_or(style.miter_limit);
+        style.opacity *= number("opacity")?.unwrap_or(1.);
+
+        Ok(Some(style))
+    }
+}
+
+fn parse_transform(value: &str) -> anyhow::Result<Affine2> {
+    let mut transform = Affine2::IDENTITY;
+    for item in value
+        .split(')')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let (name, arguments) = item
+            .trim_start_matches(',')
+            .split_once('(')
+            .ok_or_else(|| anyhow!("invalid transform {value}"))?;
+        let arguments = Tokens::new(arguments).numbers()?;
+
+        transform = transform
+            * match (name.trim(), arguments.as_slice()) {
+                ("matrix", &[a, b, c, d, e, f]) => Affine2::from_cols_array(&[a, b, c, d, e, f]),
+                ("translate", &[x]) => Affine2::from_translation(vec2(x, 0.)),
+                ("translate", &[x, y]) => Affine2::from_translation(vec2(x, y)),
+                ("scale", &[s]) => Affine2::from_scale(Vec2::splat(s)),
+                ("scale", &[x, y]) => Affine2::from_scale(vec2(x, y)),
+                ("rotate", &[angle]) => Affine2::from_angle(angle.to_radians()),
+                ("rotate", &[angle, x, y]) => {
+                    Affine2::from_angle_translation(angle.to_radians(), vec2(x, y))
+                        * Affine2::from_translation(-vec2(x, y))
+                }
+                ("skewX", &[angle]) => Affine2::from_mat2(Mat2::from_cols_array(&[
+                    1.,
+                    0.,
+                    angle.to_radians().tan(),
+                    1.,
+                ])),
+                ("skewY", &[angle]) => Affine2::from_mat2(Mat2::from_cols_array(&[
+                    1.,
+                    angle.to_radians().tan(),
+                    0.,
+                    1.,
+                ])),
+                _ => bail!("invalid transform {item})"),
+            };
+    }
+    Ok(transform)
+}
+
+/// Splits the affine transform into a rotation followed by a scaled and rotated
+/// [`GTransform`], which together also represent skewing
+fn to_gtransforms(transform: Affine2) -> [GTransform; 2] {
+    let [a, b, c, d] = transform.matrix2.to_cols_array();
+    let (e, f, g, h) = ((a + d) / 2., (a - d) / 2., (b + c) / 2., (b - c) / 2.);
+    let (q, r) = (e.hypot(h), f.hypot(g));
+    let (a1, a2) = (g.atan2(f), h.atan2(e));
+
+    [
+        GTransform::default().rotate((a2 - a1) / 2.),
+        GTransform {
+            center: transform.translation,
+            rotation: (a2 + a1) / 2.,
+            scale: vec2(q + r, q - r),
+        },
+    ]
+}
+
+fn element_path(node: Node) -> anyhow::Result<Option<Path>> {
+    let number = |name: &str| {
+        node.attribute(name)
+            .map(parse_number)
+            .transpose()
+            .map(Option::unwrap_or_default)
+    };
+
+    let path = match node.tag_name().name() {
+        "path" => Path::from_svg_data(node.attribute("d").unwrap_or_default())?,
+        "rect" => {
+            let origin = vec2(number("x")?, number("y")?);
+            let size = vec2(number("width")?, number("height")?);
+            let (rx, ry) = (node.attribute("rx"), node.attribute("ry"));
+            let radii = match (rx.or(ry), ry.or(rx)) {
+                (Some(rx), Some(ry)) => vec2(parse_number(rx)?, parse_number(ry)?),
+                _ => Vec2::ZERO,
+            }
+            .min(size / 2.);
+
+            if radii.cmpgt(Vec2::ZERO).all() {
+                let quarter = std::f32::consts::FRAC_PI_2;
+                let corners = [
+                    (origin + size - radii, 0.),
+                    (origin + vec2(radii.x, size.y - radii.y), quarter),
+                    (origin + radii, 2. * quarter),
+                    (origin + vec2(size.x - radii.x, radii.y), 3. * quarter),
+                ];
+                corners
+                    .into_iter()
+                    .fold(Path::new(), |path, (center, angle)| {
+                        path.ellipse_arc(center, radii, 0., angle, quarter)
+                    })
+                    .close()
+            } else {
+                Path::new()
+                    .move_to(origin)
+                    .line_to(origin + vec2(size.x, 0.))
+                    .line_to(origin + size)
+                    .line_to(origin + vec2(0., size.y))
+                    .close()
+            }
+        }
+        "circle" | "ellipse" => {
+            let center = vec2(number("cx")?, number("cy")?);
+            let radii = match node.attribute("r") {
+                Some(r) => Vec2::splat(parse_number(r)?),
+                None => vec2(number("rx")?, number("ry")?),
+            };
+            Path::new()
+                .ellipse_arc(center, radii, 0., 0., std::f32::consts::TAU)
+                .close()
+        }
+        "line" => Path::new()
+            .move_to(vec2(number("x1")?, number("y1")?))
+            .line_to(vec2(number("x2")?, number("y2")?)),
+        tag @ ("polyline" | "polygon") => {
+            let numbers = Tokens::new(node.attribute("points").unwrap_or_default()).numbers()?;
+            let path =
+                numbers
+                    .chunks_exact(2)
+                    .fold(Path::new(), |path, point| match path.position() {
+                        None => path.move_to(vec2(point[0], point[1])),
+                        Some(_) => path.line_to(vec2(point[0], point[1])),
+                    });
+            match tag {
+                "polygon" => path.close(),
+                _ => path,
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(path))
+}
+
+fn element<T: Textures>(
+    node: Node,
+    parent: &Style,
+    parent_transform: Affine2,
+    shapes: &mut Vec<Shape<T>>,
+) -> anyhow::Result<()> {
+    let Some(style) = parent.inherit(node)? else {
+        return Ok(());
+    };
+    let transform = match node.attribute("transform") {
+        Some(transform) => parent_transform * parse_transform(transform)?,
+        None => parent_transform,
+    };
+
+    match node.tag_name().name() {
+        "svg" | "g" | "a" | "switch" => {
+            for child in node.children().filter(Node::is_element) {
+                element(child, &style, transform, shapes)
+                    .with_context(|| format!("in <{}>", child.tag_name().name()))?;
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+    let Some(path) = element_path(node)? else {
+        return Ok(());
+    };
+
+    let gtransforms = to_gtransforms(transform);
+    let scale = gtransforms[1].scale.abs();
+    let path = path.set_tolerance(SVG_TOLERANCE / scale.max_element());
+
+    if let Some(fill) = style.fill {
+        let shape = gtransforms
+            .iter()
+            .fold(path.fill::<T>(), |shape, gtransform| {
+                shape.apply(*gtransform)
+            });
+        if !shape.points.is_empty() {
+            let alpha = fill.a * style.fill_opacity * style.opacity;
+            shapes.push(
+                shape
+                    .set_fill_rule(style.fill_rule)
+                    .update_texture_coords()
+                    .set_color(fill.set_alpha(alpha)),
+            );
+        }
+    }
+
+    if let Some(stroke) = style.stroke.filter(|_| style.stroke_width > 0.) {
+        // Strokes are built after transforming, so their width scales uniformly
+        let width = style.stroke_width * (scale.x * scale.y).sqrt();
+        let lines = path.flatten().into_iter().map(|(points, closed)| {
+            let points = points
+                .into_iter()
+                .map(|p| gtransforms.iter().fold(p, |p, g| g.transform(p)))
+                .collect();
+            Polyline::new(points)
+                .set_thickness(width)
+                .set_join(style.join)
+                .set_cap(style.cap)
+                .set_miter_limit(style.miter_limit)
+                .set_closed(closed)
+                .into()
+        });
+        let alpha = stroke.a * style.stroke_opacity * style.opacity;
+        shapes.push(Shape::from_meshes(lines).set_color(stroke.set_alpha(alpha)));
+    }
+
+    Ok(())
+}
+
+impl<T: Textures> Shape<T> {
+    /// Parses an SVG document into filled and stroked shapes. Coordinates are in SVG user
+    /// units with y flipped to point up and the top left corner of the `viewBox` at the
+    /// origin, `width`, `height` and `preserveAspectRatio` are ignored. Shapes come in paint
+    /// order with z decreasing from below 1 to 0 in steps of `1 / count`, so that later
+    /// elements cover earlier ones. Gradients, text, `<use>` and clipping are not supported.
+    pub fn from_svg(source: &str) -> anyhow::Result<Vec<Self>> {
+        let document = roxmltree::Document::parse(source)?;
+        let root = document.root_element();
+        if !root.has_tag_name("svg") {
+            bail!("expected an <svg> root element");
+        }
+
+        let origin = match root.attribute("viewBox") {
+            Some(view_box) => {
+                let numbers = Tokens::new(view_box).numbers()?;
+                let &[x, y, width, height] = numbers.as_slice() else {
+                    bail!("invalid viewBox {view_box}");
+                };
+                ensure!(width > 0. && height > 0., "viewBox {view_box} is empty");
+                vec2(x, y)
+            }
+            None => Vec2::ZERO,
+        };
+
+        let mut shapes = vec![];
+        let flip = Affine2::from_scale(vec2(1., -1.)) * Affine2::from_translation(-origin);
+        element(root, &Style::default(), flip, &mut shapes)?;
+
+        let count = shapes.len();
+        Ok(shapes
+            .into_iter()
+            .enumerate()
+            .map(|(i, shape)| shape.set_z((count - 1 - i) as f32 / count as f32))
+            .collect())
+    }
+
+    /// Reads an SVG file from disk, see [`Shape::from_svg`].
+    pub fn load_svg(path: impl AsRef<std::path::Path>) -> anyhow::Result<Vec<Self>> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Self::from_svg(&source).with_context(|| format!("failed to parse {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type TestShape = Shape<TestTextures>;
+
+    fn load(source: &str) -> Vec<TestShape> {
+        Shape::from_svg(source).unwrap()
+    }
+
+    /// Compares the area with a relative tolerance, which covers flattened curves
+    fn assert_shape(shape: &TestShape, area: f32, min: Vec2, max: Vec2, color: Color) {
+        let aabb = shape.aabb().unwrap();
+        assert!(
+            (shape.area() - area).abs() <= area * 0.02,
+            "area {} instead of {area}",
+            shape.area()
+        );
+        assert!(aabb.min.abs_diff_eq(min, 0.1), "{aabb:?}");
+        assert!(aabb.max.abs_diff_eq(max, 0.1), "{aabb:?}");
+        assert_eq!(shape.color, color);
+    }
+
+    #[test]
+    fn paths() {
+        let shapes = load(include_str!("../../../tests/fixtures/svg/shapes.svg"));
+        assert_eq!(shapes.len(), 6);
+        // The view box moves its corner at -10, -10 to the origin
+        assert_shape(
+            &shapes[0],
+            200.,
+            vec2(10., -20.),
+            vec2(30., -10.),
+            Color::RED,
+        );
+        assert_shape(
+            &shapes[1],
+            400.,
+            vec2(50., -30.),
+            vec2(70., -10.),
+            Color::BLUE,
+        );
+
+        let (even_odd, non_zero) = (&shapes[2], &shapes[3]);
+        assert_shape(
+            even_odd,
+            1200.,
+            vec2(10., -80.),
+            vec2(50., -40.),
+            Color::GREEN,
+        );
+        assert!(even_odd.contains_point(vec2(12., -45.)));
+        assert!(!even_odd.contains_point(vec2(30., -60.)));
+        assert_shape(
+            non_zero,
+            1600.,
+            vec2(10., -80.),
+            vec2(50., -40.),
+            Color::BLACK,
+        );
+        assert!(non_zero.contains_point(vec2(30., -60.)));
+
+        let half_disk = std::f32::consts::PI * 100. / 2.;
+        let gray = Color::from_hex(0x808080).set_alpha(0.5);
+        assert_shape(
+            &shapes[4],
+            half_disk,
+            vec2(60., -60.),
+            vec2(80., -50.),
+            gray,
+        );
+        // Radii too small to reach the end point are scaled up
+        assert_shape(
+            &shapes[5],
+            half_disk,
+            vec2(20., -100.),
+            vec2(40., -90.),
+            Color::BLUE,
+        );
+    }
+
+    #[test]
+    fn transforms() {
+        let shapes = load(include_str!("../../../tests/fixtures/svg/transforms.svg"));
+        assert_eq!(shapes.len(), 4);
+        assert_shape(
+            &shapes[0],
+            100.,
+            vec2(80., -10.),
+            vec2(90., 0.),
+            Color::GREEN,
+        );
+        assert_shape(
+            &shapes[1],
+            200.,
+            vec2(-20., -10.),
+            vec2(0., 0.),
+            Color::BLACK,
+        );
+
+        let skewed = &shapes[2];
+        assert_shape(skewed, 100., vec2(0., -60.), vec2(20., -50.), Color::BLACK);
+        assert!(skewed.contains_point(vec2(12., -55.)));
+        assert!(!skewed.contains_point(vec2(3., -55.)));
+
+        let circle = std::f32::consts::PI * 100.;
+        let blue = Color::BLUE.set_alpha(0.25);
+        assert_shape(&shapes[3], circle, vec2(10., -90.), vec2(30., -70.), blue);
+    }
+
+    #[test]
+    fn strokes() {
+        let shapes = load(include_str!("../../../tests/fixtures/svg/strokes.svg"));
+        assert_eq!(shapes.len(), 5);
+        assert_shape(&shapes[0], 80., vec2(0., -2.), vec2(20., 2.), Color::BLUE);
+        assert_shape(&shapes[1], 80., vec2(29., -11.), vec2(41., 1.), Color::RED);
+        // Square caps extend the scaled line by half its width
+        let black = Color::BLACK.set_alpha(0.5);
+        assert_shape(&shapes[2], 44., vec2(-1., -41.), vec2(21., -39.), black);
+
+        let (fill, stroke) = (&shapes[3], &shapes[4]);
+        assert_shape(fill, 100., vec2(0., -60.), vec2(10., -50.), Color::YELLOW);
+        assert_shape(stroke, 80., vec2(-1., -61.), vec2(11., -49.), Color::BLACK);
+        assert!(stroke.z < fill.z);
+    }
+
+    #[test]
+    fn later_shapes_are_in_front() {
+        let count = 20_000;
+        let source = format!(
+            "<svg>{}</svg>",
+            "<rect width='1' height='1'/>".repeat(count)
+        );
+        let shapes = load(&source);
+        assert_eq!(shapes.len(), count);
+        assert_eq!(shapes[count - 1].z, 0.);
+        assert!(shapes[0].z < 1.);
+        assert!(shapes.windows(2).all(|pair| pair[1].z < pair[0].z));
+    }
+
+    #[test]
+    fn invalid_documents() {
+        for source in [
+            "<g/>",
+            "<svg viewBox='0 0 10'/>",
+            "<svg viewBox='0 0 0 10'/>",
+            "<svg><path d='M0 0 X'/></svg>",
+            "<svg><rect fill='blurple'/></svg>",
+            "<svg><rect transform='spin(3)'/></svg>",
+        ] {
+            assert!(TestShape::from_svg(source).is_err(), "{source}");
+        }
+    }
+}
@@ -0,0 +1,481 @@
+use std::fmt::Display;
+
+use super::*;
+
+/// Decides which regions enclosed by several contours are filled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FillRule {
+    /// Regions enclosed by an odd number of contours, holes work regardless of winding
+    #[default]
+    EvenOdd,
+    /// Regions where the contours don't cancel out, holes have to wind opposite to the outline
+    NonZero,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriangulationError {
+    /// The edges starting at the two points cross or touch each other, points of holes
+    /// are counted after the points of the outline
+    SelfIntersecting(usize, usize),
+    /// No corner could be clipped off with the given number of points left, usually because
+    /// of rounding errors with nearly collinear points
+    NoEar(usize),
+}
+
+impl Display for TriangulationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SelfIntersecting(a, b) => write!(
+                f,
+                "contours are self-intersecting, edges starting at points {a} and {b} cross"
+            ),
+            Self::NoEar(n) => write!(f, "no ear found with {n} points left to triangulate"),
+        }
+    }
+}
+
+impl std::error::Error for TriangulationError {}
+
+fn cross(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    (b - a).perp_dot(c - a)
+}
+
+fn contour_area(points: &[Vec2], contour: &[u32]) -> f32 {
+    let positions = contour
+        .iter()
+        .map(|i| points[*i as usize])
+        .collect::<Vec<_>>();
+    signed_area(&positions)
+}
+
+/// Convex polygons winding around exactly once can skip the expensive checks
+fn is_convex(points: &[Vec2], polygon: &[u32]) -> bool {
+    let n = polygon.len();
+    let (mut positive, mut negative) = (false, false);
+    let mut total_turn = 0.;
+
+    for i in 0..n {
+        let [a, b, c] = [i, (i + 1) % n, (i + 2) % n].map(|j| points[polygon[j] as usize]);
+        let turn = cross(a, b, c);
+        positive |= turn > 0.;
+        negative |= turn < 0.;
+        total_turn += (b - a).angle_between(c - b);
+    }
+
+    !(positive && negative) && (total_turn.abs() - std::f32::consts::TAU).abs() < 0.1
+}
+
+fn segments_intersect(a0: Vec2, a1: Vec2, b0: Vec2, b1: Vec2) -> bool {
+    let on_segment = |p: Vec2, q: Vec2, r: Vec2| {
+        r.x >= p.x.min(q.x) && r.x <= p.x.max(q.x) && r.y >= p.y.min(q.y) && r.y <= p.y.max(q.y)
+    };
+
+    let [d1, d2, d3, d4] = [
+        cross(b0, b1, a0),
+        cross(b0, b1, a1),
+        cross(a0, a1, b0),
+        cross(a0, a1, b1),
+    ];
+
+    if d1 * d2 < 0. && d3 * d4 < 0. {
+        return true;
+    }
+    (d1 == 0. && on_segment(b0, b1, a0))
+        || (d2 == 0. && on_segment(b0, b1, a1))
+        || (d3 == 0. && on_segment(a0, a1, b0))
+        || (d4 == 0. && on_segment(a0, a1, b1))
+}
+
+/// Whether two edges with a common end point overlap instead of just touching there
+fn overlap_at_shared_point(a0: Vec2, a1: Vec2, b0: Vec2, b1: Vec2) -> Option<bool> {
+    let (shared, a, b) = match () {
+        _ if a0 == b0 => (a0, a1, b1),
+        _ if a0 == b1 => (a0, a1, b0),
+        _ if a1 == b0 => (a1, a0, b1),
+        _ if a1 == b1 => (a1, a0, b0),
+        _ => return None,
+    };
+    Some(cross(shared, a, b) == 0. && (a - shared).dot(b - shared) > 0.)
+}
+
+/// Edges may only touch at common points, which lets contours share corners with their
+/// holes or other contours. Edges are swept along the x axis, so only those overlapping
+/// along it get compared.
+fn find_self_intersection(points: &[Vec2], contours: &[Vec<u32>]) -> Option<(usize, usize)> {
+    let mut edges = contours
+        .iter()
+        .flat_map(|contour| {
+            let n = contour.len();
+            (0..n).map(move |i| (contour[i], contour[(i + 1) % n]))
+        })
+        .map(|(a, b)| {
+            let (p, q) = (points[a as usize], points[b as usize]);
+            (a, b, p.min(q), p.max(q))
+        })
+        .collect::<Vec<_>>();
+    edges.sort_by(|a, b| a.2.x.total_cmp(&b.2.x));
+
+    for (i, &(a0, a1, a_min, a_max)) in edges.iter().enumerate() {
+        let overlapping = edges[i + 1..]
+            .iter()
+            .take_while(|(.., b_min, _)| b_min.x <= a_max.x);
+        for &(b0, b1, b_min, b_max) in overlapping {
+            // Neighbouring edges, including the one closing the contour
+            if a1 == b0 || b1 == a0 || b_min.y > a_max.y || b_max.y < a_min.y {
+                continue;
+            }
+            let [p0, p1, q0, q1] = [a0, a1, b0, b1].map(|i| points[i as usize]);
+            let intersect = overlap_at_shared_point(p0, p1, q0, q1)
+                .unwrap_or_else(|| segments_intersect(p0, p1, q0, q1));
+            if intersect {
+                return Some((a0.min(b0) as usize, a0.max(b0) as usize));
+            }
+        }
+    }
+    None
+}
+
+fn point_in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    cross(a, b, p) >= 0. && cross(b, c, p) >= 0. && cross(c, a, p) >= 0.
+}
+
+/// Ear clipping of a counter-clockwise polygon given as indices into `points`
+pub(super) fn ear_clip(
+    points: &[Vec2],
+    mut polygon: Vec<u32>,
+) -> Result<Vec<u32>, TriangulationError> {
+    let mut indices = Vec::with_capacity(polygon.len().saturating_sub(2) * 3);
+    let position = |i: u32| points[i as usize];
+
+    let mut i = 0;
+    let mut stalled = 0;
+    while polygon.len() > 3 {
+        let n = polygon.len();
+        let (prev, current, next) = (
+            polygon[(i + n - 1) % n],
+            polygon[i % n],
+            polygon[(i + 1) % n],
+        );
+        let (a, b, c) = (position(prev), position(current), position(next));
+        let turn = cross(a, b, c);
+
+        let is_ear = turn > 0.
+            && polygon.iter().all(|&other| {
+                let p = position(other);
+                // Bridges to holes duplicate points, those never block an ear
+                p == a || p == b || p == c || !point_in_triangle(p, a, b, c)
+            });
+
+        // When no ear can be found because of rounding errors any convex corner is clipped,
+        // collinear points are dropped as they enclose no area
+        if is_ear || (stalled > n && turn > 0.) {
+            indices.extend([prev, current, next]);
+            polygon.remove(i % n);
+            stalled = 0;
+        } else if turn == 0. {
+            polygon.remove(i % n);
+            stalled = 0;
+        } else if stalled > 2 * n {
+            return Err(TriangulationError::NoEar(n));
+        } else {
+            i += 1;
+            stalled += 1;
+        }
+        i %= polygon.len();
+    }
+
+    if let [a, b, c] = polygon[..] {
+        let turn = cross(position(a), position(b), position(c));
+        if turn < 0. {
+            return Err(TriangulationError::NoEar(3));
+        }
+        if turn > 0. {
+            indices.extend(&polygon);
+        }
+    }
+    Ok(indices)
+}
+
+/// Whether the direction from the corner at `position` towards `target` points into the
+/// counter-clockwise polygon
+fn in_sector(points: &[Vec2], polygon: &[u32], position: usize, target: Vec2) -> bool {
+    let n = polygon.len();
+    let [a, p, b] =
+        [position + n - 1, position, position + 1].map(|i| points[polygon[i % n] as usize]);
+    if cross(a, p, b) >= 0. {
+        cross(a, p, target) > 0. && cross(p, b, target) > 0.
+    } else {
+        cross(a, p, target) > 0. || cross(p, b, target) > 0.
+    }
+}
+
+/// Connects the clockwise holes to the counter-clockwise outline with pairs of edges, so
+/// that the result can be ear clipped as a single polygon
+fn bridge_holes(points: &[Vec2], mut outline: Vec<u32>, mut holes: Vec<Vec<u32>>) -> Vec<u32> {
+    let position = |i: u32| points[i as usize];
+    let rightmost = |hole: &[u32]| {
+        (0..hole.len())
+            .max_by(|a, b| position(hole[*a]).x.total_cmp(&position(hole[*b]).x))
+            .unwrap()
+    };
+    holes.sort_by(|a, b| {
+        position(b[rightmost(b)])
+            .x
+            .total_cmp(&position(a[rightmost(a)]).x)
+    });
+
+    for (k, hole) in holes.iter().enumerate() {
+        let start = rightmost(hole);
+        let m = position(hole[start]);
+
+        let crosses = |p: Vec2| {
+            let n = outline.len();
+            let outline_edges = (0..n).map(|i| (outline[i], outline[(i + 1) % n]));
+            let hole_edges = holes[k..].iter().flat_map(|hole| {
+                let n = hole.len();
+                (0..n).map(move |i| (hole[i], hole[(i + 1) % n]))
+            });
+            outline_edges.chain(hole_edges).any(|(e0, e1)| {
+                let (e0, e1) = (position(e0), position(e1));
+                ![e0, e1].contains(&p) && ![e0, e1].contains(&m) && segments_intersect(m, p, e0, e1)
+            })
+        };
+
+        let mut candidates = (0..outline.len())
+            .filter(|&i| in_sector(points, &outline, i, m))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|a, b| {
+            let distance = |i: &usize| position(outline[*i]).distance_squared(m);
+            distance(a).total_cmp(&distance(b))
+        });
+
+        let Some(bridge) = candidates
+            .into_iter()
+            .find(|&i| !crosses(position(outline[i])))
+        else {
+            log::warn!("Hole can't be connected to the outline, skipping it");
+            continue;
+        };
+
+        let mut spliced = hole[start..].to_vec();
+        spliced.extend(&hole[..=start]);
+        spliced.push(outline[bridge]);
+        outline.splice(bridge + 1..bridge + 1, spliced);
+    }
+
+    outline
+}
+
+fn oriented(points: &[Vec2], mut contour: Vec<u32>, counter_clockwise: bool) -> Vec<u32> {
+    if (contour_area(points, &contour) > 0.) != counter_clockwise {
+        contour.reverse();
+    }
+    contour
+}
+
+/// Triangles filling the non-intersecting contours according to `fill_rule`, emitted
+/// counter-clockwise. Contours are given as indices into `points` and may wind either way.
+pub(super) fn triangulate(
+    points: &[Vec2],
+    contours: Vec<Vec<u32>>,
+    fill_rule: FillRule,
+) -> Result<Vec<u32>, TriangulationError> {
+    let contours = contours
+        .into_iter()
+        .map(|contour| distinct(contour, |i| points[i as usize]))
+        .filter(|contour| contour.len() >= 3)
+        .collect::<Vec<_>>();
+
+    if let [contour] = contours.as_slice() {
+        let polygon = oriented(points, contour.clone(), true);
+        if is_convex(points, &polygon) {
+            return Ok((2..polygon.len())
+                .flat_map(|i| [polygon[0], polygon[i - 1], polygon[i]])
+                .collect());
+        }
+    }
+
+    // Ear clipping succeeds for some crossing contours, like stars drawn in one stroke
+    if let Some((a, b)) = find_self_intersection(points, &contours) {
+        return Err(TriangulationError::SelfIntersecting(a, b));
+    }
+
+    // As the contours don't cross, each one is either completely inside another or outside.
+    // The middle of an edge is tested, as corners may be shared with the other contours.
+    let areas = contours
+        .iter()
+        .map(|contour| contour_area(points, contour))
+        .collect::<Vec<_>>();
+    let containing = contours
+        .iter()
+        .map(|contour| {
+            let p = (points[contour[0] as usize] + points[contour[1] as usize]) / 2.;
+            (0..contours.len())
+                .filter(|&j| {
+                    let contour = contours[j].iter().map(|i| points[*i as usize].as_dvec2());
+                    winding_number(contour, p.as_dvec2()) != 0
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let parent = |i: usize| {
+        containing[i]
+            .iter()
+            .copied()
+            .filter(|&j| j != i)
+            .min_by(|a, b| areas[*a].abs().total_cmp(&areas[*b].abs()))
+    };
+
+    let mut indices = vec![];
+    for (i, contour) in contours.iter().enumerate() {
+        // The region right inside the contour, but outside of any contour nested in it
+        let depth = containing[i].iter().filter(|&&j| j != i).count() + 1;
+        let winding: i32 = containing[i]
+            .iter()
+            .filter(|&&j| j != i)
+            .chain([&i])
+            .map(|&j| areas[j].signum() as i32)
+            .sum();
+        let filled = match fill_rule {
+            FillRule::EvenOdd => depth % 2 == 1,
+            FillRule::NonZero => winding != 0,
+        };
+        if !filled {
+            continue;
+        }
+
+        let holes = (0..contours.len())
+            .filter(|&j| parent(j) == Some(i))
+            .map(|j| oriented(points, contours[j].clone(), false))
+            .collect::<Vec<_>>();
+        let outline = oriented(points, contour.clone(), true);
+        indices.extend(ear_clip(points, bridge_holes(points, outline, holes))?);
+    }
+
+    Ok(indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use super::*;
+
+    fn area(points: &[Vec2], indices: &[u32]) -> f32 {
+        indices
+            .chunks_exact(3)
+            .map(|t| {
+                cross(
+                    points[t[0] as usize],
+                    points[t[1] as usize],
+                    points[t[2] as usize],
+                ) / 2.
+            })
+            .sum()
+    }
+
+    #[test]
+    fn ear_clips_concave_polygons() {
+        let points = [
+            vec2(0., 0.),
+            vec2(2., 0.),
+            vec2(2., 1.),
+            vec2(1., 1.),
+            vec2(1., 2.),
+            vec2(0., 2.),
+            // Collinear with its neighbours
+            vec2(0., 1.),
+        ];
+        let indices = ear_clip(&points, (0..7).collect()).unwrap();
+        assert!((area(&points, &indices) - 3.).abs() < 1e-6);
+        assert!(indices.chunks_exact(3).all(|t| {
+            cross(
+                points[t[0] as usize],
+                points[t[1] as usize],
+                points[t[2] as usize],
+            ) > 0.
+        }));
+    }
+
+    #[test]
+    fn ear_clip_reports_missing_ears() {
+        let points = [vec2(0., 0.), vec2(0., 1.), vec2(1., 1.), vec2(1., 0.)];
+        // Clockwise polygons have no ears
+        assert_eq!(
+            ear_clip(&points, vec![0, 1, 2, 3]),
+            Err(TriangulationError::NoEar(4))
+        );
+        assert_eq!(
+            ear_clip(&points, vec![0, 1, 2]),
+            Err(TriangulationError::NoEar(3))
+        );
+        // Degenerate polygons produce no triangles
+        assert_eq!(ear_clip(&points, vec![0, 1, 1]), Ok(vec![]));
+    }
+
+    #[test]
+    fn finds_self_intersections() {
+        let points = [vec2(0., 0.), vec2(1., 1.), vec2(1., 0.), vec2(0., 1.)];
+        let bowtie = vec![(0..4).collect()];
+        assert_eq!(
+            triangulate(&points, bowtie, FillRule::EvenOdd),
+            Err(TriangulationError::SelfIntersecting(0, 2))
+        );
+    }
+
+    #[test]
+    fn finds_crossings_ear_clipping_misses() {
+        // Legs crossing each other below a notch, every corner is still an ear
+        let points = [
+            vec2(4., 4.),
+            vec2(1., 0.),
+            vec2(2., 2.),
+            vec2(3., 0.),
+            vec2(0., 4.),
+        ];
+        let crossed = (0..5).collect::<Vec<_>>();
+        assert!(ear_clip(&points, oriented(&points, crossed.clone(), true)).is_ok());
+        assert!(matches!(
+            triangulate(&points, vec![crossed], FillRule::NonZero),
+            Err(TriangulationError::SelfIntersecting(..))
+        ));
+
+        // Holes touching the outline at a corner aren't crossing it
+        let points = [
+            vec2(0., 0.),
+            vec2(3., 0.),
+            vec2(3., 3.),
+            vec2(0., 3.),
+            vec2(0., 0.),
+            vec2(1., 2.),
+            vec2(2., 1.),
+        ];
+        let contours = vec![(0..4).collect(), (4..7).collect()];
+        let indices = triangulate(&points, contours, FillRule::EvenOdd).unwrap();
+        assert!((area(&points, &indices) - 7.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn fills_holes() {
+        let points = [
+            vec2(0., 0.),
+            vec2(3., 0.),
+            vec2(3., 3.),
+            vec2(0., 3.),
+            vec2(1., 1.),
+            vec2(2., 1.),
+            vec2(2., 2.),
+            vec2(1., 2.),
+        ];
+        let contours = vec![(0..4).collect(), (4..8).collect()];
+        for fill_rule in [FillRule::EvenOdd, FillRule::NonZero] {
+            let indices = triangulate(&points, contours.clone(), fill_rule).unwrap();
+            let expected = match fill_rule {
+                FillRule::EvenOdd => 8.,
+                // Both contours wind counter-clockwise
+                FillRule::NonZero => 9.,
+            };
+            assert!((area(&points, &indices) - expected).abs() < 1e-6);
+        }
+    }
+}
@@ -1,8 +1,251 @@
-
+use glam::UVec2;
+use image::{ColorType, DynamicImage, ImageFormat};
 use std::fmt::Display;
+use std::io::Cursor;
 use strum::IntoEnumIterator;
 
-
 pub trait Textures: IntoEnumIterator + Default + Into<u32> + Clone {
     fn bytes(&self) -> Vec<u8>;
-}
\ No newline at end of file
+}
+
+/// Texture set for unit tests, `White` has no image and `Tiles` a small fixture
+#[cfg(test)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, strum::EnumIter)]
+pub(crate) enum TestTextures {
+    #[default]
+    White,
+    Tiles,
+}
+
+#[cfg(test)]
+impl From<TestTextures> for u32 {
+    fn from(texture: TestTextures) -> u32 {
+        texture as u32
+    }
+}
+
+#[cfg(test)]
+impl Textures for TestTextures {
+    fn bytes(&self) -> Vec<u8> {
+        match self {
+            Self::White => vec![],
+            Self::Tiles => include_bytes!("../../tests/fixtures/textures/tiles.png").to_vec(),
+        }
+    }
+}
+
+/// Image a texture was decoded from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureInfo {
+    /// Width and height in pixels
+    pub size: UVec2,
+    /// `None` if the format can't be told from the bytes
+    pub format: Option<ImageFormat>,
+    /// Channels and bit depth before the conversion to RGBA for the GPU
+    pub color_type: ColorType,
+}
+
+impl TextureInfo {
+    pub(crate) fn new(bytes: &[u8], image: &DynamicImage) -> Self {
+        Self {
+            size: UVec2::new(image.width(), image.height()),
+            format: image::guess_format(bytes).ok(),
+            color_type: image.color(),
+        }
+    }
+
+    /// Width over height
+    pub fn aspect_ratio(&self) -> f32 {
+        self.size.x as f32 / self.size.y as f32
+    }
+}
+
+/// Width and height in pixels from the header of the image, without decoding it
+pub(crate) fn image_size(bytes: &[u8]) -> anyhow::Result<UVec2> {
+    let (width, height) = image::io::Reader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .into_dimensions()?;
+    Ok(UVec2::new(width, height))
+}
+
+/// Combines several [`Textures`] enums into one, so that UI, characters and tilesets can
+/// keep separate enums while sharing the textures bound on the GPU. Every set becomes a
+/// variant wrapping it and converts into the combined enum, so `Shape::set_texture` accepts
+/// textures of any set.
+///
+/// ```ignore
+/// texture_set! {
+///     #[derive(Clone, Copy, Debug)]
+///     pub enum GameTextures {
+///         Ui(UiTextures),
+///         Characters(CharacterTextures),
+///     }
+/// }
+///
+/// let button = Shape::<GameTextures>::from_square().set_texture(UiTextures::Button);
+/// ```
+#[macro_export]
+macro_rules! texture_set {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $first_variant:ident($first:ty)
+            $(, $variant:ident($textures:ty))* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $first_variant($first),
+            $($variant($textures),)*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::$first_variant(Default::default())
+            }
+        }
+
+        impl From<$first> for $name {
+            fn from(texture: $first) -> Self {
+                Self::$first_variant(texture)
+            }
+        }
+
+        $(
+            impl From<$textures> for $name {
+                fn from(texture: $textures) -> Self {
+                    Self::$variant(texture)
+                }
+            }
+        )*
+
+        impl $crate::strum::IntoEnumIterator for $name {
+            type Iterator = std::vec::IntoIter<Self>;
+
+            fn iter() -> Self::Iterator {
+                let mut textures = <$first as $crate::strum::IntoEnumIterator>::iter()
+                    .map(Self::$first_variant)
+                    .collect::<Vec<_>>();
+                $(
+                    textures.extend(
+                        <$textures as $crate::strum::IntoEnumIterator>::iter().map(Self::$variant),
+                    );
+                )*
+                textures.into_iter()
+            }
+        }
+
+        /// Textures of later sets are numbered after all textures of the earlier ones
+        impl From<$name> for u32 {
+            #[allow(unused_assignments, unused_variables)]
+            fn from(textures: $name) -> u32 {
+                let mut offset = 0;
+                if let $name::$first_variant(texture) = &textures {
+                    return Into::<u32>::into(texture.clone());
+                }
+                offset += <$first as $crate::strum::IntoEnumIterator>::iter().count() as u32;
+                $(
+                    if let $name::$variant(texture) = &textures {
+                        return offset + Into::<u32>::into(texture.clone());
+                    }
+                    offset += <$textures as $crate::strum::IntoEnumIterator>::iter().count() as u32;
+                )*
+                unreachable!("every variant wraps a texture set")
+            }
+        }
+
+        impl $crate::Textures for $name {
+            fn bytes(&self) -> Vec<u8> {
+                match self {
+                    Self::$first_variant(texture) => $crate::Textures::bytes(texture),
+                    $(Self::$variant(texture) => $crate::Textures::bytes(texture),)*
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_image_sizes() {
+        assert_eq!(
+            image_size(&TestTextures::Tiles.bytes()).unwrap(),
+            UVec2::new(4, 2)
+        );
+        assert!(image_size(&TestTextures::White.bytes()).is_err());
+        assert!(image_size(b"not an image").is_err());
+    }
+
+    #[test]
+    fn describes_images() {
+        let bytes = TestTextures::Tiles.bytes();
+        let info = TextureInfo::new(&bytes, &image::load_from_memory(&bytes).unwrap());
+        assert_eq!(
+            info,
+            TextureInfo {
+                size: UVec2::new(4, 2),
+                format: Some(ImageFormat::Png),
+                color_type: ColorType::Rgb8,
+            }
+        );
+        assert_eq!(info.aspect_ratio(), 2.);
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, strum::EnumIter)]
+    enum Letters {
+        #[default]
+        A,
+        B,
+        C,
+    }
+
+    impl From<Letters> for u32 {
+        fn from(texture: Letters) -> u32 {
+            texture as u32
+        }
+    }
+
+    impl Textures for Letters {
+        fn bytes(&self) -> Vec<u8> {
+            vec![*self as u8]
+        }
+    }
+
+    crate::texture_set! {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        enum Combined {
+            Test(TestTextures),
+            Letters(Letters),
+        }
+    }
+
+    #[test]
+    fn combines_texture_sets() {
+        let textures = Combined::iter().collect::<Vec<_>>();
+        assert_eq!(
+            textures,
+            [
+                Combined::Test(TestTextures::White),
+                Combined::Test(TestTextures::Tiles),
+                Combined::Letters(Letters::A),
+                Combined::Letters(Letters::B),
+                Combined::Letters(Letters::C),
+            ]
+        );
+        // Indices count through the sets in the order they are iterated
+        let indices = textures.into_iter().map(u32::from).collect::<Vec<_>>();
+        assert_eq!(indices, [0, 1, 2, 3, 4]);
+
+        assert_eq!(Combined::default(), Combined::Test(TestTextures::White));
+        assert_eq!(Combined::from(Letters::C), Combined::Letters(Letters::C));
+        assert_eq!(u32::from(Combined::from(TestTextures::Tiles)), 1);
+        assert_eq!(Combined::from(Letters::B).bytes(), [1]);
+        assert_eq!(
+            Combined::from(TestTextures::Tiles).bytes(),
+            TestTextures::Tiles.bytes()
+        );
+    }
+}
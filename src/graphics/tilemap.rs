@@ -0,0 +1,596 @@
+use std::collections::HashMap;
+
+use glam::{ivec2, vec2, vec3, IVec2, UVec2};
+
+use super::*;
+
+mod tiled;
+pub use tiled::{ObjectKind, ObjectLayer, Properties, Property, TiledObject};
+
+/// Side length of a chunk in tiles. Chunks are the unit of re-tessellation and culling.
+pub const CHUNK_SIZE: i32 = 16;
+
+/// Depth of the first layer, every following layer is drawn `LAYER_Z_STEP` closer.
+pub const FIRST_LAYER_Z: f32 = 0.9;
+pub const LAYER_Z_STEP: f32 = 0.01;
+
+const FLIPPED_HORIZONTALLY: u32 = 0x8000_0000;
+const FLIPPED_VERTICALLY: u32 = 0x4000_0000;
+const FLIPPED_DIAGONALLY: u32 = 0x2000_0000;
+const ROTATED_HEXAGONAL_120: u32 = 0x1000_0000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Tile {
+    /// Global id, `first_gid` of the tileset plus the index of the tile inside it
+    pub gid: u32,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /// Swaps the x and y axis of the tile image, applied before the other flips
+    pub flip_diagonal: bool,
+}
+
+impl Tile {
+    pub fn new(gid: u32) -> Self {
+        Self {
+            gid,
+            ..Default::default()
+        }
+    }
+
+    /// Decodes a gid as stored by Tiled, with the flip flags in the highest bits.
+    /// Returns `None` for the empty tile `0`.
+    pub fn from_raw_gid(raw: u32) -> Option<Self> {
+        let gid = raw
+            & !(FLIPPED_HORIZONTALLY
+                | FLIPPED_VERTICALLY
+                | FLIPPED_DIAGONALLY
+                | ROTATED_HEXAGONAL_120);
+        if gid == 0 {
+            return None;
+        }
+        Some(Self {
+            gid,
+            flip_x: raw & FLIPPED_HORIZONTALLY != 0,
+            flip_y: raw & FLIPPED_VERTICALLY != 0,
+            flip_diagonal: raw & FLIPPED_DIAGONALLY != 0,
+        })
+    }
+
+    pub fn flip_x(mut self) -> Self {
+        self.flip_x = !self.flip_x;
+        self
+    }
+
+    pub fn flip_y(mut self) -> Self {
+        self.flip_y = !self.flip_y;
+        self
+    }
+}
+
+/// A texture cut into a grid of equally sized tiles.
+#[derive(Clone, Debug)]
+pub struct Tileset<T: Textures> {
+    pub name: String,
+    pub texture: T,
+    pub first_gid: u32,
+    /// Size of a single tile in pixels
+    pub tile_size: UVec2,
+    /// Size of the whole texture in pixels
+    pub image_size: UVec2,
+    pub columns: u32,
+    pub tile_count: u32,
+    pub margin: u32,
+    pub spacing: u32,
+    /// Custom properties of individual tiles, keyed by their local id
+    pub tile_properties: HashMap<u32, Properties>,
+}
+
+impl<T: Textures> Tileset<T> {
+    pub fn new(texture: T, tile_size: UVec2, image_size: UVec2) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            tile_size.x > 0 && tile_size.y > 0,
+            "tile size {tile_size} has to be positive"
+        );
+        let grid = image_size / tile_size;
+        Ok(Self {
+            name: String::new(),
+            texture,
+            first_gid: 1,
+            tile_size,
+            image_size,
+            columns: grid.x,
+            tile_count: grid.x * grid.y,
+            margin: 0,
+            spacing: 0,
+            tile_properties: HashMap::new(),
+        })
+    }
+
+    pub fn set_first_gid(mut self, first_gid: u32) -> Self {
+        self.first_gid = first_gid;
+        self
+    }
+
+    pub fn contains(&self, gid: u32) -> bool {
+        gid >= self.first_gid && gid < self.first_gid + self.tile_count
+    }
+
+    /// Top left and bottom right texture coordinates of the tile with the given local id
+    pub fn uv_rect(&self, local_id: u32) -> (Vec2, Vec2) {
+        let columns = self.columns.max(1);
+        let cell = UVec2::new(local_id % columns, local_id / columns);
+        let top_left = self.margin + cell * (self.tile_size + self.spacing);
+        let image_size = self.image_size.as_vec2();
+
+        (
+            top_left.as_vec2() / image_size,
+            (top_left + self.tile_size).as_vec2() / image_size,
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Chunk<T: Textures> {
+    tiles: Vec<Option<Tile>>,
+    /// Cached tessellation in map space, `None` when the chunk needs rebuilding
+    mesh: Option<Geometry<T>>,
+}
+
+impl<T: Textures> Chunk<T> {
+    fn new() -> Self {
+        Self {
+            tiles: vec![None; (CHUNK_SIZE * CHUNK_SIZE) as usize],
+            mesh: None,
+        }
+    }
+}
+
+/// A grid of tiles. Tile `(x, y)` covers `[x, x + 1] x [-y - 1, -y]` in map space,
+/// so rows grow downwards like in Tiled while the y axis still points up.
+#[derive(Clone, Debug)]
+pub struct TileLayer<T: Textures> {
+    pub name: String,
+    pub visible: bool,
+    pub properties: Properties,
+    offset: Vec2,
+    color: Color,
+    z: f32,
+    chunks: HashMap<IVec2, Chunk<T>>,
+}
+
+impl<T: Textures> TileLayer<T> {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            visible: true,
+            properties: Properties::new(),
+            offset: Vec2::ZERO,
+            color: Color::WHITE,
+            z: FIRST_LAYER_Z,
+            chunks: HashMap::new(),
+        }
+    }
+
+    fn split(pos: IVec2) -> (IVec2, usize) {
+        let chunk = ivec2(pos.x.div_euclid(CHUNK_SIZE), pos.y.div_euclid(CHUNK_SIZE));
+        let local = pos - chunk * CHUNK_SIZE;
+        (chunk, (local.y * CHUNK_SIZE + local.x) as usize)
+    }
+
+    pub fn tile(&self, pos: IVec2) -> Option<Tile> {
+        let (chunk, index) = Self::split(pos);
+        self.chunks.get(&chunk).and_then(|chunk| chunk.tiles[index])
+    }
+
+    /// Only the chunk containing `pos` gets re-tessellated
+    pub fn set_tile(&mut self, pos: IVec2, tile: Option<Tile>) {
+        let (chunk, index) = Self::split(pos);
+        let chunk = match (self.chunks.get_mut(&chunk), tile) {
+            (Some(chunk), _) => chunk,
+            (None, Some(_)) => self.chunks.entry(chunk).or_insert_with(Chunk::new),
+            (None, None) => return,
+        };
+        if chunk.tiles[index] != tile {
+            chunk.tiles[index] = tile;
+            chunk.mesh = None;
+        }
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = (IVec2, Tile)> + '_ {
+        self.chunks.iter().flat_map(|(chunk_pos, chunk)| {
+            chunk.tiles.iter().enumerate().filter_map(move |(i, tile)| {
+                let local = ivec2(i as i32 % CHUNK_SIZE, i as i32 / CHUNK_SIZE);
+                tile.map(|tile| (*chunk_pos * CHUNK_SIZE + local, tile))
+            })
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    pub fn offset(&self) -> Vec2 {
+        self.offset
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn z(&self) -> f32 {
+        self.z
+    }
+
+    pub fn set_offset(&mut self, offset: Vec2) {
+        self.offset = offset;
+        self.invalidate();
+    }
+
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+        self.invalidate();
+    }
+
+    pub fn set_z(&mut self, z: f32) {
+        self.z = z;
+        self.invalidate();
+    }
+
+    fn invalidate(&mut self) {
+        for chunk in self.chunks.values_mut() {
+            chunk.mesh = None;
+        }
+    }
+
+    fn chunk_bounds(&self, chunk_pos: IVec2, tile_scale: Vec2) -> (Vec2, Vec2) {
+        let min_tile = (chunk_pos * CHUNK_SIZE).as_vec2();
+        // Tiles larger than the map grid stick out to the top and right
+        let min = vec2(min_tile.x, -min_tile.y - CHUNK_SIZE as f32) + self.offset;
+        let max = vec2(
+            min_tile.x + CHUNK_SIZE as f32 - 1. + tile_scale.x,
+            -min_tile.y - 1. + tile_scale.y,
+        ) + self.offset;
+        (min, max)
+    }
+}
+
+fn tessellate_chunk<T: Textures>(
+    chunk_pos: IVec2,
+    chunk: &Chunk<T>,
+    layer: &TileLayer<T>,
+    tilesets: &[Tileset<T>],
+    tile_size: UVec2,
+) -> Geometry<T> {
+    let mut vertices = vec![];
+    let mut indices = vec![];
+
+    for (i, tile) in chunk.tiles.iter().enumerate() {
+        let Some(tile) = tile else {
+            continue;
+        };
+        let Some(tileset) = tilesets.iter().find(|tileset| tileset.contains(tile.gid)) else {
+            log::warn!("Tile with gid {} has no tileset", tile.gid);
+            continue;
+        };
+
+        let local = ivec2(i as i32 % CHUNK_SIZE, i as i32 / CHUNK_SIZE);
+        let pos = chunk_pos * CHUNK_SIZE + local;
+        // Tiled anchors tiles at the bottom left corner of their cell
+        let bottom_left = vec2(pos.x as f32, -pos.y as f32 - 1.) + layer.offset;
+        let size = tileset.tile_size.as_vec2() / tile_size.as_vec2();
+        let (uv_min, uv_max) = tileset.uv_rect(tile.gid - tileset.first_gid);
+
+        let index_offset = vertices.len() as u32;
+        // Corners in counter-clockwise order starting bottom left, as (x, y-down) in the tile
+        for corner in [vec2(0., 1.), vec2(1., 1.), vec2(1., 0.), vec2(0., 0.)] {
+            let position = bottom_left + vec2(corner.x, 1. - corner.y) * size;
+
+            let mut source = corner;
+            if tile.flip_y {
+                source.y = 1. - source.y;
+            }
+            if tile.flip_x {
+                source.x = 1. - source.x;
+            }
+            if tile.flip_diagonal {
+                source = vec2(source.y, source.x);
+            }
+
+            let mut vertex: Vertex<T> = (
+                vec3(position.x, position.y, layer.z),
+                uv_min + source * (uv_max - uv_min),
+            )
+                .into();
+            vertex.texture = tileset.texture.clone();
+            vertex.color = layer.color;
+            vertices.push(vertex);
+        }
+        indices.extend([0, 1, 2, 0, 2, 3].map(|i| i + index_offset));
+    }
+
+    (vertices, indices)
+}
+
+/// Whether the map space rectangle ends up on screen after applying `camera`
+fn is_visible(camera: &GTransform, (min, max): (Vec2, Vec2)) -> bool {
+    let corners = [min, vec2(max.x, min.y), max, vec2(min.x, max.y)].map(|p| camera.transform(p));
+    let screen_min = corners
+        .iter()
+        .fold(Vec2::splat(f32::MAX), |acc, p| acc.min(*p));
+    let screen_max = corners
+        .iter()
+        .fold(Vec2::splat(f32::MIN), |acc, p| acc.max(*p));
+
+    screen_max.x >= -1. && screen_min.x <= 1. && screen_max.y >= -1. && screen_min.y <= 1.
+}
+
+/// Layers of tiles rendered in chunks. Chunk meshes are cached in map space and only
+/// rebuilt when their tiles change, chunks outside of the screen are skipped entirely.
+#[derive(Clone, Debug)]
+pub struct Tilemap<T: Textures> {
+    /// Size of a map cell in pixels, one cell is one unit in map space
+    pub tile_size: UVec2,
+    pub tilesets: Vec<Tileset<T>>,
+    pub layers: Vec<TileLayer<T>>,
+    pub object_layers: Vec<ObjectLayer>,
+    pub properties: Properties,
+}
+
+impl<T: Textures> Tilemap<T> {
+    pub fn new(tile_size: UVec2) -> Self {
+        Self {
+            tile_size,
+            tilesets: vec![],
+            layers: vec![],
+            object_layers: vec![],
+            properties: Properties::new(),
+        }
+    }
+
+    pub fn add_tileset(mut self, tileset: Tileset<T>) -> Self {
+        self.tilesets.push(tileset);
+        self
+    }
+
+    pub fn add_layer(mut self, layer: TileLayer<T>) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&TileLayer<T>> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut TileLayer<T>> {
+        self.layers.iter_mut().find(|layer| layer.name == name)
+    }
+
+    pub fn object_layer(&self, name: &str) -> Option<&ObjectLayer> {
+        self.object_layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub fn tileset(&self, gid: u32) -> Option<&Tileset<T>> {
+        self.tilesets.iter().find(|tileset| tileset.contains(gid))
+    }
+
+    pub fn tile_properties(&self, gid: u32) -> Option<&Properties> {
+        let tileset = self.tileset(gid)?;
+        tileset.tile_properties.get(&(gid - tileset.first_gid))
+    }
+
+    /// Geometry of all visible chunks, `camera` maps map space to screen space.
+    pub fn geometry(&mut self, camera: GTransform) -> Geometry<T> {
+        let mut vertices = vec![];
+        let mut indices = vec![];
+
+        let tile_size = self.tile_size;
+        let tilesets = &self.tilesets;
+        let max_tile_scale = tilesets.iter().fold(Vec2::ONE, |acc, tileset| {
+            acc.max(tileset.tile_size.as_vec2() / tile_size.as_vec2())
+        });
+
+        for layer in self.layers.iter_mut().filter(|layer| layer.visible) {
+            let mut chunks = std::mem::take(&mut layer.chunks);
+
+            for (chunk_pos, chunk) in &mut chunks {
+                if !is_visible(&camera, layer.chunk_bounds(*chunk_pos, max_tile_scale)) {
+                    continue;
+                }
+
+                if chunk.mesh.is_none() {
+                    chunk.mesh = Some(tessellate_chunk(
+                        *chunk_pos, chunk, layer, tilesets, tile_size,
+                    ));
+                }
+                let (chunk_vertices, chunk_indices) = chunk.mesh.as_ref().unwrap();
+
+                let index_offset = vertices.len() as u32;
+                vertices.extend(chunk_vertices.iter().map(|vertex| {
+                    let mut vertex = vertex.clone();
+                    let position = camera.transform(vertex.position.truncate());
+                    vertex.position = position.extend(vertex.position.z);
+                    vertex
+                }));
+                indices.extend(chunk_indices.iter().map(|i| i + index_offset));
+            }
+
+            layer.chunks = chunks;
+        }
+
+        (vertices, indices)
+    }
+
+    pub fn draw(&mut self, graphics: &mut Graphics<T>, camera: GTransform) {
+        graphics.add_geometry(self.geometry(camera));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::uvec2;
+
+    use super::*;
+
+    fn tilemap() -> Tilemap<TestTextures> {
+        let tileset = Tileset::new(TestTextures::Tiles, uvec2(16, 16), uvec2(32, 32)).unwrap();
+        let mut layer = TileLayer::new("ground");
+        layer.set_tile(ivec2(0, 0), Some(Tile::new(1)));
+        layer.set_tile(ivec2(1, 0), Some(Tile::new(2)));
+        layer.set_tile(ivec2(CHUNK_SIZE * 10, 0), Some(Tile::new(3)));
+        Tilemap::new(uvec2(16, 16))
+            .add_tileset(tileset)
+            .add_layer(layer)
+    }
+
+    /// Shows map space from -10 to 10 on both axes
+    fn camera() -> GTransform {
+        GTransform::from_inflation(0.1)
+    }
+
+    fn mesh(layer: &TileLayer<TestTextures>, chunk: IVec2) -> Option<&Geometry<TestTextures>> {
+        layer.chunks[&chunk].mesh.as_ref()
+    }
+
+    #[test]
+    fn raw_gids() {
+        assert_eq!(Tile::from_raw_gid(0), None);
+        assert_eq!(Tile::from_raw_gid(FLIPPED_HORIZONTALLY), None);
+        let tile = Tile::from_raw_gid(5 | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY).unwrap();
+        assert_eq!(
+            (tile.gid, tile.flip_x, tile.flip_y, tile.flip_diagonal),
+            (5, false, true, true)
+        );
+    }
+
+    #[test]
+    fn tiles_in_chunks() {
+        let mut layer = TileLayer::<TestTextures>::new("layer");
+        for pos in [
+            ivec2(-1, -1),
+            ivec2(CHUNK_SIZE, 3),
+            ivec2(-CHUNK_SIZE - 1, 0),
+        ] {
+            layer.set_tile(pos, Some(Tile::new(1)));
+            assert_eq!(layer.tile(pos), Some(Tile::new(1)));
+        }
+        assert_eq!(layer.chunks.len(), 3);
+        assert_eq!(layer.tile(ivec2(0, 0)), None);
+        assert_eq!(layer.tiles().count(), 3);
+
+        // Clearing tiles of missing chunks doesn't create them
+        layer.set_tile(ivec2(100, 100), None);
+        assert_eq!(layer.chunks.len(), 3);
+    }
+
+    #[test]
+    fn meshes_are_reused() {
+        let mut map = tilemap();
+        let (vertices, indices) = map.geometry(camera());
+        assert_eq!((vertices.len(), indices.len()), (8, 12));
+
+        let layer = &map.layers[0];
+        let cached = mesh(layer, IVec2::ZERO).unwrap().0.as_ptr();
+        map.geometry(camera().translate(Vec2::X));
+        assert_eq!(
+            mesh(&map.layers[0], IVec2::ZERO).unwrap().0.as_ptr(),
+            cached
+        );
+    }
+
+    #[test]
+    fn set_tile_rebuilds_its_chunk() {
+        let mut map = tilemap();
+        // Squashes the whole map onto the screen, so that every chunk gets tessellated
+        map.geometry(GTransform::from_inflation(0.));
+        let layer = &mut map.layers[0];
+        assert!(mesh(layer, ivec2(10, 0)).is_some());
+
+        layer.set_tile(ivec2(2, 0), Some(Tile::new(4)));
+        assert!(mesh(layer, IVec2::ZERO).is_none());
+        assert!(mesh(layer, ivec2(10, 0)).is_some());
+
+        // Setting the same tile again keeps the mesh
+        map.geometry(camera());
+        let layer = &mut map.layers[0];
+        layer.set_tile(ivec2(2, 0), Some(Tile::new(4)));
+        assert!(mesh(layer, IVec2::ZERO).is_some());
+        assert_eq!(map.geometry(camera()).0.len(), 12);
+    }
+
+    #[test]
+    fn culls_chunks() {
+        let mut map = tilemap();
+        let (vertices, _) = map.geometry(camera());
+        assert_eq!(vertices.len(), 8);
+        assert!(vertices
+            .iter()
+            .all(|vertex| vertex.position.truncate().abs().max_element() <= 1.));
+        // Chunks off screen aren't tessellated
+        assert!(mesh(&map.layers[0], ivec2(10, 0)).is_none());
+
+        let far = camera().translate(-vec2(CHUNK_SIZE as f32 * 10., 0.));
+        assert_eq!(map.geometry(far).0.len(), 4);
+
+        map.layers[0].visible = false;
+        assert!(map.geometry(camera()).0.is_empty());
+
+        assert!(is_visible(&camera(), (vec2(9., 9.), vec2(20., 20.))));
+        assert!(!is_visible(&camera(), (vec2(10.5, -5.), vec2(20., 5.))));
+        assert!(!is_visible(&camera(), (vec2(-5., -30.), vec2(5., -11.))));
+    }
+
+    #[test]
+    fn flips() {
+        let uvs = |tile: Tile| {
+            let mut map = tilemap();
+            map.layers[0].clear();
+            map.layers[0].set_tile(IVec2::ZERO, Some(tile));
+            let (vertices, _) = map.geometry(camera());
+            vertices
+                .iter()
+                .map(|vertex| vertex.texture_coords)
+                .collect::<Vec<_>>()
+        };
+        let (bottom_left, bottom_right, top_right, top_left) =
+            (vec2(0., 0.5), vec2(0.5, 0.5), vec2(0.5, 0.), Vec2::ZERO);
+
+        let tile = Tile::new(1);
+        assert_eq!(uvs(tile), [bottom_left, bottom_right, top_right, top_left]);
+        assert_eq!(
+            uvs(tile.flip_x()),
+            [bottom_right, bottom_left, top_left, top_right]
+        );
+        assert_eq!(
+            uvs(tile.flip_y()),
+            [top_left, top_right, bottom_right, bottom_left]
+        );
+        let diagonal = Tile {
+            flip_diagonal: true,
+            ..tile
+        };
+        assert_eq!(
+            uvs(diagonal),
+            [top_right, bottom_right, bottom_left, top_left]
+        );
+    }
+
+    #[test]
+    fn positions() {
+        let mut map = tilemap();
+        map.layers[0].set_offset(vec2(0.5, 0.));
+        map.layers[0].set_color(Color::RED);
+        let (vertices, _) = map.geometry(GTransform::default());
+        // Tile (1, 0) covers [1, 2] x [-1, 0] before the offset
+        let tile = vertices[4..8]
+            .iter()
+            .map(|vertex| vertex.position.truncate())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tile,
+            [vec2(1.5, -1.), vec2(2.5, -1.), vec2(2.5, 0.), vec2(1.5, 0.)]
+        );
+        assert!(vertices
+            .iter()
+            .all(|vertex| vertex.color == Color::RED && vertex.position.z == FIRST_LAYER_Z));
+    }
+}
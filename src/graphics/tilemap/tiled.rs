@@ -0,0 +1,959 @@
+//! Import of maps made in the [Tiled](https://www.mapeditor.org) editor, both the XML
+//! (`.tmx`, `.tsx`) and the JSON (`.tmj`, `.tsj`) flavour.
+
+use std::path::Path;
+
+use anyhow::{anyhow, bail, ensure, Context};
+use glam::{uvec2, UVec2};
+use serde_json::Value;
+
+use super::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Property {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Color(Color),
+    File(String),
+    /// Id of the referenced object, `0` when unset
+    Object(u32),
+}
+
+pub type Properties = HashMap<String, Property>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectKind {
+    /// Extends from the object position to the right and down
+    Rectangle {
+        size: Vec2,
+    },
+    /// Inscribed in the rectangle the object would have
+    Ellipse {
+        size: Vec2,
+    },
+    Point,
+    /// Points relative to the object position
+    Polygon(Vec<Vec2>),
+    Polyline(Vec<Vec2>),
+    /// Extends from the object position to the right and up
+    Tile {
+        tile: Tile,
+        size: Vec2,
+    },
+    Text(String),
+}
+
+/// An object from an object layer, all positions and sizes are in map space.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    /// Called `type` in older versions of Tiled
+    pub class: String,
+    pub position: Vec2,
+    /// Counter-clockwise in radians around `position`
+    pub rotation: f32,
+    pub visible: bool,
+    pub kind: ObjectKind,
+    pub properties: Properties,
+}
+
+impl TiledObject {
+    fn gtransform(&self) -> GTransform {
+        GTransform::from_translation(self.position).rotate(self.rotation)
+    }
+
+    /// Outline of the object as a shape, `None` for points, polylines and text.
+    pub fn to_shape<T: Textures>(&self) -> Option<Shape<T>> {
+        let shape = match &self.kind {
+            ObjectKind::Rectangle { size } => Shape::from_square()
+                .apply(GTransform::from_translation(-Vec2::Y * size.y).stretch(*size)),
+            ObjectKind::Tile { size, .. } => {
+                Shape::from_square().apply(GTransform::from_scale(*size))
+            }
+            ObjectKind::Ellipse { size } => Shape::from_circle(32)
+                .apply(GTransform::from_translation(*size * vec2(0.5, -0.5)).stretch(*size / 2.)),
+            ObjectKind::Polygon(points) => Shape::new(points.clone()),
+            ObjectKind::Point | ObjectKind::Polyline(_) | ObjectKind::Text(_) => return None,
+        };
+        Some(shape.apply(self.gtransform()).update_texture_coords())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectLayer {
+    pub name: String,
+    pub visible: bool,
+    pub color: Color,
+    pub z: f32,
+    pub objects: Vec<TiledObject>,
+    pub properties: Properties,
+}
+
+impl ObjectLayer {
+    pub fn object(&self, name: &str) -> Option<&TiledObject> {
+        self.objects.iter().find(|object| object.name == name)
+    }
+
+    pub fn shapes<T: Textures>(&self) -> impl Iterator<Item = Shape<T>> + '_ {
+        self.objects.iter().filter_map(|object| {
+            object
+                .to_shape()
+                .map(|shape| shape.set_color(self.color).set_z(self.z))
+        })
+    }
+}
+
+/// State shared by all layers of a group, groups nest their offsets, opacity and visibility
+#[derive(Clone, Copy)]
+struct Group {
+    offset: Vec2,
+    color: Color,
+    visible: bool,
+}
+
+impl Group {
+    fn child(&self, offset: Vec2, color: Color, visible: bool) -> Self {
+        Self {
+            offset: self.offset + offset,
+            color: self.color * color,
+            visible: self.visible && visible,
+        }
+    }
+}
+
+/// Converts pixel coordinates with the y axis pointing down into map space
+fn to_map_space(px: Vec2, tile_size: UVec2) -> Vec2 {
+    px / tile_size.as_vec2() * vec2(1., -1.)
+}
+
+/// Tiled colours are `#RRGGBB` or `#AARRGGBB`
+fn parse_color(s: &str) -> anyhow::Result<Color> {
+    let hex = u32::from_str_radix(s.trim_start_matches('#'), 16)
+        .with_context(|| format!("invalid colour {s}"))?;
+    let color = Color::from_hex(hex);
+    Ok(match s.trim_start_matches('#').len() {
+        8 => color.set_alpha(((hex >> 24) & 0xFF) as f32 / 255.),
+        _ => color,
+    })
+}
+
+fn parse_property(kind: &str, value: &str) -> anyhow::Result<Option<Property>> {
+    Ok(Some(match kind {
+        "" | "string" => Property::String(value.to_owned()),
+        "bool" => Property::Bool(value == "true"),
+        "int" => Property::Int(value.parse()?),
+        "float" => Property::Float(value.parse()?),
+        "color" if value.is_empty() => Property::Color(Color::TRANSPARENT),
+        "color" => Property::Color(parse_color(value)?),
+        "file" => Property::File(value.to_owned()),
+        "object" => Property::Object(value.parse()?),
+        // Custom class properties
+        _ => return Ok(None),
+    }))
+}
+
+fn decode_base64(input: &str) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len() / 4 * 3);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in input
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b'=')
+    {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => bail!("invalid base64 character {}", c as char),
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+
+    Ok(bytes)
+}
+
+fn decode_tile_data(data: &str, encoding: &str, compression: &str) -> anyhow::Result<Vec<u32>> {
+    match (encoding, compression) {
+        ("csv", _) => data
+            .split(',')
+            .map(str::trim)
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| {
+                gid.parse()
+                    .with_context(|| format!("invalid tile gid {gid}"))
+            })
+            .collect(),
+        ("base64", "") => Ok(decode_base64(data)?
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()),
+        ("base64", compression) => bail!(
+            "{compression} compressed tile layer data is not supported, \
+             save the map with CSV or uncompressed Base64 layer format"
+        ),
+        (encoding, _) => bail!("unknown tile layer encoding {encoding}"),
+    }
+}
+
+fn fill_tiles<T: Textures>(layer: &mut TileLayer<T>, origin: IVec2, width: u32, gids: &[u32]) {
+    let width = width.max(1);
+    for (i, gid) in gids.iter().enumerate() {
+        let pos = origin + ivec2((i as u32 % width) as i32, (i as u32 / width) as i32);
+        layer.set_tile(pos, Tile::from_raw_gid(*gid));
+    }
+}
+
+/// Loads files referenced by a map, such as external tilesets
+pub(super) type Resolver<'a> = &'a mut dyn FnMut(&str) -> anyhow::Result<String>;
+
+/// Looks up the texture for an image path as written in the tileset
+pub(super) type TextureLookup<'a, T> = &'a dyn Fn(&str) -> Option<T>;
+
+struct Loader<'a, T: Textures> {
+    map: Tilemap<T>,
+    resolve: Resolver<'a>,
+    textures: TextureLookup<'a, T>,
+    layer_count: usize,
+}
+
+impl<'a, T: Textures> Loader<'a, T> {
+    fn next_z(&mut self) -> f32 {
+        let z = FIRST_LAYER_Z - self.layer_count as f32 * LAYER_Z_STEP;
+        self.layer_count += 1;
+        z
+    }
+
+    fn texture(&self, image: &str) -> anyhow::Result<T> {
+        (self.textures)(image).ok_or_else(|| anyhow!("no texture for tileset image {image}"))
+    }
+}
+
+mod tmx {
+    use roxmltree::Node;
+
+    use super::*;
+
+    fn attr<'n>(node: Node<'n, '_>, name: &str) -> Option<&'n str> {
+        node.attribute(name)
+    }
+
+    fn parse_attr<F: std::str::FromStr>(node: Node, name: &str, default: F) -> anyhow::Result<F>
+    where
+        F::Err: std::error::Error + Send + Sync + 'static,
+    {
+        attr(node, name)
+            .map(|value| {
+                value.parse().with_context(|| {
+                    format!("invalid {name} attribute on <{}>", node.tag_name().name())
+                })
+            })
+            .unwrap_or(Ok(default))
+    }
+
+    fn children<'n, 'i>(
+        node: Node<'n, 'i>,
+        tag: &'static str,
+    ) -> impl Iterator<Item = Node<'n, 'i>> {
+        node.children().filter(move |child| child.has_tag_name(tag))
+    }
+
+    fn properties(node: Node) -> anyhow::Result<Properties> {
+        let mut properties = Properties::new();
+        for property in children(node, "properties").flat_map(|p| children(p, "property")) {
+            let name = attr(property, "name").unwrap_or_default();
+            // Multi-line strings are stored as text content
+            let value = attr(property, "value")
+                .or_else(|| property.text())
+                .unwrap_or_default();
+            if let Some(value) = parse_property(attr(property, "type").unwrap_or_default(), value)?
+            {
+                properties.insert(name.to_owned(), value);
+            }
+        }
+        Ok(properties)
+    }
+
+    fn layer_group(node: Node, parent: Group, tile_size: UVec2) -> anyhow::Result<Group> {
+        let offset = vec2(
+            parse_attr(node, "offsetx", 0.)?,
+            parse_attr(node, "offsety", 0.)?,
+        );
+        let color = match attr(node, "tintcolor") {
+            Some(tint) => parse_color(tint)?,
+            None => Color::WHITE,
+        };
+        Ok(parent.child(
+            to_map_space(offset, tile_size),
+            color.set_alpha(color.a * parse_attr(node, "opacity", 1.)?),
+            parse_attr(node, "visible", 1)? != 0,
+        ))
+    }
+
+    pub(super) fn tileset<T: Textures>(
+        loader: &mut Loader<T>,
+        node: Node,
+        first_gid: u32,
+    ) -> anyhow::Result<Tileset<T>> {
+        if let Some(source) = attr(node, "source") {
+            let file = (loader.resolve)(source)
+                .with_context(|| format!("failed to load tileset {source}"))?;
+            return if source.ends_with(".tsx") {
+                let document = roxmltree::Document::parse(&file)?;
+                tileset(loader, document.root_element(), first_gid)
+            } else {
+                tmj::tileset(loader, &serde_json::from_str(&file)?, first_gid)
+            };
+        }
+
+        let image = children(node, "image")
+            .next()
+            .ok_or_else(|| anyhow!("image collection tilesets are not supported"))?;
+        let tile_size = uvec2(
+            parse_attr(node, "tilewidth", 0)?,
+            parse_attr(node, "tileheight", 0)?,
+        );
+        let image_size = uvec2(
+            parse_attr(image, "width", 0)?,
+            parse_attr(image, "height", 0)?,
+        );
+        ensure!(
+            tile_size.x > 0 && tile_size.y > 0,
+            "tileset {} is missing tilewidth or tileheight",
+            attr(node, "name").unwrap_or_default()
+        );
+
+        let mut tileset = Tileset::new(
+            loader.texture(attr(image, "source").unwrap_or_default())?,
+            tile_size,
+            image_size,
+        )?
+        .set_first_gid(first_gid);
+        tileset.name = attr(node, "name").unwrap_or_default().to_owned();
+        tileset.margin = parse_attr(node, "margin", 0)?;
+        tileset.spacing = parse_attr(node, "spacing", 0)?;
+        tileset.columns = parse_attr(node, "columns", tileset.columns)?;
+        tileset.tile_count = parse_attr(node, "tilecount", tileset.tile_count)?;
+
+        for tile in children(node, "tile") {
+            let properties = properties(tile)?;
+            if !properties.is_empty() {
+                tileset
+                    .tile_properties
+                    .insert(parse_attr(tile, "id", 0)?, properties);
+            }
+        }
+
+        Ok(tileset)
+    }
+
+    fn tile_layer<T: Textures>(
+        loader: &mut Loader<T>,
+        node: Node,
+        group: Group,
+    ) -> anyhow::Result<()> {
+        let mut layer = TileLayer::new(attr(node, "name").unwrap_or_default());
+        layer.visible = group.visible;
+        layer.properties = properties(node)?;
+        layer.offset = group.offset;
+        layer.color = group.color;
+        layer.z = loader.next_z();
+
+        let width = parse_attr(node, "width", 0)?;
+        if let Some(data) = children(node, "data").next() {
+            let encoding = attr(data, "encoding").unwrap_or_default();
+            let compression = attr(data, "compression").unwrap_or_default();
+            let decode = |node: Node| -> anyhow::Result<Vec<u32>> {
+                if encoding.is_empty() {
+                    children(node, "tile")
+                        .map(|tile| parse_attr(tile, "gid", 0))
+                        .collect()
+                } else {
+                    decode_tile_data(node.text().unwrap_or_default(), encoding, compression)
+                }
+            };
+
+            let mut chunks = children(data, "chunk").peekable();
+            if chunks.peek().is_none() {
+                fill_tiles(&mut layer, IVec2::ZERO, width, &decode(data)?);
+            }
+            // Infinite maps
+            for chunk in chunks {
+                let origin = ivec2(parse_attr(chunk, "x", 0)?, parse_attr(chunk, "y", 0)?);
+                fill_tiles(
+                    &mut layer,
+                    origin,
+                    parse_attr(chunk, "width", 0)?,
+                    &decode(chunk)?,
+                );
+            }
+        }
+
+        loader.map.layers.push(layer);
+        Ok(())
+    }
+
+    fn points(node: Node, tile_size: UVec2) -> anyhow::Result<Vec<Vec2>> {
+        attr(node, "points")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|point| {
+                let (x, y) = point
+                    .split_once(',')
+                    .ok_or_else(|| anyhow!("invalid point {point}"))?;
+                Ok(to_map_space(vec2(x.parse()?, y.parse()?), tile_size))
+            })
+            .collect()
+    }
+
+    fn object_layer<T: Textures>(
+        loader: &mut Loader<T>,
+        node: Node,
+        group: Group,
+    ) -> anyhow::Result<()> {
+        let tile_size = loader.map.tile_size;
+        let mut objects = vec![];
+
+        for object in children(node, "object") {
+            let size = vec2(
+                parse_attr(object, "width", 0.)?,
+                parse_attr(object, "height", 0.)?,
+            ) / tile_size.as_vec2();
+            let child = |tag| children(object, tag).next();
+
+            let kind = if let Some(tile) = Tile::from_raw_gid(parse_attr(object, "gid", 0)?) {
+                ObjectKind::Tile { tile, size }
+            } else if let Some(polygon) = child("polygon") {
+                ObjectKind::Polygon(points(polygon, tile_size)?)
+            } else if let Some(polyline) = child("polyline") {
+                ObjectKind::Polyline(points(polyline, tile_size)?)
+            } else if child("ellipse").is_some() {
+                ObjectKind::Ellipse { size }
+            } else if child("point").is_some() {
+                ObjectKind::Point
+            } else if let Some(text) = child("text") {
+                ObjectKind::Text(text.text().unwrap_or_default().to_owned())
+            } else {
+                ObjectKind::Rectangle { size }
+            };
+
+            let position = vec2(parse_attr(object, "x", 0.)?, parse_attr(object, "y", 0.)?);
+            objects.push(TiledObject {
+                id: parse_attr(object, "id", 0)?,
+                name: attr(object, "name").unwrap_or_default().to_owned(),
+                class: attr(object, "class")
+                    .or_else(|| attr(object, "type"))
+                    .unwrap_or_default()
+                    .to_owned(),
+                position: to_map_space(position, tile_size) + group.offset,
+                rotation: -parse_attr(object, "rotation", 0f32)?.to_radians(),
+                visible: parse_attr(object, "visible", 1)? != 0,
+                kind,
+                properties: properties(object)?,
+            });
+        }
+
+        let z = loader.next_z();
+        loader.map.object_layers.push(ObjectLayer {
+            name: attr(node, "name").unwrap_or_default().to_owned(),
+            visible: group.visible,
+            color: group.color,
+            z,
+            objects,
+            properties: properties(node)?,
+        });
+        Ok(())
+    }
+
+    fn layers<T: Textures>(loader: &mut Loader<T>, node: Node, group: Group) -> anyhow::Result<()> {
+        for child in node.children().filter(Node::is_element) {
+            let tile_size = loader.map.tile_size;
+            match child.tag_name().name() {
+                "layer" => tile_layer(loader, child, layer_group(child, group, tile_size)?)?,
+                "objectgroup" => {
+                    object_layer(loader, child, layer_group(child, group, tile_size)?)?
+                }
+                "group" => layers(loader, child, layer_group(child, group, tile_size)?)?,
+                "imagelayer" => log::warn!("Tiled image layers are not supported, skipping"),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn map<T: Textures>(loader: &mut Loader<T>, source: &str) -> anyhow::Result<()> {
+        let document = roxmltree::Document::parse(source)?;
+        let root = document.root_element();
+        if !root.has_tag_name("map") {
+            bail!("expected a <map> root element");
+        }
+        let orientation = attr(root, "orientation").unwrap_or("orthogonal");
+        if orientation != "orthogonal" {
+            bail!("{orientation} maps are not supported, only orthogonal ones");
+        }
+
+        loader.map.tile_size = uvec2(
+            parse_attr(root, "tilewidth", 0)?,
+            parse_attr(root, "tileheight", 0)?,
+        );
+        ensure!(
+            loader.map.tile_size.x > 0 && loader.map.tile_size.y > 0,
+            "map is missing tilewidth or tileheight"
+        );
+        loader.map.properties = properties(root)?;
+
+        for node in children(root, "tileset") {
+            let tileset = tileset(loader, node, parse_attr(node, "firstgid", 1)?)?;
+            loader.map.tilesets.push(tileset);
+        }
+
+        let group = Group {
+            offset: Vec2::ZERO,
+            color: Color::WHITE,
+            visible: true,
+        };
+        layers(loader, root, group)
+    }
+}
+
+mod tmj {
+    use super::*;
+
+    fn number<F: Default + TryFrom<u64>>(value: &Value, key: &str) -> F {
+        value
+            .get(key)
+            .and_then(Value::as_u64)
+            .and_then(|n| F::try_from(n).ok())
+            .unwrap_or_default()
+    }
+
+    fn int(value: &Value, key: &str) -> i32 {
+        value.get(key).and_then(Value::as_i64).unwrap_or_default() as i32
+    }
+
+    fn float(value: &Value, key: &str, default: f32) -> f32 {
+        value
+            .get(key)
+            .and_then(Value::as_f64)
+            .map(|f| f as f32)
+            .unwrap_or(default)
+    }
+
+    fn string<'v>(value: &'v Value, key: &str) -> &'v str {
+        value.get(key).and_then(Value::as_str).unwrap_or_default()
+    }
+
+    fn array<'v>(value: &'v Value, key: &str) -> &'v [Value] {
+        value
+            .get(key)
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn properties(value: &Value) -> anyhow::Result<Properties> {
+        let mut properties = Properties::new();
+        for property in array(value, "properties") {
+            let raw = match &property["value"] {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            if let Some(parsed) = parse_property(string(property, "type"), &raw)? {
+                properties.insert(string(property, "name").to_owned(), parsed);
+            }
+        }
+        Ok(properties)
+    }
+
+    fn layer_group(value: &Value, parent: Group, tile_size: UVec2) -> anyhow::Result<Group> {
+        let offset = vec2(float(value, "offsetx", 0.), float(value, "offsety", 0.));
+        let color = match value.get("tintcolor").and_then(Value::as_str) {
+            Some(tint) => parse_color(tint)?,
+            None => Color::WHITE,
+        };
+        Ok(parent.child(
+            to_map_space(offset, tile_size),
+            color.set_alpha(color.a * float(value, "opacity", 1.)),
+            value
+                .get("visible")
+                .and_then(Value::as_bool)
+                .unwrap_or(true),
+        ))
+    }
+
+    pub(super) fn tileset<T: Textures>(
+        loader: &mut Loader<T>,
+        value: &Value,
+        first_gid: u32,
+    ) -> anyhow::Result<Tileset<T>> {
+        if let Some(source) = value.get("source").and_then(Value::as_str) {
+            let file = (loader.resolve)(source)
+                .with_context(|| format!("failed to load tileset {source}"))?;
+            return if source.ends_with(".tsx") {
+                let document = roxmltree::Document::parse(&file)?;
+                tmx::tileset(loader, document.root_element(), first_gid)
+            } else {
+                tileset(loader, &serde_json::from_str(&file)?, first_gid)
+            };
+        }
+
+        let image = value
+            .get("image")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("image collection tilesets are not supported"))?;
+        let tile_size = uvec2(number(value, "tilewidth"), number(value, "tileheight"));
+        let image_size = uvec2(number(value, "imagewidth"), number(value, "imageheight"));
+        ensure!(
+            tile_size.x > 0 && tile_size.y > 0,
+            "tileset {} is missing tilewidth or tileheight",
+            string(value, "name")
+        );
+
+        let mut tileset =
+            Tileset::new(loader.texture(image)?, tile_size, image_size)?.set_first_gid(first_gid);
+        tileset.name = string(value, "name").to_owned();
+        tileset.margin = number(value, "margin");
+        tileset.spacing = number(value, "spacing");
+        if value.get("columns").is_some() {
+            tileset.columns = number(value, "columns");
+        }
+        if value.get("tilecount").is_some() {
+            tileset.tile_count = number(value, "tilecount");
+        }
+
+        for tile in array(value, "tiles") {
+            let properties = properties(tile)?;
+            if !properties.is_empty() {
+                tileset
+                    .tile_properties
+                    .insert(number(tile, "id"), properties);
+            }
+        }
+
+        Ok(tileset)
+    }
+
+    fn tile_data(value: &Value, encoding: &str, compression: &str) -> anyhow::Result<Vec<u32>> {
+        match &value["data"] {
+            Value::String(data) => decode_tile_data(data, encoding, compression),
+            Value::Array(gids) => Ok(gids
+                .iter()
+                .map(|gid| gid.as_u64().unwrap_or_default() as u32)
+                .collect()),
+            _ => Ok(vec![]),
+        }
+    }
+
+    fn tile_layer<T: Textures>(
+        loader: &mut Loader<T>,
+        value: &Value,
+        group: Group,
+    ) -> anyhow::Result<()> {
+        let mut layer = TileLayer::new(string(value, "name"));
+        layer.visible = group.visible;
+        layer.properties = properties(value)?;
+        layer.offset = group.offset;
+        layer.color = group.color;
+        layer.z = loader.next_z();
+
+        let encoding = value
+            .get("encoding")
+            .and_then(Value::as_str)
+            .unwrap_or("csv");
+        let compression = string(value, "compression");
+
+        fill_tiles(
+            &mut layer,
+            IVec2::ZERO,
+            number(value, "width"),
+            &tile_data(value, encoding, compression)?,
+        );
+        // Infinite maps
+        for chunk in array(value, "chunks") {
+            fill_tiles(
+                &mut layer,
+                ivec2(int(chunk, "x"), int(chunk, "y")),
+                number(chunk, "width"),
+                &tile_data(chunk, encoding, compression)?,
+            );
+        }
+
+        loader.map.layers.push(layer);
+        Ok(())
+    }
+
+    fn object_layer<T: Textures>(
+        loader: &mut Loader<T>,
+        value: &Value,
+        group: Group,
+    ) -> anyhow::Result<()> {
+        let tile_size = loader.map.tile_size;
+        let points = |points: &[Value]| {
+            points
+                .iter()
+                .map(|p| to_map_space(vec2(float(p, "x", 0.), float(p, "y", 0.)), tile_size))
+                .collect::<Vec<_>>()
+        };
+        let flag = |object: &Value, key| object.get(key).and_then(Value::as_bool).unwrap_or(false);
+
+        let mut objects = vec![];
+        for object in array(value, "objects") {
+            let size =
+                vec2(float(object, "width", 0.), float(object, "height", 0.)) / tile_size.as_vec2();
+
+            let kind = if let Some(tile) = Tile::from_raw_gid(number(object, "gid")) {
+                ObjectKind::Tile { tile, size }
+            } else if object.get("polygon").is_some() {
+                ObjectKind::Polygon(points(array(object, "polygon")))
+            } else if object.get("polyline").is_some() {
+                ObjectKind::Polyline(points(array(object, "polyline")))
+            } else if flag(object, "ellipse") {
+                ObjectKind::Ellipse { size }
+            } else if flag(object, "point") {
+                ObjectKind::Point
+            } else if let Some(text) = object.get("text") {
+                ObjectKind::Text(string(text, "text").to_owned())
+            } else {
+                ObjectKind::Rectangle { size }
+            };
+
+            let position = vec2(float(object, "x", 0.), float(object, "y", 0.));
+            objects.push(TiledObject {
+                id: number(object, "id"),
+                name: string(object, "name").to_owned(),
+                class: object
+                    .get("class")
+                    .or_else(|| object.get("type"))
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_owned(),
+                position: to_map_space(position, tile_size) + group.offset,
+                rotation: -float(object, "rotation", 0.).to_radians(),
+                visible: object
+                    .get("visible")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true),
+                kind,
+                properties: properties(object)?,
+            });
+        }
+
+        let z = loader.next_z();
+        loader.map.object_layers.push(ObjectLayer {
+            name: string(value, "name").to_owned(),
+            visible: group.visible,
+            color: group.color,
+            z,
+            objects,
+            properties: properties(value)?,
+        });
+        Ok(())
+    }
+
+    fn layers<T: Textures>(
+        loader: &mut Loader<T>,
+        value: &Value,
+        group: Group,
+    ) -> anyhow::Result<()> {
+        for layer in array(value, "layers") {
+            let group = layer_group(layer, group, loader.map.tile_size)?;
+            match string(layer, "type") {
+                "tilelayer" => tile_layer(loader, layer, group)?,
+                "objectgroup" => object_layer(loader, layer, group)?,
+                "group" => layers(loader, layer, group)?,
+                "imagelayer" => log::warn!("Tiled image layers are not supported, skipping"),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn map<T: Textures>(loader: &mut Loader<T>, source: &str) -> anyhow::Result<()> {
+        let root: Value = serde_json::from_str(source)?;
+        let orientation = root
+            .get("orientation")
+            .and_then(Value::as_str)
+            .unwrap_or("orthogonal");
+        if orientation != "orthogonal" {
+            bail!("{orientation} maps are not supported, only orthogonal ones");
+        }
+
+        loader.map.tile_size = uvec2(number(&root, "tilewidth"), number(&root, "tileheight"));
+        ensure!(
+            loader.map.tile_size.x > 0 && loader.map.tile_size.y > 0,
+            "map is missing tilewidth or tileheight"
+        );
+        loader.map.properties = properties(&root)?;
+
+        for value in array(&root, "tilesets") {
+            let first_gid = value.get("firstgid").and_then(Value::as_u64).unwrap_or(1) as u32;
+            let tileset = tileset(loader, value, first_gid)?;
+            loader.map.tilesets.push(tileset);
+        }
+
+        let group = Group {
+            offset: Vec2::ZERO,
+            color: Color::WHITE,
+            visible: true,
+        };
+        layers(loader, &root, group)
+    }
+}
+
+impl<T: Textures> Tilemap<T> {
+    fn load_with(
+        source: &str,
+        json: bool,
+        resolve: Resolver,
+        textures: TextureLookup<T>,
+    ) -> anyhow::Result<Self> {
+        let mut loader = Loader {
+            map: Tilemap::new(UVec2::ONE),
+            resolve,
+            textures,
+            layer_count: 0,
+        };
+        if json {
+            tmj::map(&mut loader, source)?;
+        } else {
+            tmx::map(&mut loader, source)?;
+        }
+        Ok(loader.map)
+    }
+
+    /// Parses a `.tmx` map, `textures` maps the image path of every tileset to a texture.
+    /// Tilesets have to be embedded in the map, use [`Tilemap::load`] for external ones.
+    pub fn from_tmx(source: &str, textures: impl Fn(&str) -> Option<T>) -> anyhow::Result<Self> {
+        Self::load_with(source, false, &mut unresolved, &textures)
+    }
+
+    /// Parses a `.tmj` map, see [`Tilemap::from_tmx`].
+    pub fn from_tmj(source: &str, textures: impl Fn(&str) -> Option<T>) -> anyhow::Result<Self> {
+        Self::load_with(source, true, &mut unresolved, &textures)
+    }
+
+    /// Reads a `.tmx` or `.tmj` map from disk, external tilesets are looked up relative to it.
+    pub fn load(
+        path: impl AsRef<Path>,
+        textures: impl Fn(&str) -> Option<T>,
+    ) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let mut resolve = |file: &str| -> anyhow::Result<String> {
+            Ok(std::fs::read_to_string(directory.join(file))?)
+        };
+        let json = matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("tmj" | "json")
+        );
+
+        Self::load_with(&source, json, &mut resolve, &textures)
+    }
+}
+
+fn unresolved(file: &str) -> anyhow::Result<String> {
+    bail!("external file {file} can't be loaded from a string, use Tilemap::load instead")
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::ivec2;
+
+    use super::*;
+
+    const TMX: &str = include_str!("../../../tests/fixtures/tiled/map.tmx");
+    const TMJ: &str = include_str!("../../../tests/fixtures/tiled/map.tmj");
+
+    fn textures(image: &str) -> Option<TestTextures> {
+        (image == "terrain.png").then_some(TestTextures::Tiles)
+    }
+
+    fn check(map: &Tilemap<TestTextures>) {
+        assert_eq!(map.tile_size, uvec2(16, 16));
+        assert!(matches!(&map.properties["music"], Property::String(music) if music == "forest"));
+
+        let tileset = &map.tilesets[0];
+        assert_eq!(tileset.name, "terrain");
+        assert_eq!(tileset.texture, TestTextures::Tiles);
+        assert_eq!((tileset.columns, tileset.tile_count), (2, 4));
+        assert!(matches!(
+            map.tile_properties(2)
+                .map(|properties| &properties["solid"]),
+            Some(Property::Bool(true))
+        ));
+
+        let ground = map.layer("ground").unwrap();
+        assert_eq!(ground.tile(ivec2(0, 0)), Some(Tile::new(1)));
+        assert_eq!(ground.tile(ivec2(1, 0)), Some(Tile::new(2)));
+        assert_eq!(ground.tile(ivec2(2, 0)), None);
+        assert_eq!(ground.tile(ivec2(1, 1)), Some(Tile::new(4).flip_x()));
+        assert_eq!(ground.tile(ivec2(2, 1)), Some(Tile::new(3)));
+        assert_eq!(ground.tiles().count(), 4);
+        assert_eq!(ground.color(), Color::WHITE.set_alpha(0.5));
+
+        let player = map
+            .object_layer("spawns")
+            .unwrap()
+            .object("player")
+            .unwrap();
+        assert_eq!(player.position, vec2(1., -1.));
+        assert_eq!(player.kind, ObjectKind::Rectangle { size: Vec2::ONE });
+    }
+
+    #[test]
+    fn tmx() {
+        check(&Tilemap::from_tmx(TMX, textures).unwrap());
+    }
+
+    #[test]
+    fn tmj() {
+        check(&Tilemap::from_tmj(TMJ, textures).unwrap());
+    }
+
+    #[test]
+    fn missing_tile_size() {
+        let tmx = TMX.replace(
+            r#"name="terrain" tilewidth="16" tileheight="16""#,
+            r#"name="terrain""#,
+        );
+        assert!(Tilemap::from_tmx(&tmx, textures).is_err());
+        let tmj = TMJ.replace(
+            r#""tilewidth": 16,
+      "tileheight": 16,
+      "tilecount""#,
+            r#""tilecount""#,
+        );
+        assert_ne!(tmj, TMJ);
+        assert!(Tilemap::from_tmj(&tmj, textures).is_err());
+        assert!(Tileset::new(TestTextures::Tiles, uvec2(0, 16), uvec2(32, 32)).is_err());
+
+        // The map's own tile size, which every position is divided by
+        for map_size in [r#"tilewidth="0" tileheight="16""#, r#"tileheight="16""#] {
+            let tmx = TMX.replacen(r#"tilewidth="16" tileheight="16""#, map_size, 1);
+            assert_ne!(tmx, TMX);
+            assert!(Tilemap::from_tmx(&tmx, textures).is_err());
+        }
+        for map_size in ["\"tilewidth\": 0,\n", ""] {
+            let tmj = TMJ.replacen("\"tilewidth\": 16,\n", map_size, 1);
+            assert_ne!(tmj, TMJ);
+            assert!(Tilemap::from_tmj(&tmj, textures).is_err());
+        }
+    }
+
+    #[test]
+    fn unknown_texture() {
+        assert!(Tilemap::<TestTextures>::from_tmx(TMX, |_| None).is_err());
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+mod editor;
+
+/// How a key blends into the next one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Holds the value until the next key
+    Step,
+    #[default]
+    Linear,
+    Ease(Ease),
+}
+
+impl Interpolation {
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Step => 0.,
+            Self::Linear => t,
+            Self::Ease(ease) => ease.apply(t),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Key<V> {
+    /// Seconds from the start of the timeline
+    pub time: f32,
+    pub value: V,
+    pub interpolation: Interpolation,
+}
+
+/// Keys of one animated value sorted by time, before the first and after the last key the
+/// value stays constant
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Keys<V>")]
+pub struct Track<V> {
+    keys: Vec<Key<V>>,
+}
+
+/// Keys as they are stored, in any order
+#[derive(Deserialize)]
+struct Keys<V> {
+    keys: Vec<Key<V>>,
+}
+
+/// Later keys replace earlier ones at the same time, like with [`Track::insert_key`]
+impl<V> From<Keys<V>> for Track<V> {
+    fn from(Keys { mut keys }: Keys<V>) -> Self {
+        keys.reverse();
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        keys.dedup_by(|later, earlier| later.time == earlier.time);
+        Self { keys }
+    }
+}
+
+impl<V> Default for Track<V> {
+    fn default() -> Self {
+        Self { keys: vec![] }
+    }
+}
+
+impl<V: Lerp + Clone> Track<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keys at the same time are replaced
+    pub fn add_key(mut self, time: f32, value: V, interpolation: Interpolation) -> Self {
+        self.insert_key(Key {
+            time,
+            value,
+            interpolation,
+        });
+        self
+    }
+
+    /// Keys at the same time are replaced
+    pub fn insert_key(&mut self, key: Key<V>) {
+        match self.keys.binary_search_by(|k| k.time.total_cmp(&key.time)) {
+            Ok(i) => self.keys[i] = key,
+            Err(i) => self.keys.insert(i, key),
+        }
+    }
+
+    pub fn remove_key(&mut self, index: usize) -> Option<Key<V>> {
+        (index < self.keys.len()).then(|| self.keys.remove(index))
+    }
+
+    pub fn keys(&self) -> &[Key<V>] {
+        &self.keys
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Time of the last key
+    pub fn duration(&self) -> f32 {
+        self.keys.last().map_or(0., |key| key.time)
+    }
+
+    /// `None` for tracks without keys
+    pub fn sample(&self, time: f32) -> Option<V> {
+        let next = self.keys.partition_point(|key| key.time <= time);
+        match (self.keys.get(next.wrapping_sub(1)), self.keys.get(next)) {
+            (Some(a), Some(b)) => {
+                let t = (time - a.time) / (b.time - a.time);
+                Some(a.value.lerp(&b.value, a.interpolation.apply(t)))
+            }
+            (Some(key), None) | (None, Some(key)) => Some(key.value.clone()),
+            (None, None) => None,
+        }
+    }
+
+    /// Restores the order after key times were changed in place
+    fn sort(&mut self) {
+        self.keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+}
+
+/// Values of all tracks of a [`Timeline`] at one point in time
+#[derive(Clone, Debug)]
+pub struct Pose {
+    /// Position, rotation and scale, defaults for tracks without keys
+    pub gtransform: GTransform,
+    /// White without colour keys
+    pub color: Color,
+    /// Texture frame, like a tile of a sprite sheet
+    pub frame: Option<u32>,
+    pub custom: BTreeMap<String, f32>,
+}
+
+/// Keyframed tracks animating an object, serialisable so animations can be authored as data
+/// and scrubbed in [`Timeline::editor`]. Rotation is interpolated as a plain angle so keys can
+/// turn by more than half a circle.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timeline {
+    pub position: Track<Vec2>,
+    pub rotation: Track<f32>,
+    pub scale: Track<Vec2>,
+    pub color: Track<Color>,
+    /// Linear interpolation counts through the frames in between
+    pub frame: Track<u32>,
+    pub custom: BTreeMap<String, Track<f32>>,
+    /// Plays again from the start after the last key
+    pub looping: bool,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Time of the last key of any track
+    pub fn duration(&self) -> f32 {
+        [
+            self.position.duration(),
+            self.rotation.duration(),
+            self.scale.duration(),
+            self.color.duration(),
+            self.frame.duration(),
+        ]
+        .into_iter()
+        .chain(self.custom.values().map(Track::duration))
+        .fold(0., f32::max)
+    }
+
+    pub fn sample(&self, time: f32) -> Pose {
+        let duration = self.duration();
+        let time = match self.looping && duration > 0. {
+            true => time.rem_euclid(duration),
+            false => time,
+        };
+        let default = GTransform::default();
+
+        Pose {
+            gtransform: GTransform {
+                center: self.position.sample(time).unwrap_or(default.center),
+                rotation: self.rotation.sample(time).unwrap_or(default.rotation),
+                scale: self.scale.sample(time).unwrap_or(default.scale),
+            },
+            color: self.color.sample(time).unwrap_or(Color::WHITE),
+            frame: self.frame.sample(time),
+            custom: self
+                .custom
+                .iter()
+                .filter_map(|(name, track)| Some((name.clone(), track.sample(time)?)))
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{a} instead of {b}");
+    }
+
+    fn times<V>(track: &Track<V>) -> Vec<f32> {
+        track.keys.iter().map(|key| key.time).collect()
+    }
+
+    #[test]
+    fn samples_before_between_and_after_keys() {
+        let track = Track::new()
+            .add_key(3., 20., Interpolation::Step)
+            .add_key(1., 10., Interpolation::Linear)
+            .add_key(4., 0., Interpolation::Linear);
+        assert_eq!(times(&track), [1., 3., 4.]);
+        assert_eq!(track.duration(), 4.);
+
+        for (time, value) in [(-1., 10.), (1., 10.), (2., 15.), (2.5, 17.5), (3., 20.)] {
+            assert_close(track.sample(time).unwrap(), value);
+        }
+        // Step keys hold their value until the next key
+        assert_close(track.sample(3.99).unwrap(), 20.);
+        assert_close(track.sample(4.).unwrap(), 0.);
+        assert_close(track.sample(100.).unwrap(), 0.);
+
+        // Keys at the same time replace each other
+        let track = track.add_key(1., 0., Interpolation::Linear);
+        assert_eq!(times(&track), [1., 3., 4.]);
+        assert_close(track.sample(2.).unwrap(), 10.);
+        assert_eq!(Track::<f32>::new().sample(1.), None);
+    }
+
+    #[test]
+    fn eases_between_keys() {
+        let end = vec2(4., -8.);
+        let track = Track::new()
+            .add_key(0., Vec2::ZERO, Interpolation::Ease(Ease::QuadIn))
+            .add_key(2., end, Interpolation::Linear);
+        let eased = Ease::QuadIn.apply(0.25);
+        assert!(eased < 0.25);
+        assert!(track.sample(0.5).unwrap().distance(end * eased) < 1e-5);
+        assert!(track.sample(2.).unwrap().distance(end) < 1e-5);
+    }
+
+    #[test]
+    fn counts_through_frames() {
+        let track = Track::new().add_key(0., 0, Interpolation::Linear).add_key(
+            1.,
+            4,
+            Interpolation::Linear,
+        );
+        // Every frame is shown for a quarter of a second
+        let frames = [0., 0.24, 0.25, 0.5, 0.74, 0.99, 1.].map(|time| track.sample(time).unwrap());
+        assert_eq!(frames, [0, 0, 1, 2, 2, 3, 4]);
+
+        let backwards = Track::new().add_key(0., 4, Interpolation::Linear).add_key(
+            1.,
+            0,
+            Interpolation::Linear,
+        );
+        assert_eq!(backwards.sample(0.1), Some(3));
+    }
+
+    #[test]
+    fn loops_around() {
+        let mut timeline = Timeline::new().set_looping(true);
+        timeline.rotation = Track::new().add_key(0., 0., Interpolation::Linear).add_key(
+            2.,
+            1.,
+            Interpolation::Linear,
+        );
+        let rotation = |timeline: &Timeline, time| timeline.sample(time).gtransform.rotation;
+        assert_eq!(timeline.duration(), 2.);
+        assert_close(rotation(&timeline, 2.5), 0.25);
+        assert_close(rotation(&timeline, -0.5), 0.75);
+        assert_close(rotation(&timeline, 4.), 0.);
+
+        // Custom tracks count towards the duration
+        let glow = Track::new().add_key(4., 1., Interpolation::Linear);
+        timeline.custom.insert("glow".into(), glow);
+        assert_eq!(timeline.duration(), 4.);
+        assert_close(rotation(&timeline, 5.), 0.5);
+        assert_close(rotation(&timeline, 3.), 1.);
+
+        timeline.looping = false;
+        assert_close(rotation(&timeline, 5.), 1.);
+        assert_close(rotation(&timeline, -1.), 0.);
+    }
+
+    #[test]
+    fn samples_defaults_without_keys() {
+        let pose = Timeline::new().set_looping(true).sample(1.);
+        let default = GTransform::default();
+        assert_eq!(pose.gtransform.center, default.center);
+        assert_eq!(pose.gtransform.rotation, default.rotation);
+        assert_eq!(pose.gtransform.scale, default.scale);
+        assert_eq!(pose.color, Color::WHITE);
+        assert_eq!(pose.frame, None);
+        assert!(pose.custom.is_empty());
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut timeline = Timeline::new().set_looping(true);
+        timeline.position = Track::new()
+            .add_key(0., Vec2::ZERO, Interpolation::Ease(Ease::BackOut))
+            .add_key(1.5, vec2(3., -2.), Interpolation::Linear);
+        timeline.rotation = Track::new().add_key(0.5, 7., Interpolation::Step);
+        timeline.scale = Track::new().add_key(1., Vec2::splat(2.), Interpolation::Linear);
+        timeline.color = Track::new()
+            .add_key(0., Color::RED, Interpolation::Linear)
+            .add_key(2., Color::BLUE.set_alpha(0.5), Interpolation::Linear);
+        timeline.frame = Track::new().add_key(0.25, 3, Interpolation::Step);
+        timeline.custom.insert(
+            "glow".into(),
+            Track::new().add_key(1., 0.5, Interpolation::Linear),
+        );
+
+        let json = timeline.to_json().unwrap();
+        assert_eq!(Timeline::from_json(&json).unwrap(), timeline);
+        assert!(Timeline::from_json("{").is_err());
+    }
+
+    #[test]
+    fn sorts_keys_when_deserializing() {
+        let json = r#"{"keys": [
+            {"time": 2, "value": 20, "interpolation": "Linear"},
+            {"time": 0, "value": 0, "interpolation": {"Ease": "QuadIn"}},
+            {"time": 1, "value": 10, "interpolation": "Linear"},
+            {"time": 2, "value": 30, "interpolation": "Step"}
+        ]}"#;
+        let track = serde_json::from_str::<Track<f32>>(json).unwrap();
+        // The later of the keys at the same time wins, like when adding them in that order
+        let expected = Track::new()
+            .add_key(0., 0., Interpolation::Ease(Ease::QuadIn))
+            .add_key(1., 10., Interpolation::Linear)
+            .add_key(2., 30., Interpolation::Step);
+        assert_eq!(track, expected);
+        assert_close(track.sample(1.5).unwrap(), 20.);
+    }
+}
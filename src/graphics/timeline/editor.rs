@@ -0,0 +1,229 @@
+use egui::{CollapsingHeader, ComboBox, DragValue, Slider, Ui};
+
+use super::*;
+
+/// Widget for the value of a key
+trait EditValue {
+    fn edit(&mut self, ui: &mut Ui);
+}
+
+impl EditValue for f32 {
+    fn edit(&mut self, ui: &mut Ui) {
+        ui.add(DragValue::new(self).speed(0.01));
+    }
+}
+
+impl EditValue for Vec2 {
+    fn edit(&mut self, ui: &mut Ui) {
+        ui.add(DragValue::new(&mut self.x).speed(0.01).prefix("x: "));
+        ui.add(DragValue::new(&mut self.y).speed(0.01).prefix("y: "));
+    }
+}
+
+impl EditValue for u32 {
+    fn edit(&mut self, ui: &mut Ui) {
+        ui.add(DragValue::new(self));
+    }
+}
+
+impl EditValue for Color {
+    fn edit(&mut self, ui: &mut Ui) {
+        // The picker works on linear channels, colours store them sRGB encoded
+        let mut rgba = self.to_linear();
+        if ui.color_edit_button_rgba_unmultiplied(&mut rgba).changed() {
+            let [r, g, b, a] = rgba;
+            *self = Color::linear(r, g, b).set_alpha(a);
+        }
+    }
+}
+
+fn interpolation_name(interpolation: Interpolation) -> String {
+    match interpolation {
+        Interpolation::Ease(ease) => format!("{ease:?}"),
+        interpolation => format!("{interpolation:?}"),
+    }
+}
+
+fn edit_interpolation(ui: &mut Ui, interpolation: &mut Interpolation) {
+    ComboBox::from_id_source("interpolation")
+        .selected_text(interpolation_name(*interpolation))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(interpolation, Interpolation::Step, "Step");
+            ui.selectable_value(interpolation, Interpolation::Linear, "Linear");
+            for ease in Ease::iter().filter(|ease| *ease != Ease::Linear) {
+                ui.selectable_value(
+                    interpolation,
+                    Interpolation::Ease(ease),
+                    format!("{ease:?}"),
+                );
+            }
+        });
+}
+
+/// `default` is the value of the first key added to an empty track
+fn edit_track<V: Lerp + Clone + EditValue>(
+    ui: &mut Ui,
+    name: &str,
+    track: &mut Track<V>,
+    time: f32,
+    default: V,
+) {
+    CollapsingHeader::new(format!("{name} ({})", track.keys.len()))
+        .id_source(name)
+        .show(ui, |ui| {
+            let mut removed = None;
+            let mut editing_time = false;
+            for (i, key) in track.keys.iter_mut().enumerate() {
+                ui.push_id(i, |ui| {
+                    ui.horizontal(|ui| {
+                        let response = ui.add(
+                            DragValue::new(&mut key.time)
+                                .speed(0.01)
+                                .clamp_range(0. ..=f32::MAX)
+                                .suffix(" s"),
+                        );
+                        editing_time |= response.dragged() || response.has_focus();
+                        key.value.edit(ui);
+                        edit_interpolation(ui, &mut key.interpolation);
+                        if ui.small_button("🗑").clicked() {
+                            removed = Some(i);
+                        }
+                    });
+                });
+            }
+            if let Some(i) = removed {
+                track.remove_key(i);
+            }
+            // Rows are identified by index, sorting while a time is dragged or typed would
+            // hand the edit over to another key
+            if !editing_time {
+                track.sort();
+            }
+
+            if ui.button("Add key").clicked() {
+                let value = track.sample(time).unwrap_or(default);
+                track.insert_key(Key {
+                    time,
+                    value,
+                    interpolation: Interpolation::default(),
+                });
+            }
+        });
+}
+
+impl Timeline {
+    /// Panel for scrubbing through the timeline at `time` and editing its keys, new keys are
+    /// added at `time` with the value the track has there
+    pub fn editor(&mut self, ui: &mut Ui, time: &mut f32) {
+        ui.horizontal(|ui| {
+            let duration = self.duration().max(*time).max(1.);
+            ui.add(Slider::new(time, 0. ..=duration).suffix(" s"));
+            ui.checkbox(&mut self.looping, "Loop");
+        });
+
+        let transform = GTransform::default();
+        edit_track(ui, "Position", &mut self.position, *time, transform.center);
+        edit_track(
+            ui,
+            "Rotation",
+            &mut self.rotation,
+            *time,
+            transform.rotation,
+        );
+        edit_track(ui, "Scale", &mut self.scale, *time, transform.scale);
+        edit_track(ui, "Color", &mut self.color, *time, Color::WHITE);
+        edit_track(ui, "Frame", &mut self.frame, *time, 0);
+        for (name, track) in &mut self.custom {
+            edit_track(ui, name, track, *time, 0.);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::epaint::ClippedShape;
+    use egui::{CentralPanel, Context, Event, PointerButton, Pos2, RawInput, Rect};
+
+    use super::*;
+
+    /// Runs one frame of the rotation track editor, opened, and returns where the text is
+    fn frame(ctx: &Context, track: &mut Track<f32>, events: Vec<Event>) -> Vec<(String, Rect)> {
+        let input = RawInput {
+            events,
+            ..Default::default()
+        };
+        let output = ctx.run(input, |ctx| {
+            CentralPanel::default().show(ctx, |ui| {
+                edit_track(ui, "Rotation", track, 0., 0.);
+            });
+        });
+        let mut shapes = output
+            .shapes
+            .into_iter()
+            .map(|ClippedShape(_, shape)| shape)
+            .collect::<Vec<_>>();
+        let mut texts = vec![];
+        while let Some(shape) = shapes.pop() {
+            match shape {
+                egui::Shape::Vec(inner) => shapes.extend(inner),
+                egui::Shape::Text(text) => texts.push((
+                    text.galley.text().to_owned(),
+                    text.galley.rect.translate(text.pos.to_vec2()),
+                )),
+                _ => {}
+            }
+        }
+        texts
+    }
+
+    fn press(pos: Pos2, pressed: bool) -> Event {
+        Event::PointerButton {
+            pos,
+            button: PointerButton::Primary,
+            pressed,
+            modifiers: Default::default(),
+        }
+    }
+
+    #[test]
+    fn keeps_dragging_the_same_key_past_another() {
+        let ctx = Context::default();
+        let mut track = Track::new()
+            .add_key(1., 10., Interpolation::Linear)
+            .add_key(2., 20., Interpolation::Linear);
+        let header = frame(&ctx, &mut track, vec![])[0].1.center();
+        frame(
+            &ctx,
+            &mut track,
+            vec![Event::PointerMoved(header), press(header, true)],
+        );
+        frame(&ctx, &mut track, vec![press(header, false)]);
+        // Lets the header finish opening
+        let texts = (0..10).fold(vec![], |_, _| frame(&ctx, &mut track, vec![]));
+        let (_, rect) = texts.iter().find(|(text, _)| text == "1.00 s").unwrap();
+
+        // Dragging right raises the time past the other key
+        let start = rect.center();
+        frame(
+            &ctx,
+            &mut track,
+            vec![Event::PointerMoved(start), press(start, true)],
+        );
+        for x in [10., 50., 100., 150.] {
+            let pos = start + egui::vec2(x, 0.);
+            frame(&ctx, &mut track, vec![Event::PointerMoved(pos)]);
+        }
+        assert_eq!(track.keys[0].value, 10.);
+        assert!(track.keys[0].time > 2.);
+        frame(
+            &ctx,
+            &mut track,
+            vec![press(start + egui::vec2(150., 0.), false)],
+        );
+
+        // Sorted once the drag is released
+        assert_eq!(track.keys[0].time, 2.);
+        assert!(track.keys[1].time > 2.);
+        assert_eq!(track.keys[1].value, 10.);
+    }
+}
@@ -0,0 +1,385 @@
+use std::f32::consts::TAU;
+
+use super::*;
+
+/// Easing curve mapping linear progress from 0 to 1 onto eased progress. Back and elastic
+/// curves overshoot outside of 0 to 1 on the way.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Hash, EnumIter, serde::Serialize, serde::Deserialize,
+)]
+pub enum Ease {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1. / D {
+        N * t * t
+    } else if t < 2. / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+impl Ease {
+    pub fn apply(self, t: f32) -> f32 {
+        const BACK: f32 = 1.70158;
+        const BACK_IN_OUT: f32 = BACK * 1.525;
+        const ELASTIC: f32 = TAU / 3.;
+        const ELASTIC_IN_OUT: f32 = TAU / 4.5;
+
+        let t = t.clamp(0., 1.);
+        match self {
+            Self::Linear => t,
+            Self::QuadIn => t * t,
+            Self::QuadOut => 1. - (1. - t).powi(2),
+            Self::QuadInOut if t < 0.5 => 2. * t * t,
+            Self::QuadInOut => 1. - (2. - 2. * t).powi(2) / 2.,
+            Self::CubicIn => t.powi(3),
+            Self::CubicOut => 1. - (1. - t).powi(3),
+            Self::CubicInOut if t < 0.5 => 4. * t.powi(3),
+            Self::CubicInOut => 1. - (2. - 2. * t).powi(3) / 2.,
+            Self::BackIn => (BACK + 1.) * t.powi(3) - BACK * t * t,
+            Self::BackOut => 1. + (BACK + 1.) * (t - 1.).powi(3) + BACK * (t - 1.).powi(2),
+            Self::BackInOut if t < 0.5 => {
+                (2. * t).powi(2) * ((BACK_IN_OUT + 1.) * 2. * t - BACK_IN_OUT) / 2.
+            }
+            Self::BackInOut => {
+                let t = 2. * t - 2.;
+                (t * t * ((BACK_IN_OUT + 1.) * t + BACK_IN_OUT) + 2.) / 2.
+            }
+            _ if t == 0. || t == 1. => t,
+            Self::ElasticIn => -(2f32.powf(10. * t - 10.)) * ((10. * t - 10.75) * ELASTIC).sin(),
+            Self::ElasticOut => 2f32.powf(-10. * t) * ((10. * t - 0.75) * ELASTIC).sin() + 1.,
+            Self::ElasticInOut if t < 0.5 => {
+                -(2f32.powf(20. * t - 10.) * ((20. * t - 11.125) * ELASTIC_IN_OUT).sin()) / 2.
+            }
+            Self::ElasticInOut => {
+                2f32.powf(-20. * t + 10.) * ((20. * t - 11.125) * ELASTIC_IN_OUT).sin() / 2. + 1.
+            }
+            Self::BounceIn => 1. - bounce_out(1. - t),
+            Self::BounceOut => bounce_out(t),
+            Self::BounceInOut if t < 0.5 => (1. - bounce_out(1. - 2. * t)) / 2.,
+            Self::BounceInOut => (1. + bounce_out(2. * t - 1.)) / 2.,
+        }
+    }
+}
+
+/// How often a tween plays
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Repeat {
+    #[default]
+    Once,
+    /// Plays the given number of times in total
+    Times(u32),
+    Forever,
+}
+
+#[derive(Clone, Debug)]
+struct Step<V> {
+    value: V,
+    duration: f32,
+    ease: Ease,
+}
+
+/// Value animated through a sequence of steps, advanced by [`Tween::update`] from
+/// `App::update`. Works with anything implementing [`Lerp`], like `f32`, `Vec2`, `Color` and
+/// `GTransform`.
+///
+/// ```ignore
+/// let mut pop = Tween::new(0.)
+///     .to(1.2, 0.15, Ease::BackOut)
+///     .to(1., 0.1, Ease::QuadInOut)
+///     .on_complete(|| log::info!("Popped"));
+/// ```
+pub struct Tween<V> {
+    start: V,
+    steps: Vec<Step<V>>,
+    repeat: Repeat,
+    yoyo: bool,
+    elapsed: f32,
+    finished: bool,
+    on_complete: Option<Box<dyn FnMut()>>,
+}
+
+impl<V: std::fmt::Debug> std::fmt::Debug for Tween<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tween")
+            .field("start", &self.start)
+            .field("steps", &self.steps)
+            .field("repeat", &self.repeat)
+            .field("yoyo", &self.yoyo)
+            .field("elapsed", &self.elapsed)
+            .field("finished", &self.finished)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<V: Lerp + Clone> Tween<V> {
+    pub fn new(start: V) -> Self {
+        Self {
+            start,
+            steps: vec![],
+            repeat: Repeat::Once,
+            yoyo: false,
+            elapsed: 0.,
+            finished: false,
+            on_complete: None,
+        }
+    }
+
+    /// Moves on to `value` over `duration` seconds after the previous steps
+    pub fn to(mut self, value: V, duration: f32, ease: Ease) -> Self {
+        self.steps.push(Step {
+            value,
+            duration: duration.max(0.),
+            ease,
+        });
+        self
+    }
+
+    /// Holds the current value for `duration` seconds
+    pub fn wait(self, duration: f32) -> Self {
+        let value = self.end().clone();
+        self.to(value, duration, Ease::Linear)
+    }
+
+    pub fn set_repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Plays every other repetition backwards
+    pub fn set_yoyo(mut self, yoyo: bool) -> Self {
+        self.yoyo = yoyo;
+        self
+    }
+
+    /// Called once when the last repetition ends, never for [`Repeat::Forever`]
+    pub fn on_complete(mut self, callback: impl FnMut() + 'static) -> Self {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+
+    fn end(&self) -> &V {
+        self.steps.last().map_or(&self.start, |step| &step.value)
+    }
+
+    /// Length of one repetition in seconds
+    pub fn duration(&self) -> f32 {
+        self.steps.iter().map(|step| step.duration).sum()
+    }
+
+    fn repetitions(&self) -> Option<u32> {
+        match self.repeat {
+            Repeat::Once => Some(1),
+            Repeat::Times(times) => Some(times.max(1)),
+            Repeat::Forever => None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Starts over, the completion callback fires again at the end
+    pub fn reset(&mut self) {
+        self.elapsed = 0.;
+        self.finished = false;
+    }
+
+    /// Advances by `dt` seconds and returns the new value
+    pub fn update(&mut self, dt: f32) -> V {
+        if !self.finished {
+            self.elapsed += dt;
+            let duration = self.duration();
+            match self.repetitions() {
+                Some(times) if self.elapsed >= duration * times as f32 => {
+                    self.elapsed = duration * times as f32;
+                    self.finished = true;
+                    if let Some(on_complete) = &mut self.on_complete {
+                        on_complete();
+                    }
+                }
+                // Two repetitions keep the direction of yoyos
+                None if duration > 0. => self.elapsed %= 2. * duration,
+                _ => {}
+            }
+        }
+        self.value()
+    }
+
+    pub fn value(&self) -> V {
+        let duration = self.duration();
+        if duration <= 0. {
+            return self.end().clone();
+        }
+
+        let (repetition, mut time) = match self.repetitions() {
+            // Counted rather than divided out, rounding could land on the previous repetition
+            Some(times) if self.finished => (times - 1, duration),
+            _ => {
+                let repetition = (self.elapsed / duration).floor();
+                (repetition as u32, self.elapsed - repetition * duration)
+            }
+        };
+        if self.yoyo && repetition % 2 == 1 {
+            time = duration - time;
+        }
+
+        let mut from = &self.start;
+        for step in &self.steps {
+            if time <= step.duration {
+                let t = match step.duration {
+                    duration if duration > 0. => time / duration,
+                    _ => 1.,
+                };
+                return from.lerp(&step.value, step.ease.apply(t));
+            }
+            time -= step.duration;
+            from = &step.value;
+        }
+        self.end().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn easing_endpoints() {
+        for ease in Ease::iter() {
+            assert!(close(ease.apply(0.), 0.), "{ease:?}");
+            assert!(close(ease.apply(1.), 1.), "{ease:?}");
+            assert!(
+                close(ease.apply(-1.), 0.) && close(ease.apply(2.), 1.),
+                "{ease:?}"
+            );
+        }
+        assert!(close(Ease::QuadIn.apply(0.5), 0.25));
+        assert!(Ease::BackOut.apply(0.5) > 1.);
+    }
+
+    #[test]
+    fn steps() {
+        let mut tween = Tween::new(0.)
+            .to(2., 1., Ease::Linear)
+            .wait(1.)
+            .to(0., 2., Ease::Linear);
+        assert!(close(tween.duration(), 4.));
+        assert!(close(tween.update(0.5), 1.));
+        assert!(close(tween.update(1.), 2.));
+        assert!(close(tween.update(1.5), 1.));
+        assert!(close(tween.update(5.), 0.));
+        assert!(tween.is_finished());
+    }
+
+    #[test]
+    fn repeat() {
+        let mut tween = Tween::new(0.)
+            .to(1., 1., Ease::Linear)
+            .set_repeat(Repeat::Times(3));
+        assert!(close(tween.update(1.25), 0.25));
+        assert!(close(tween.update(1.5), 0.75));
+        assert!(!tween.is_finished());
+        assert!(close(tween.update(10.), 1.));
+        assert!(tween.is_finished());
+
+        let mut tween = Tween::new(0.)
+            .to(1., 1., Ease::Linear)
+            .set_repeat(Repeat::Forever);
+        assert!(close(tween.update(100.25), 0.25));
+        assert!(!tween.is_finished());
+    }
+
+    #[test]
+    fn yoyo() {
+        let tween = |times| {
+            Tween::new(0.)
+                .to(1., 1., Ease::Linear)
+                .set_repeat(Repeat::Times(times))
+                .set_yoyo(true)
+        };
+        let mut even = tween(2);
+        assert!(close(even.update(0.75), 0.75));
+        assert!(close(even.update(0.5), 0.75));
+        assert!(close(even.update(0.5), 0.25));
+        // Overshooting the end lands on the end of the last repetition
+        assert!(close(even.update(0.5), 0.));
+        assert!(even.is_finished());
+
+        let mut odd = tween(3);
+        assert!(close(odd.update(3.), 1.));
+
+        // 3 * 0.45 / 0.45 rounds below 3 in f32
+        let mut rounding = Tween::new(0.)
+            .to(1., 0.45, Ease::Linear)
+            .set_repeat(Repeat::Times(3))
+            .set_yoyo(true);
+        assert!(close(rounding.update(2.), 1.));
+
+        let mut forever = tween(1).set_repeat(Repeat::Forever);
+        assert!(close(forever.update(10.25), 0.25));
+        assert!(close(forever.update(1.), 0.75));
+    }
+
+    #[test]
+    fn on_complete_fires_once() {
+        let completions = Rc::new(Cell::new(0));
+        let counter = completions.clone();
+        let mut tween = Tween::new(0.)
+            .to(1., 1., Ease::Linear)
+            .set_repeat(Repeat::Times(2))
+            .on_complete(move || counter.set(counter.get() + 1));
+
+        tween.update(1.5);
+        assert_eq!(completions.get(), 0);
+        tween.update(1.);
+        tween.update(1.);
+        tween.update(5.);
+        assert_eq!(completions.get(), 1);
+
+        tween.reset();
+        tween.update(2.);
+        assert_eq!(completions.get(), 2);
+
+        let forever = Rc::new(Cell::new(0));
+        let counter = forever.clone();
+        let mut tween = Tween::new(0.)
+            .to(1., 1., Ease::Linear)
+            .set_repeat(Repeat::Forever)
+            .on_complete(move || counter.set(counter.get() + 1));
+        tween.update(100.);
+        assert_eq!(forever.get(), 0);
+    }
+}
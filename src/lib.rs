@@ -14,7 +14,18 @@ use winit::{
 use wasm_bindgen::prelude::*;
 
 mod graphics;
-pub use graphics::{Color, GTransform, Geometry, Graphics, Shape, Textures};
+pub use graphics::{
+    particles, scene, tilemap, Aabb, Color, Ease, FillRule, GTransform, Geometry, Gradient,
+    Graphics, Interpolation, Key, Lerp, LineCap, LineJoin, Path, Polyline, Pose, Ramp, RayHit,
+    Repeat, Segments, Shape, StrokeAlignment, TextureInfo, Textures, Timeline, Track,
+    TriangulationError, Tween,
+};
+
+#[doc(hidden)]
+pub use strum;
+
+pub mod collision;
+pub mod physics;
 
 pub trait App<T: Textures> {
     fn new(window: Window) -> Self;
@@ -59,10 +70,8 @@ pub fn run<T: Textures, A: App<T> + 'static>() {
             .expect("Couldn't append canvas to document body.");
     }
 
-
     let mut app = A::new(window);
 
-
     let mut last_update = now();
 
     event_loop.run(move |event, _, control_flow| {
@@ -146,12 +155,22 @@ pub fn now() -> time::Duration {
 }
 
 pub mod prelude {
-    pub use crate::{App, Color, GTransform, Geometry, Graphics, Shape, Textures};
+    pub use crate::collision::{Collider, Contact, Impact, SpatialHash};
+    pub use crate::particles::ParticleEmitter;
+    pub use crate::physics::{BodyHandle, BodyKind, Joint, PhysicsWorld, RigidBody};
+    pub use crate::scene::{Node, NodeHandle, Scene};
+    pub use crate::texture_set;
+    pub use crate::tilemap::{Tile, TileLayer, Tilemap, Tileset};
+    pub use crate::{
+        Aabb, App, Color, Ease, FillRule, GTransform, Geometry, Gradient, Graphics, Interpolation,
+        Lerp, LineCap, LineJoin, Path, Polyline, Ramp, Repeat, Segments, Shape, StrokeAlignment,
+        Textures, Timeline, Track, Tween,
+    };
     pub use async_trait::async_trait;
     pub use egui;
     pub use egui_file;
+    pub use ellipsoid_derive::Textures;
     pub use glam::{self, vec2, Vec2};
     pub use image::ImageFormat;
     pub use winit::{self, event::WindowEvent};
-    pub use ellipsoid_derive::Textures;
 }
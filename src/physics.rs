@@ -0,0 +1,854 @@
+use std::collections::HashMap;
+
+use glam::{Mat2, Vec2};
+
+use crate::collision::{Collider, Contact, SpatialHash};
+use crate::GTransform;
+
+/// Velocity iterations of the solver per step
+const ITERATIONS: usize = 8;
+/// Penetration which is left uncorrected, avoids jitter of resting bodies
+const SLOP: f32 = 0.005;
+/// Fraction of the penetration and joint error corrected per step
+const BAUMGARTE: f32 = 0.2;
+/// Approach speed below which bodies don't bounce
+const RESTITUTION_THRESHOLD: f32 = 0.05;
+/// Steps done by one call to `update` at most, so slow frames can't pile up work
+const MAX_STEPS: usize = 8;
+/// Contact points moving less than this between steps start from their previous impulses
+const WARM_START_DISTANCE: f32 = 0.02;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BodyKind {
+    /// Moved by forces, gravity and collisions
+    #[default]
+    Dynamic,
+    /// Moved only by its velocity, pushes dynamic bodies as if infinitely heavy
+    Kinematic,
+    /// Never moves
+    Static,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BodyHandle(usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct JointHandle(usize);
+
+/// Body whose pose is a [`GTransform`]. The collider is given in local coordinates and moves
+/// with the transform, scaling included.
+#[derive(Clone, Debug)]
+pub struct RigidBody {
+    pub gtransform: GTransform,
+    /// Velocity of the center of mass
+    pub velocity: Vec2,
+    pub angular_velocity: f32,
+    kind: BodyKind,
+    collider: Collider,
+    density: f32,
+    restitution: f32,
+    friction: f32,
+    gravity_scale: f32,
+    force: Vec2,
+    torque: f32,
+    mass: f32,
+    inertia: f32,
+    /// Center of mass relative to `gtransform.center`, before rotating
+    local_center: Vec2,
+}
+
+/// Mass, center of mass and rotational inertia around it, for a density of 1
+fn mass_properties(collider: &Collider) -> (f32, Vec2, f32) {
+    let (mass, moment, origin_inertia) = match collider {
+        Collider::Circle { center, radius } => {
+            let mass = std::f32::consts::PI * radius * radius;
+            let inertia = mass * (radius * radius / 2. + center.length_squared());
+            (mass, *center * mass, inertia)
+        }
+        Collider::Polygons(polygons) => polygons
+            .iter()
+            .flat_map(|polygon| {
+                (1..polygon.len().saturating_sub(1))
+                    .map(|i| (polygon[0], polygon[i], polygon[i + 1]))
+            })
+            .fold(
+                (0., Vec2::ZERO, 0.),
+                |(mass, moment, inertia), (a, b, c)| {
+                    let area = (b - a).perp_dot(c - a) / 2.;
+                    // Inertia of the triangle around its first corner, moved to the origin
+                    let (e1, e2) = (b - a, c - a);
+                    let corner = area / 6. * (e1.dot(e1) + e1.dot(e2) + e2.dot(e2));
+                    let centroid = (a + b + c) / 3.;
+                    let inertia_at_origin = corner
+                        + area * (centroid.length_squared() - (centroid - a).length_squared());
+                    (
+                        mass + area,
+                        moment + centroid * area,
+                        inertia + inertia_at_origin,
+                    )
+                },
+            ),
+    };
+    if mass <= 0. {
+        return (0., Vec2::ZERO, 0.);
+    }
+    let center = moment / mass;
+    (
+        mass,
+        center,
+        origin_inertia - mass * center.length_squared(),
+    )
+}
+
+impl RigidBody {
+    pub fn new(kind: BodyKind, collider: Collider) -> Self {
+        Self {
+            gtransform: GTransform::default(),
+            velocity: Vec2::ZERO,
+            angular_velocity: 0.,
+            kind,
+            collider,
+            density: 1.,
+            restitution: 0.,
+            friction: 0.5,
+            gravity_scale: 1.,
+            force: Vec2::ZERO,
+            torque: 0.,
+            mass: 0.,
+            inertia: 0.,
+            local_center: Vec2::ZERO,
+        }
+    }
+
+    pub fn dynamic(collider: Collider) -> Self {
+        Self::new(BodyKind::Dynamic, collider)
+    }
+
+    pub fn kinematic(collider: Collider) -> Self {
+        Self::new(BodyKind::Kinematic, collider)
+    }
+
+    pub fn fixed(collider: Collider) -> Self {
+        Self::new(BodyKind::Static, collider)
+    }
+
+    pub fn set_gtransform(mut self, gtransform: GTransform) -> Self {
+        self.gtransform = gtransform;
+        self
+    }
+
+    pub fn set_velocity(mut self, velocity: Vec2) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn set_angular_velocity(mut self, angular_velocity: f32) -> Self {
+        self.angular_velocity = angular_velocity;
+        self
+    }
+
+    /// Mass per unit of area
+    pub fn set_density(mut self, density: f32) -> Self {
+        self.density = density;
+        self
+    }
+
+    /// Bounciness from 0 to 1, the larger value of two touching bodies is used
+    pub fn set_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    /// Coulomb friction coefficient, combined as the geometric mean of two touching bodies
+    pub fn set_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    pub fn set_gravity_scale(mut self, gravity_scale: f32) -> Self {
+        self.gravity_scale = gravity_scale;
+        self
+    }
+
+    pub fn kind(&self) -> BodyKind {
+        self.kind
+    }
+
+    pub fn collider(&self) -> &Collider {
+        &self.collider
+    }
+
+    /// Zero for bodies which aren't dynamic
+    pub fn mass(&self) -> f32 {
+        self.mass
+    }
+
+    /// Collider in world coordinates
+    pub fn world_collider(&self) -> Collider {
+        self.collider.clone().apply(self.gtransform)
+    }
+
+    /// Center of mass in world coordinates
+    pub fn center_of_mass(&self) -> Vec2 {
+        self.gtransform.center
+            + Vec2::from_angle(self.gtransform.rotation).rotate(self.local_center)
+    }
+
+    /// Recomputes mass and inertia, needed after changing the scale of `gtransform`
+    pub fn update_mass(&mut self) {
+        let scaled = self
+            .collider
+            .clone()
+            .apply(GTransform::from_scale(self.gtransform.scale));
+        let (area, center, inertia) = mass_properties(&scaled);
+        self.local_center = center;
+        (self.mass, self.inertia) = match self.kind {
+            BodyKind::Dynamic => (area * self.density, inertia * self.density),
+            BodyKind::Kinematic | BodyKind::Static => (0., 0.),
+        };
+    }
+
+    fn inverse_mass(&self) -> f32 {
+        match self.mass > 0. {
+            true => 1. / self.mass,
+            false => 0.,
+        }
+    }
+
+    fn inverse_inertia(&self) -> f32 {
+        match self.inertia > 0. {
+            true => 1. / self.inertia,
+            false => 0.,
+        }
+    }
+
+    /// Force applied during the next step
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.force += force;
+    }
+
+    pub fn apply_torque(&mut self, torque: f32) {
+        self.torque += torque;
+    }
+
+    /// Instant change of momentum at the world coordinates `point`
+    pub fn apply_impulse(&mut self, impulse: Vec2, point: Vec2) {
+        self.velocity += impulse * self.inverse_mass();
+        self.angular_velocity +=
+            (point - self.center_of_mass()).perp_dot(impulse) * self.inverse_inertia();
+    }
+
+    fn velocity_at(&self, offset: Vec2) -> Vec2 {
+        self.velocity + offset.perp() * self.angular_velocity
+    }
+}
+
+/// Constraint between two bodies, anchors are in the local coordinates of their body.
+/// Joined bodies don't collide with each other.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Joint {
+    /// Keeps the anchors at a fixed distance, like a rod
+    Distance {
+        a: BodyHandle,
+        b: BodyHandle,
+        anchor_a: Vec2,
+        anchor_b: Vec2,
+        length: f32,
+    },
+    /// Pins the anchors together, the bodies can rotate around them freely
+    Revolute {
+        a: BodyHandle,
+        b: BodyHandle,
+        anchor_a: Vec2,
+        anchor_b: Vec2,
+    },
+}
+
+/// Contact point prepared for the solver
+#[derive(Clone, Debug)]
+struct ContactConstraint {
+    a: usize,
+    b: usize,
+    point: Vec2,
+    normal: Vec2,
+    offset_a: Vec2,
+    offset_b: Vec2,
+    normal_mass: f32,
+    tangent_mass: f32,
+    bias: f32,
+    friction: f32,
+    normal_impulse: f32,
+    tangent_impulse: f32,
+}
+
+/// Effective mass of the bodies against an impulse along `direction`
+fn effective_mass(
+    a: &RigidBody,
+    b: &RigidBody,
+    offset_a: Vec2,
+    offset_b: Vec2,
+    direction: Vec2,
+) -> f32 {
+    let (ra, rb) = (offset_a.perp_dot(direction), offset_b.perp_dot(direction));
+    let k = a.inverse_mass()
+        + b.inverse_mass()
+        + a.inverse_inertia() * ra * ra
+        + b.inverse_inertia() * rb * rb;
+    match k > 0. {
+        true => 1. / k,
+        false => 0.,
+    }
+}
+
+/// Two bodies of the slice at once
+fn pair(
+    bodies: &mut [Option<RigidBody>],
+    a: usize,
+    b: usize,
+) -> Option<(&mut RigidBody, &mut RigidBody)> {
+    if a == b {
+        return None;
+    }
+    let (low, high) = bodies.split_at_mut(a.max(b));
+    let (low, high) = (low[a.min(b)].as_mut()?, high[0].as_mut()?);
+    Some(match a < b {
+        true => (low, high),
+        false => (high, low),
+    })
+}
+
+fn apply_pair_impulse(
+    a: &mut RigidBody,
+    b: &mut RigidBody,
+    offset_a: Vec2,
+    offset_b: Vec2,
+    impulse: Vec2,
+) {
+    a.velocity -= impulse * a.inverse_mass();
+    a.angular_velocity -= offset_a.perp_dot(impulse) * a.inverse_inertia();
+    b.velocity += impulse * b.inverse_mass();
+    b.angular_velocity += offset_b.perp_dot(impulse) * b.inverse_inertia();
+}
+
+/// Rigid bodies stepped at a fixed rate with sequential impulses
+#[derive(Clone, Debug)]
+pub struct PhysicsWorld {
+    bodies: Vec<Option<RigidBody>>,
+    joints: Vec<Option<Joint>>,
+    gravity: Vec2,
+    timestep: f32,
+    accumulator: f32,
+    contacts: Vec<(BodyHandle, BodyHandle, Contact)>,
+    /// Solved contacts of the last step
+    constraints: Vec<ContactConstraint>,
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhysicsWorld {
+    pub fn new() -> Self {
+        Self {
+            bodies: vec![],
+            joints: vec![],
+            gravity: Vec2::new(0., -9.81),
+            timestep: 1. / 60.,
+            accumulator: 0.,
+            contacts: vec![],
+            constraints: vec![],
+        }
+    }
+
+    pub fn set_gravity(mut self, gravity: Vec2) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    /// Duration of one step in seconds, panics unless it is positive and finite
+    pub fn set_timestep(mut self, timestep: f32) -> Self {
+        assert!(
+            timestep > 0. && timestep.is_finite(),
+            "timestep has to be positive, got {timestep}"
+        );
+        self.timestep = timestep;
+        self
+    }
+
+    pub fn add_body(&mut self, mut body: RigidBody) -> BodyHandle {
+        body.update_mass();
+        self.bodies.push(Some(body));
+        BodyHandle(self.bodies.len() - 1)
+    }
+
+    /// Joints attached to the body are removed with it
+    pub fn remove_body(&mut self, handle: BodyHandle) -> Option<RigidBody> {
+        for joint in &mut self.joints {
+            if let Some(Joint::Distance { a, b, .. } | Joint::Revolute { a, b, .. }) = joint {
+                if *a == handle || *b == handle {
+                    *joint = None;
+                }
+            }
+        }
+        self.bodies.get_mut(handle.0)?.take()
+    }
+
+    pub fn body(&self, handle: BodyHandle) -> Option<&RigidBody> {
+        self.bodies.get(handle.0)?.as_ref()
+    }
+
+    pub fn body_mut(&mut self, handle: BodyHandle) -> Option<&mut RigidBody> {
+        self.bodies.get_mut(handle.0)?.as_mut()
+    }
+
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyHandle, &RigidBody)> {
+        self.bodies
+            .iter()
+            .enumerate()
+            .filter_map(|(i, body)| Some((BodyHandle(i), body.as_ref()?)))
+    }
+
+    /// Pose of the body for drawing
+    pub fn gtransform(&self, handle: BodyHandle) -> Option<GTransform> {
+        self.body(handle).map(|body| body.gtransform)
+    }
+
+    pub fn add_joint(&mut self, joint: Joint) -> JointHandle {
+        self.joints.push(Some(joint));
+        JointHandle(self.joints.len() - 1)
+    }
+
+    pub fn remove_joint(&mut self, handle: JointHandle) -> Option<Joint> {
+        self.joints.get_mut(handle.0)?.take()
+    }
+
+    /// Contacts found during the last step, normals point from the first body to the second
+    pub fn contacts(&self) -> &[(BodyHandle, BodyHandle, Contact)] {
+        &self.contacts
+    }
+
+    /// Advances the simulation by `dt` in fixed steps, the remainder is carried over to the
+    /// next call
+    pub fn update(&mut self, dt: f32) {
+        self.accumulator += dt;
+        let mut steps = 0;
+        while self.accumulator >= self.timestep && steps < MAX_STEPS {
+            self.step();
+            self.accumulator -= self.timestep;
+            steps += 1;
+        }
+        self.accumulator = self.accumulator.min(self.timestep);
+    }
+
+    /// Advances the simulation by one timestep
+    pub fn step(&mut self) {
+        let dt = self.timestep;
+
+        for body in self.bodies.iter_mut().flatten() {
+            if body.kind == BodyKind::Dynamic {
+                body.velocity +=
+                    (self.gravity * body.gravity_scale + body.force * body.inverse_mass()) * dt;
+                body.angular_velocity += body.torque * body.inverse_inertia() * dt;
+            }
+            body.force = Vec2::ZERO;
+            body.torque = 0.;
+        }
+
+        self.find_contacts();
+        let mut constraints = self.contact_constraints(dt);
+        for constraint in &constraints {
+            if let Some((a, b)) = pair(&mut self.bodies, constraint.a, constraint.b) {
+                let impulse = constraint.normal * constraint.normal_impulse
+                    + constraint.normal.perp() * constraint.tangent_impulse;
+                apply_pair_impulse(a, b, constraint.offset_a, constraint.offset_b, impulse);
+            }
+        }
+        let joints = self.joints.iter().flatten().copied().collect::<Vec<_>>();
+        for _ in 0..ITERATIONS {
+            for joint in &joints {
+                self.solve_joint(*joint, dt);
+            }
+            for constraint in &mut constraints {
+                self.solve_contact(constraint);
+            }
+        }
+        self.constraints = constraints;
+
+        for body in self.bodies.iter_mut().flatten() {
+            if body.kind == BodyKind::Static {
+                continue;
+            }
+            let center = body.center_of_mass() + body.velocity * dt;
+            body.gtransform.rotation += body.angular_velocity * dt;
+            body.gtransform.center =
+                center - Vec2::from_angle(body.gtransform.rotation).rotate(body.local_center);
+        }
+    }
+
+    fn joined(&self, a: BodyHandle, b: BodyHandle) -> bool {
+        self.joints.iter().flatten().any(|joint| {
+            let (Joint::Distance { a: c, b: d, .. } | Joint::Revolute { a: c, b: d, .. }) = joint;
+            (a, b) == (*c, *d) || (a, b) == (*d, *c)
+        })
+    }
+
+    /// Tests every pair of bodies with overlapping bounds of which at least one is dynamic and
+    /// which aren't joined
+    fn find_contacts(&mut self) {
+        let colliders = self
+            .bodies
+            .iter()
+            .map(|body| {
+                let body = body.as_ref()?;
+                let collider = body.world_collider();
+                Some((body.kind, collider.aabb()?, collider))
+            })
+            .collect::<Vec<_>>();
+
+        // Cells around twice the average body size keep most bodies in few cells
+        let sizes = colliders
+            .iter()
+            .flatten()
+            .map(|(_, aabb, _)| aabb.size().max_element());
+        let (total, count) = sizes.fold((0., 0), |(total, count), size| (total + size, count + 1));
+        let mut broadphase = SpatialHash::new((2. * total / count.max(1) as f32).max(f32::EPSILON));
+        for (i, collider) in colliders.iter().enumerate() {
+            if let Some((_, aabb, _)) = collider {
+                broadphase.insert(i, *aabb);
+            }
+        }
+        // Sorted, so that the solver visits contacts in the same order every run
+        let mut pairs = broadphase
+            .pairs()
+            .into_iter()
+            .map(|(i, j)| (i.min(j), i.max(j)))
+            .collect::<Vec<_>>();
+        pairs.sort_unstable();
+
+        self.contacts.clear();
+        for (i, j) in pairs {
+            let (Some((kind_a, _, collider_a)), Some((kind_b, _, collider_b))) =
+                (&colliders[i], &colliders[j])
+            else {
+                continue;
+            };
+            if (*kind_a != BodyKind::Dynamic && *kind_b != BodyKind::Dynamic)
+                || self.joined(BodyHandle(i), BodyHandle(j))
+            {
+                continue;
+            }
+            for contact in collider_a.contacts(collider_b) {
+                self.contacts.push((BodyHandle(i), BodyHandle(j), contact));
+            }
+        }
+    }
+
+    fn contact_constraints(&self, dt: f32) -> Vec<ContactConstraint> {
+        let mut previous: HashMap<(usize, usize), Vec<&ContactConstraint>> = HashMap::new();
+        for old in &self.constraints {
+            previous.entry((old.a, old.b)).or_default().push(old);
+        }
+
+        let mut constraints = vec![];
+        for (BodyHandle(a), BodyHandle(b), contact) in &self.contacts {
+            let (Some(body_a), Some(body_b)) = (&self.bodies[*a], &self.bodies[*b]) else {
+                continue;
+            };
+            let tangent = contact.normal.perp();
+            for (point, depth) in &contact.points {
+                let offset_a = *point - body_a.center_of_mass();
+                let offset_b = *point - body_b.center_of_mass();
+                let approach = (body_b.velocity_at(offset_b) - body_a.velocity_at(offset_a))
+                    .dot(contact.normal);
+
+                let restitution = body_a.restitution.max(body_b.restitution);
+                let bounce = match approach < -RESTITUTION_THRESHOLD {
+                    true => -restitution * approach,
+                    false => 0.,
+                };
+                let correction = BAUMGARTE / dt * (depth - SLOP).max(0.);
+                // Resting contacts converge much faster from the impulses of the last step
+                let (normal_impulse, tangent_impulse) = previous
+                    .get(&(*a, *b))
+                    .into_iter()
+                    .flatten()
+                    .find(|old| {
+                        old.point.distance(*point) < WARM_START_DISTANCE
+                            && old.normal.dot(contact.normal) > 0.95
+                    })
+                    .map_or((0., 0.), |old| (old.normal_impulse, old.tangent_impulse));
+
+                constraints.push(ContactConstraint {
+                    a: *a,
+                    b: *b,
+                    point: *point,
+                    normal: contact.normal,
+                    offset_a,
+                    offset_b,
+                    normal_mass: effective_mass(body_a, body_b, offset_a, offset_b, contact.normal),
+                    tangent_mass: effective_mass(body_a, body_b, offset_a, offset_b, tangent),
+                    bias: bounce.max(correction),
+                    friction: (body_a.friction * body_b.friction).sqrt(),
+                    normal_impulse,
+                    tangent_impulse,
+                });
+            }
+        }
+        constraints
+    }
+
+    fn solve_contact(&mut self, constraint: &mut ContactConstraint) {
+        let Some((a, b)) = pair(&mut self.bodies, constraint.a, constraint.b) else {
+            return;
+        };
+        let (offset_a, offset_b) = (constraint.offset_a, constraint.offset_b);
+        let relative =
+            |a: &RigidBody, b: &RigidBody| b.velocity_at(offset_b) - a.velocity_at(offset_a);
+
+        // Accumulated impulses are clamped, so that later iterations can take back pushes
+        let speed = relative(a, b).dot(constraint.normal);
+        let impulse = constraint.normal_mass * (constraint.bias - speed);
+        let total = (constraint.normal_impulse + impulse).max(0.);
+        let impulse = total - constraint.normal_impulse;
+        constraint.normal_impulse = total;
+        apply_pair_impulse(a, b, offset_a, offset_b, constraint.normal * impulse);
+
+        let tangent = constraint.normal.perp();
+        let speed = relative(a, b).dot(tangent);
+        let limit = constraint.friction * constraint.normal_impulse;
+        let total =
+            (constraint.tangent_impulse - constraint.tangent_mass * speed).clamp(-limit, limit);
+        let impulse = total - constraint.tangent_impulse;
+        constraint.tangent_impulse = total;
+        apply_pair_impulse(a, b, offset_a, offset_b, tangent * impulse);
+    }
+
+    fn solve_joint(&mut self, joint: Joint, dt: f32) {
+        let (Joint::Distance {
+            a,
+            b,
+            anchor_a,
+            anchor_b,
+            ..
+        }
+        | Joint::Revolute {
+            a,
+            b,
+            anchor_a,
+            anchor_b,
+        }) = joint;
+        let Some((body_a, body_b)) = pair(&mut self.bodies, a.0, b.0) else {
+            return;
+        };
+
+        let world_anchor = |body: &RigidBody, anchor: Vec2| body.gtransform.transform(anchor);
+        let (point_a, point_b) = (
+            world_anchor(body_a, anchor_a),
+            world_anchor(body_b, anchor_b),
+        );
+        let offset_a = point_a - body_a.center_of_mass();
+        let offset_b = point_b - body_b.center_of_mass();
+        let relative = body_b.velocity_at(offset_b) - body_a.velocity_at(offset_a);
+
+        let impulse = match joint {
+            Joint::Distance { length, .. } => {
+                let Some(direction) = (point_b - point_a).try_normalize() else {
+                    return;
+                };
+                let error = point_b.distance(point_a) - length;
+                let mass = effective_mass(body_a, body_b, offset_a, offset_b, direction);
+                direction * -mass * (relative.dot(direction) + BAUMGARTE / dt * error)
+            }
+            Joint::Revolute { .. } => {
+                let (inverse_mass, inverse_inertia_a, inverse_inertia_b) = (
+                    body_a.inverse_mass() + body_b.inverse_mass(),
+                    body_a.inverse_inertia(),
+                    body_b.inverse_inertia(),
+                );
+                let (ra, rb) = (offset_a, offset_b);
+                let k = Mat2::from_cols_array(&[
+                    inverse_mass
+                        + inverse_inertia_a * ra.y * ra.y
+                        + inverse_inertia_b * rb.y * rb.y,
+                    -inverse_inertia_a * ra.x * ra.y - inverse_inertia_b * rb.x * rb.y,
+                    -inverse_inertia_a * ra.x * ra.y - inverse_inertia_b * rb.x * rb.y,
+                    inverse_mass
+                        + inverse_inertia_a * ra.x * ra.x
+                        + inverse_inertia_b * rb.x * rb.x,
+                ]);
+                if k.determinant() == 0. {
+                    return;
+                }
+                -(k.inverse() * (relative + BAUMGARTE / dt * (point_b - point_a)))
+            }
+        };
+        apply_pair_impulse(body_a, body_b, offset_a, offset_b, impulse);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use super::*;
+
+    fn rect(min: Vec2, max: Vec2) -> Collider {
+        Collider::convex(vec![min, vec2(max.x, min.y), max, vec2(min.x, max.y)])
+    }
+
+    /// World with a static floor whose top is at y = 0
+    fn world_with_floor(floor: RigidBody) -> PhysicsWorld {
+        let mut world = PhysicsWorld::new();
+        world.add_body(floor);
+        world
+    }
+
+    fn floor() -> RigidBody {
+        RigidBody::fixed(rect(vec2(-50., -1.), vec2(50., 0.)))
+    }
+
+    fn steps(world: &mut PhysicsWorld, seconds: f32) {
+        for _ in 0..(seconds * 60.) as usize {
+            world.step();
+        }
+    }
+
+    #[test]
+    fn boxes_settle_on_the_floor() {
+        let mut world = world_with_floor(floor());
+        let unit = rect(Vec2::splat(-0.5), Vec2::splat(0.5));
+        let body = world.add_body(
+            RigidBody::dynamic(unit).set_gtransform(GTransform::from_translation(vec2(0., 2.))),
+        );
+        steps(&mut world, 4.);
+
+        let body = world.body(body).unwrap();
+        assert!(body.velocity.length() < 0.01, "{body:?}");
+        assert!(body.angular_velocity.abs() < 0.01, "{body:?}");
+        assert!(
+            (body.gtransform.center.y - 0.5).abs() < 2. * SLOP,
+            "{body:?}"
+        );
+        assert!(body.gtransform.center.x.abs() < 1e-3, "{body:?}");
+        assert!(body.gtransform.rotation.abs() < 1e-3, "{body:?}");
+        assert!(!world.contacts().is_empty());
+    }
+
+    /// Highest center of a ball dropped from y = 3 after it first touched the floor
+    fn bounce_height(restitution: f32) -> f32 {
+        let mut world = world_with_floor(floor());
+        let ball = world.add_body(
+            RigidBody::dynamic(Collider::circle(Vec2::ZERO, 0.5))
+                .set_restitution(restitution)
+                .set_gtransform(GTransform::from_translation(vec2(0., 3.))),
+        );
+        let mut bounced = false;
+        let mut height = 0f32;
+        for _ in 0..180 {
+            world.step();
+            let body = world.body(ball).unwrap();
+            bounced |= !world.contacts().is_empty();
+            if bounced {
+                height = height.max(body.gtransform.center.y);
+            }
+        }
+        height
+    }
+
+    #[test]
+    fn restitution_bounces() {
+        // Without losses the ball would come back up to 0.5 + 2.5 * restitution²
+        let height = bounce_height(0.8);
+        assert!((1.8..2.3).contains(&height), "{height}");
+        assert!(bounce_height(0.) < 0.55);
+    }
+
+    /// Distance a box slides after being pushed at 5 m/s
+    fn slide(friction: f32) -> (f32, RigidBody) {
+        let mut world = world_with_floor(floor().set_friction(friction));
+        let unit = rect(Vec2::splat(-0.5), Vec2::splat(0.5));
+        let body = world.add_body(
+            RigidBody::dynamic(unit)
+                .set_friction(friction)
+                .set_velocity(vec2(5., 0.))
+                .set_gtransform(GTransform::from_translation(vec2(0., 0.5))),
+        );
+        steps(&mut world, 3.);
+        let body = world.body(body).unwrap().clone();
+        (body.gtransform.center.x, body)
+    }
+
+    #[test]
+    fn friction_stops_sliding() {
+        let (distance, body) = slide(0.5);
+        assert!(body.velocity.length() < 0.01, "{body:?}");
+        // v² / (2 μ g)
+        let expected = 25. / (2. * 0.5 * 9.81);
+        assert!((distance - expected).abs() < 0.3, "{distance}");
+
+        let (distance, body) = slide(0.);
+        assert!((body.velocity.x - 5.).abs() < 0.01, "{body:?}");
+        assert!(distance > 14.5, "{distance}");
+    }
+
+    #[test]
+    fn distance_joints_hold() {
+        let mut world = PhysicsWorld::new();
+        let anchor = world.add_body(RigidBody::fixed(Collider::circle(Vec2::ZERO, 0.1)));
+        let ball = world.add_body(
+            RigidBody::dynamic(Collider::circle(Vec2::ZERO, 0.2))
+                .set_gtransform(GTransform::from_translation(vec2(2., 0.))),
+        );
+        world.add_joint(Joint::Distance {
+            a: anchor,
+            b: ball,
+            anchor_a: Vec2::ZERO,
+            anchor_b: Vec2::ZERO,
+            length: 2.,
+        });
+
+        let mut lowest = 0f32;
+        for _ in 0..180 {
+            world.step();
+            let center = world.body(ball).unwrap().gtransform.center;
+            assert!((center.length() - 2.).abs() < 0.05, "{center}");
+            lowest = lowest.min(center.y);
+        }
+        // The pendulum swung through its lowest point
+        assert!(lowest < -1.9, "{lowest}");
+    }
+
+    #[test]
+    fn revolute_joints_hold() {
+        let mut world = PhysicsWorld::new();
+        let pin = world.add_body(RigidBody::fixed(Collider::circle(Vec2::ZERO, 0.1)));
+        let bar = world.add_body(
+            RigidBody::dynamic(rect(vec2(-1., -0.1), vec2(1., 0.1)))
+                .set_gtransform(GTransform::from_translation(vec2(1., 0.))),
+        );
+        world.add_joint(Joint::Revolute {
+            a: pin,
+            b: bar,
+            anchor_a: Vec2::ZERO,
+            anchor_b: vec2(-1., 0.),
+        });
+
+        for _ in 0..180 {
+            world.step();
+            let body = world.body(bar).unwrap();
+            let pinned = body.gtransform.transform(vec2(-1., 0.));
+            assert!(pinned.length() < 0.05, "{pinned}");
+        }
+        // The bar swings around the pin instead of falling
+        let rotation = world.body(bar).unwrap().gtransform.rotation;
+        assert!(rotation.abs() > 0.1, "{rotation}");
+    }
+
+    #[test]
+    #[should_panic(expected = "timestep has to be positive")]
+    fn zero_timestep() {
+        let _ = PhysicsWorld::new().set_timestep(0.);
+    }
+}
@@ -4,14 +4,16 @@ struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) tex_index: u32,
     @location(2) tex_coord: vec2<f32>,
-    @location(3) color: vec4<f32>
+    @location(3) color: vec4<f32>,
+    @location(4) tex_rect: vec4<f32>
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_index: u32,
     @location(1) tex_coord: vec2<f32>,
-    @location(2) color: vec4<f32>
+    @location(2) color: vec4<f32>,
+    @location(3) tex_rect: vec4<f32>
 }
 
 @vertex
@@ -23,6 +25,7 @@ fn vs_main(
     out.tex_coord = model.tex_coord;
     out.tex_index = model.tex_index;
     out.color = model.color;
+    out.tex_rect = model.tex_rect;
     return out;
 }
 
@@ -31,13 +34,33 @@ var texture_array: binding_array<texture_2d<f32>>;
 @group(0) @binding(1)
 var text_sampler: sampler;
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    var color: vec4<f32>;
-    color = textureSample(
+fn shade(in: VertexOutput) -> vec4<f32> {
+    // Coordinates outside of 0 to 1 repeat the texture rect
+    let inside = in.tex_coord >= vec2<f32>(0.0) & in.tex_coord <= vec2<f32>(1.0);
+    let local = select(fract(in.tex_coord), in.tex_coord, inside);
+
+    // Textures are sampled and vertex colours uploaded in linear light
+    return textureSample(
         texture_array[in.tex_index],
         text_sampler,
-        in.tex_coord,
+        mix(in.tex_rect.xy, in.tex_rect.zw, local),
     ) * in.color;
-    return color;
-}
\ No newline at end of file
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let c = max(color, vec3<f32>(0.0));
+    return select(1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055, c * 12.92, c <= vec3<f32>(0.0031308));
+}
+
+// For sRGB surfaces, which encode on write
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(in);
+}
+
+// For surfaces without an sRGB format, which store the output as is
+@fragment
+fn fs_main_srgb(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = shade(in);
+    return vec4<f32>(linear_to_srgb(color.rgb), color.a);
+}